@@ -0,0 +1,776 @@
+use crate::extraction::{DocumentType, docx};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek};
+
+pub const DEFAULT_MAX_ENTRIES: usize = 1000;
+pub const DEFAULT_MAX_ENTRY_BYTES: u64 = 25 * 1024 * 1024;
+pub const DEFAULT_MAX_TOTAL_BYTES: u64 = 1024 * 1024 * 1024;
+pub const DEFAULT_MAX_COMPRESSION_RATIO: u64 = 100;
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+/// Entries smaller than this skip the ratio check; a short text file of
+/// repeated characters compresses extremely well without being dangerous.
+const RATIO_CHECK_MIN_BYTES: u64 = 1024 * 1024;
+/// Longest path component kept by `sanitize_entry_name`, in bytes.
+const MAX_COMPONENT_BYTES: usize = 200;
+const SEVEN_ZIP_MAGIC: &[u8] = b"7z\xBC\xAF\x27\x1C";
+
+/// Bounds a batch archive must stay within. Entry count and sizes add up across
+/// nested archives, so an archive of archives cannot multiply its way past them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchiveLimits {
+    pub max_entries: usize,
+    pub max_entry_bytes: u64,
+    pub max_total_bytes: u64,
+    /// Largest allowed uncompressed-to-compressed size ratio of a single ZIP entry.
+    pub max_compression_ratio: u64,
+    /// How many levels of archives inside archives are extracted; 0 extracts none.
+    pub max_depth: usize,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_entry_bytes: DEFAULT_MAX_ENTRY_BYTES,
+            max_total_bytes: DEFAULT_MAX_TOTAL_BYTES,
+            max_compression_ratio: DEFAULT_MAX_COMPRESSION_RATIO,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    SevenZip,
+}
+
+impl ArchiveFormat {
+    /// Detects the format from the first bytes of a file. Gzip data is assumed to
+    /// be a tarball.
+    pub fn detect(head: &[u8]) -> Option<Self> {
+        if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if head.starts_with(b"\x1f\x8b") {
+            Some(Self::TarGz)
+        } else if head.starts_with(SEVEN_ZIP_MAGIC) {
+            Some(Self::SevenZip)
+        } else if head.get(257..262) == Some(b"ustar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Zip => "ZIP",
+            Self::Tar => "tar",
+            Self::TarGz => "tar.gz",
+            Self::SevenZip => "7z",
+        }
+    }
+}
+
+/// A file found while walking an archive. Names are the raw paths from the
+/// archive, prefixed with the path of the archive they were nested in, and still
+/// need `sanitize_entry_name`.
+#[derive(Debug)]
+pub enum ArchiveItem {
+    /// A file spooled to an anonymous temporary file; `document_type` is `None` if
+    /// it is not a supported resume format.
+    File {
+        name: String,
+        size: u64,
+        document_type: Option<DocumentType>,
+        file: File,
+    },
+    /// A nested archive beyond `max_depth`, left unextracted.
+    Skipped { name: String, size: u64, reason: String },
+    /// An entry, or a nested archive, that could not be read.
+    Failed { name: String, size: u64, error: String },
+}
+
+/// Why an archive was rejected. The message is stored on the `zip_archives` row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitExceeded(pub String);
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Archive rejected: {}", self.0)
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// Errors that stop extraction of the whole archive.
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// Not a ZIP, tar, tar.gz or 7z file.
+    Unsupported,
+    Limit(LimitExceeded),
+    /// The archive could not be opened, or its stream broke part way through.
+    Corrupt(String),
+    /// The sink stopped accepting items.
+    Closed,
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::Unsupported => write!(f, "Unsupported archive format"),
+            ArchiveError::Limit(e) => write!(f, "{}", e),
+            ArchiveError::Corrupt(e) => write!(f, "Failed to read archive: {}", e),
+            ArchiveError::Closed => write!(f, "Extraction was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+fn corrupt(e: impl fmt::Display) -> ArchiveError {
+    ArchiveError::Corrupt(e.to_string())
+}
+
+/// Walks a ZIP, tar, tar.gz or 7z archive and hands every file in it to `sink`,
+/// descending into nested archives up to `limits.max_depth` levels. DOCX files are
+/// ZIP packages but are passed on as files. `sink` returns `false` to stop. This
+/// blocks, so async callers should run it with `spawn_blocking`.
+pub fn extract<R: Read + Seek>(
+    mut reader: R,
+    limits: &ArchiveLimits,
+    mut sink: impl FnMut(ArchiveItem) -> bool,
+) -> Result<(), ArchiveError> {
+    let head = read_head(&mut reader).map_err(corrupt)?;
+    let format = ArchiveFormat::detect(&head).ok_or(ArchiveError::Unsupported)?;
+
+    let mut walker = Walker {
+        budget: Budget::new(*limits),
+        sink: &mut sink,
+    };
+    walker.walk(format, reader, "", 0)
+}
+
+/// Running totals checked against the limits as entries are admitted.
+struct Budget {
+    limits: ArchiveLimits,
+    entries: usize,
+    bytes: u64,
+}
+
+impl Budget {
+    fn new(limits: ArchiveLimits) -> Self {
+        Self {
+            limits,
+            entries: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Checks the sizes declared in a ZIP's central directory without decompressing
+    /// anything. Headers can lie, so entries must still be read with `read_entry`.
+    fn admit_zip<R: Read + Seek>(
+        &mut self,
+        archive: &mut zip::ZipArchive<R>,
+    ) -> Result<(), LimitExceeded> {
+        let entries = self.entries + archive.len();
+        if entries > self.limits.max_entries {
+            return Err(LimitExceeded(format!(
+                "{} entries exceeds the limit of {}",
+                entries, self.limits.max_entries
+            )));
+        }
+        self.entries = entries;
+
+        for i in 0..archive.len() {
+            // Unreadable entries are reported per member during extraction
+            let Ok(file) = archive.by_index_raw(i) else {
+                continue;
+            };
+            self.admit_bytes(file.name(), file.size(), Some(file.compressed_size()))?;
+        }
+        Ok(())
+    }
+
+    /// Admits one entry of a format without a central directory.
+    fn admit(&mut self, name: &str, size: u64) -> Result<(), LimitExceeded> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(LimitExceeded(format!(
+                "more than {} entries",
+                self.limits.max_entries
+            )));
+        }
+        self.admit_bytes(name, size, None)
+    }
+
+    fn admit_bytes(
+        &mut self,
+        name: &str,
+        size: u64,
+        compressed: Option<u64>,
+    ) -> Result<(), LimitExceeded> {
+        let limits = &self.limits;
+        if size > limits.max_entry_bytes {
+            return Err(LimitExceeded(format!(
+                "entry '{}' is {} bytes, the limit is {}",
+                name, size, limits.max_entry_bytes
+            )));
+        }
+        if let Some(compressed) = compressed
+            && size >= RATIO_CHECK_MIN_BYTES
+            && size > compressed.saturating_mul(limits.max_compression_ratio)
+        {
+            return Err(LimitExceeded(format!(
+                "entry '{}' expands from {} to {} bytes, more than {}x",
+                name, compressed, size, limits.max_compression_ratio
+            )));
+        }
+
+        self.bytes = self.bytes.saturating_add(size);
+        if self.bytes > limits.max_total_bytes {
+            return Err(LimitExceeded(format!(
+                "uncompressed size exceeds the limit of {} bytes",
+                limits.max_total_bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+enum EntryError {
+    Io(std::io::Error),
+    /// The entry decompressed to more bytes than its header declared.
+    Oversized(LimitExceeded),
+}
+
+/// Copies an admitted entry to a temporary file, refusing to decompress past the
+/// size its header declared. Returns the file and the number of bytes written.
+fn spool_entry(
+    reader: impl Read,
+    name: &str,
+    declared_size: u64,
+) -> Result<(File, u64), EntryError> {
+    let mut file = tempfile::tempfile().map_err(EntryError::Io)?;
+    let written = std::io::copy(&mut reader.take(declared_size.saturating_add(1)), &mut file)
+        .map_err(EntryError::Io)?;
+    if written > declared_size {
+        return Err(EntryError::Oversized(LimitExceeded(format!(
+            "entry '{}' is larger than the {} bytes its header declares",
+            name, declared_size
+        ))));
+    }
+    Ok((file, written))
+}
+
+/// Reads the first 512 bytes, enough for every archive signature, and rewinds.
+fn read_head(reader: &mut (impl Read + Seek)) -> std::io::Result<Vec<u8>> {
+    reader.rewind()?;
+    let mut head = Vec::with_capacity(512);
+    reader.by_ref().take(512).read_to_end(&mut head)?;
+    reader.rewind()?;
+    Ok(head)
+}
+
+enum Kind {
+    Archive(ArchiveFormat),
+    Document(Option<DocumentType>),
+}
+
+fn identify(file: &mut File, name: &str) -> std::io::Result<Kind> {
+    match ArchiveFormat::detect(&read_head(file)?) {
+        // DOCX files are ZIP packages too, but they are resumes, not archives
+        Some(ArchiveFormat::Zip) => {
+            let is_docx = docx::is_docx(&mut *file);
+            file.rewind()?;
+            Ok(if is_docx {
+                Kind::Document(Some(DocumentType::Docx))
+            } else {
+                Kind::Archive(ArchiveFormat::Zip)
+            })
+        }
+        Some(format) => Ok(Kind::Archive(format)),
+        None => DocumentType::detect_reader(file, name).map(Kind::Document),
+    }
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+struct Walker<'a> {
+    budget: Budget,
+    sink: &'a mut dyn FnMut(ArchiveItem) -> bool,
+}
+
+impl Walker<'_> {
+    fn send(&mut self, item: ArchiveItem) -> Result<(), ArchiveError> {
+        if (self.sink)(item) {
+            Ok(())
+        } else {
+            Err(ArchiveError::Closed)
+        }
+    }
+
+    fn walk<R: Read + Seek>(
+        &mut self,
+        format: ArchiveFormat,
+        reader: R,
+        prefix: &str,
+        depth: usize,
+    ) -> Result<(), ArchiveError> {
+        match format {
+            ArchiveFormat::Zip => self.walk_zip(reader, prefix, depth),
+            ArchiveFormat::Tar => self.walk_tar(reader, prefix, depth),
+            ArchiveFormat::TarGz => self.walk_tar(GzDecoder::new(reader), prefix, depth),
+            ArchiveFormat::SevenZip => self.walk_7z(reader, prefix, depth),
+        }
+    }
+
+    /// Passes a file on, or extracts it in place if it is itself an archive.
+    fn file(&mut self, name: String, mut file: File, size: u64, depth: usize) -> Result<(), ArchiveError> {
+        let format = match identify(&mut file, &name) {
+            Ok(Kind::Archive(format)) => format,
+            Ok(Kind::Document(document_type)) => {
+                return self.send(ArchiveItem::File {
+                    name,
+                    size,
+                    document_type,
+                    file,
+                });
+            }
+            Err(e) => {
+                return self.send(ArchiveItem::Failed {
+                    name,
+                    size,
+                    error: format!("Failed to read file from archive: {}", e),
+                });
+            }
+        };
+
+        if depth >= self.budget.limits.max_depth {
+            let reason = format!(
+                "Nested archives deeper than {} levels are not extracted",
+                self.budget.limits.max_depth
+            );
+            return self.send(ArchiveItem::Skipped { name, size, reason });
+        }
+        match self.walk(format, file, &name, depth + 1) {
+            Err(ArchiveError::Corrupt(e)) => self.send(ArchiveItem::Failed {
+                name,
+                size,
+                error: format!("Failed to read nested {} archive: {}", format.name(), e),
+            }),
+            result => result,
+        }
+    }
+
+    fn walk_zip<R: Read + Seek>(
+        &mut self,
+        reader: R,
+        prefix: &str,
+        depth: usize,
+    ) -> Result<(), ArchiveError> {
+        let mut archive = zip::ZipArchive::new(reader).map_err(corrupt)?;
+        self.budget
+            .admit_zip(&mut archive)
+            .map_err(ArchiveError::Limit)?;
+
+        for i in 0..archive.len() {
+            // The central directory name is readable even when the entry itself is not
+            let entry_name = archive.name_for_index(i).map(str::to_string);
+            let mut file = match archive.by_index(i) {
+                Ok(f) => f,
+                Err(e) => {
+                    let name = entry_name.unwrap_or_else(|| format!("entry #{}", i));
+                    self.send(ArchiveItem::Failed {
+                        name: join(prefix, &name),
+                        size: 0,
+                        error: format!("Failed to read file from archive: {}", e),
+                    })?;
+                    continue;
+                }
+            };
+            if file.is_dir() {
+                continue;
+            }
+
+            let name = join(prefix, file.name());
+            let size = file.size();
+            match spool_entry(&mut file, &name, size) {
+                Ok((spooled, size)) => {
+                    drop(file);
+                    self.file(name, spooled, size, depth)?;
+                }
+                Err(EntryError::Io(e)) => self.send(ArchiveItem::Failed {
+                    name,
+                    size,
+                    error: format!("Failed to read file from archive: {}", e),
+                })?,
+                Err(EntryError::Oversized(e)) => return Err(ArchiveError::Limit(e)),
+            }
+        }
+        Ok(())
+    }
+
+    fn walk_tar<R: Read>(&mut self, reader: R, prefix: &str, depth: usize) -> Result<(), ArchiveError> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries().map_err(corrupt)? {
+            let mut entry = entry.map_err(corrupt)?;
+            // Directories, links and devices have no content to upload
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let name = join(prefix, &String::from_utf8_lossy(&entry.path_bytes()));
+            let size = entry.size();
+            self.budget.admit(&name, size).map_err(ArchiveError::Limit)?;
+            match spool_entry(&mut entry, &name, size) {
+                Ok((spooled, size)) => self.file(name, spooled, size, depth)?,
+                // Tar has no index, so nothing after a broken entry can be found
+                Err(EntryError::Io(e)) => return Err(corrupt(e)),
+                Err(EntryError::Oversized(e)) => return Err(ArchiveError::Limit(e)),
+            }
+        }
+        Ok(())
+    }
+
+    fn walk_7z<R: Read + Seek>(
+        &mut self,
+        reader: R,
+        prefix: &str,
+        depth: usize,
+    ) -> Result<(), ArchiveError> {
+        let mut archive = sevenz_rust2::ArchiveReader::new(reader, sevenz_rust2::Password::empty())
+            .map_err(corrupt)?;
+
+        // The callback can only return the crate's own error type, so ours wait here
+        let mut stopped = None;
+        let result = archive.for_each_entries(|entry, reader| {
+            if entry.is_directory() {
+                return Ok(true);
+            }
+            let name = join(prefix, entry.name());
+            let size = entry.size();
+            let outcome = match self.budget.admit(&name, size) {
+                Err(e) => Err(ArchiveError::Limit(e)),
+                Ok(()) => match spool_entry(reader, &name, size) {
+                    Ok((spooled, size)) => self.file(name, spooled, size, depth),
+                    Err(EntryError::Io(e)) => Err(corrupt(e)),
+                    Err(EntryError::Oversized(e)) => Err(ArchiveError::Limit(e)),
+                },
+            };
+            match outcome {
+                Ok(()) => Ok(true),
+                Err(e) => {
+                    stopped = Some(e);
+                    Ok(false)
+                }
+            }
+        });
+        match stopped {
+            Some(e) => Err(e),
+            None => result.map_err(corrupt),
+        }
+    }
+}
+
+/// Turns an entry name into a safe object key suffix: backslashes count as
+/// separators, empty, `.` and `..` components and drive prefixes are dropped, and
+/// anything but letters, digits, spaces and `-_.()` is replaced with `_`. Names
+/// the zip reader could not decode as UTF-8 arrive with replacement characters,
+/// which are replaced the same way. Returns `None` if nothing is left.
+pub fn sanitize_entry_name(name: &str) -> Option<String> {
+    let components: Vec<String> = name
+        .split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != "." && *c != "..")
+        .filter(|c| !(c.len() == 2 && c.ends_with(':')))
+        .map(|c| {
+            let mut safe: String = c
+                .chars()
+                .map(|ch| {
+                    if ch.is_alphanumeric() || matches!(ch, ' ' | '-' | '_' | '.' | '(' | ')') {
+                        ch
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            if safe.len() > MAX_COMPONENT_BYTES {
+                let mut end = MAX_COMPONENT_BYTES;
+                while !safe.is_char_boundary(end) {
+                    end -= 1;
+                }
+                safe.truncate(end);
+            }
+            safe.trim().to_string()
+        })
+        .filter(|c| !c.is_empty() && c != "." && c != "..")
+        .collect();
+
+    (!components.is_empty()).then(|| components.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn zip_bytes(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(Cursor::new(&mut buf));
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            for (name, data) in entries {
+                zip.start_file(*name, options).unwrap();
+                zip.write_all(data).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        buf
+    }
+
+    fn zip_with(entries: &[(&str, Vec<u8>)]) -> zip::ZipArchive<Cursor<Vec<u8>>> {
+        zip::ZipArchive::new(Cursor::new(zip_bytes(entries))).unwrap()
+    }
+
+    fn tar_bytes(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, &data[..]).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn seven_zip_bytes(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut writer = sevenz_rust2::ArchiveWriter::new(Cursor::new(Vec::new())).unwrap();
+        for (name, data) in entries {
+            writer
+                .push_archive_entry(
+                    sevenz_rust2::ArchiveEntry::new_file(name),
+                    Some(&data[..]),
+                )
+                .unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// Extracts `data` into `(name, summary)` pairs. Text files are summarized by
+    /// their content, other documents by their type.
+    fn extract_all(
+        data: Vec<u8>,
+        limits: &ArchiveLimits,
+    ) -> Result<Vec<(String, String)>, ArchiveError> {
+        let mut items = Vec::new();
+        extract(Cursor::new(data), limits, |item| {
+            items.push(match item {
+                ArchiveItem::File {
+                    name,
+                    size,
+                    document_type,
+                    mut file,
+                } => {
+                    let mut contents = String::new();
+                    file.rewind().unwrap();
+                    file.read_to_string(&mut contents).unwrap_or_default();
+                    assert_eq!(size, file.metadata().unwrap().len());
+                    let summary = match document_type {
+                        Some(DocumentType::PlainText | DocumentType::Markdown) => contents,
+                        Some(document_type) => document_type.name().to_string(),
+                        None => "unsupported".to_string(),
+                    };
+                    (name, summary)
+                }
+                ArchiveItem::Skipped { name, reason, .. } => (name, format!("skipped: {}", reason)),
+                ArchiveItem::Failed { name, error, .. } => (name, format!("failed: {}", error)),
+            });
+            true
+        })?;
+        Ok(items)
+    }
+
+    fn item(name: &str, summary: &str) -> (String, String) {
+        (name.to_string(), summary.to_string())
+    }
+
+    #[test]
+    fn test_check_rejects_each_limit() {
+        let limits = ArchiveLimits {
+            max_entries: 2,
+            max_entry_bytes: 2 * 1024 * 1024,
+            max_total_bytes: 3 * 1024 * 1024,
+            max_compression_ratio: 100,
+            max_depth: 1,
+        };
+        let check = |mut archive| Budget::new(limits).admit_zip(&mut archive);
+
+        let ok = zip_with(&[("a.txt", b"Alex".to_vec()), ("b.txt", vec![b' '; 1000])]);
+        assert!(check(ok).is_ok());
+
+        let many = zip_with(&[("a", vec![]), ("b", vec![]), ("c", vec![])]);
+        assert!(check(many).unwrap_err().0.contains("3 entries"));
+
+        let big = zip_with(&[("big.txt", vec![b'x'; 3 * 1024 * 1024])]);
+        assert!(check(big).unwrap_err().0.contains("'big.txt' is"));
+
+        // 1.5 MiB of zeros deflates to a few KiB
+        let bomb = zip_with(&[("bomb.txt", vec![0; 1536 * 1024])]);
+        assert!(check(bomb).unwrap_err().0.contains("expands"));
+
+        let random: Vec<u8> = (0..1600 * 1024).map(|_| fastrand::u8(..)).collect();
+        let total = zip_with(&[("a.bin", random.clone()), ("b.bin", random)]);
+        assert!(check(total).unwrap_err().0.contains("uncompressed size"));
+    }
+
+    #[test]
+    fn test_spool_entry_stops_at_declared_size() {
+        let Ok((mut file, 4)) = spool_entry(&b"Alex"[..], "a.txt", 4) else {
+            panic!("expected a 4 byte entry");
+        };
+        let mut contents = String::new();
+        file.rewind().unwrap();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "Alex");
+
+        let Err(EntryError::Oversized(err)) = spool_entry(&b"Alex Rivera"[..], "a.txt", 4) else {
+            panic!("expected an oversized entry");
+        };
+        assert!(err.to_string().contains("larger than the 4 bytes"));
+    }
+
+    #[test]
+    fn test_detect_archive_format() {
+        assert_eq!(ArchiveFormat::detect(&zip_bytes(&[])), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::detect(&tar_bytes(&[("a.txt", vec![])])), Some(ArchiveFormat::Tar));
+        assert_eq!(ArchiveFormat::detect(&gzip(b"")), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::detect(&seven_zip_bytes(&[])), Some(ArchiveFormat::SevenZip));
+        assert_eq!(ArchiveFormat::detect(b"%PDF-1.7"), None);
+        assert!(matches!(
+            extract_all(b"not an archive".to_vec(), &ArchiveLimits::default()),
+            Err(ArchiveError::Unsupported)
+        ));
+    }
+
+    #[test]
+    fn test_extract_each_format() {
+        let entries = [("cv/alex.txt", b"Alex Rivera".to_vec()), ("jordan.md", b"# Jordan".to_vec())];
+        let expected = vec![item("cv/alex.txt", "Alex Rivera"), item("jordan.md", "# Jordan")];
+        let limits = ArchiveLimits::default();
+
+        assert_eq!(extract_all(zip_bytes(&entries), &limits).unwrap(), expected);
+        assert_eq!(extract_all(tar_bytes(&entries), &limits).unwrap(), expected);
+        assert_eq!(extract_all(gzip(&tar_bytes(&entries)), &limits).unwrap(), expected);
+        assert_eq!(extract_all(seven_zip_bytes(&entries), &limits).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_extract_detects_document_types() {
+        let entries = [
+            ("photo.png", b"\x89PNG\r\n\x1a\n\0\0".to_vec()),
+            ("cv.pdf", b"%PDF-1.7".to_vec()),
+        ];
+        assert_eq!(
+            extract_all(zip_bytes(&entries), &ArchiveLimits::default()).unwrap(),
+            vec![item("photo.png", "unsupported"), item("cv.pdf", "PDF")]
+        );
+    }
+
+    #[test]
+    fn test_extract_nested_archives() {
+        let docx = crate::extraction::docx::tests::docx("<w:p><w:r><w:t>Alex</w:t></w:r></w:p>");
+        let innermost = zip_bytes(&[("deep.txt", b"Too deep".to_vec())]);
+        let inner = gzip(&tar_bytes(&[
+            ("sam.txt", b"Sam Patel".to_vec()),
+            ("more.zip", innermost),
+        ]));
+        let outer = zip_bytes(&[
+            ("alex.docx", docx),
+            ("term/inner.tar.gz", inner),
+            ("broken.7z", SEVEN_ZIP_MAGIC.to_vec()),
+        ]);
+        let limits = ArchiveLimits {
+            max_depth: 1,
+            ..ArchiveLimits::default()
+        };
+
+        let items = extract_all(outer, &limits).unwrap();
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[0], item("alex.docx", "DOCX"));
+        assert_eq!(items[1], item("term/inner.tar.gz/sam.txt", "Sam Patel"));
+        assert_eq!(
+            items[2],
+            item(
+                "term/inner.tar.gz/more.zip",
+                "skipped: Nested archives deeper than 1 levels are not extracted"
+            )
+        );
+        assert_eq!(items[3].0, "broken.7z");
+        assert!(items[3].1.starts_with("failed: Failed to read nested 7z archive"));
+    }
+
+    #[test]
+    fn test_nested_entries_share_the_budget() {
+        let inner = zip_bytes(&[("a.txt", vec![]), ("b.txt", vec![])]);
+        let outer = tar_bytes(&[("one.zip", inner.clone()), ("two.zip", inner)]);
+        let limits = ArchiveLimits {
+            max_entries: 5,
+            ..ArchiveLimits::default()
+        };
+        // 2 archives + 2 members + 2 more members
+        assert!(matches!(
+            extract_all(outer, &limits),
+            Err(ArchiveError::Limit(e)) if e.0.contains("6 entries")
+        ));
+    }
+
+    #[test]
+    fn test_sanitize_entry_name() {
+        assert_eq!(sanitize_entry_name("resume.pdf").as_deref(), Some("resume.pdf"));
+        assert_eq!(
+            sanitize_entry_name("Spring 2026/CS/José (final).pdf").as_deref(),
+            Some("Spring 2026/CS/José (final).pdf")
+        );
+        assert_eq!(
+            sanitize_entry_name("../../etc/passwd").as_deref(),
+            Some("etc/passwd")
+        );
+        assert_eq!(
+            sanitize_entry_name("/abs//./path\\win.pdf").as_deref(),
+            Some("abs/path/win.pdf")
+        );
+        assert_eq!(
+            sanitize_entry_name("C:\\Users\\cv.pdf").as_deref(),
+            Some("Users/cv.pdf")
+        );
+        assert_eq!(
+            sanitize_entry_name("bad\u{0}name?*\u{fffd}.pdf").as_deref(),
+            Some("bad_name___.pdf")
+        );
+        assert_eq!(sanitize_entry_name("../.."), None);
+        assert_eq!(sanitize_entry_name(""), None);
+
+        let long = "a".repeat(300) + ".pdf";
+        assert_eq!(sanitize_entry_name(&long).unwrap().len(), MAX_COMPONENT_BYTES);
+    }
+}
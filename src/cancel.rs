@@ -0,0 +1,307 @@
+use crate::AppState;
+use crate::events::JobEvent;
+use crate::queue::TaskKind;
+use crate::job_state::{JobStatus, transition_job};
+use crate::service::DocumentStatus;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// Why a job can't be cancelled.
+#[derive(Debug)]
+pub enum CancelError {
+    NotFound,
+    /// The job already finished, was cancelled, or is being matched.
+    Conflict(JobStatus),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for CancelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Not found"),
+            Self::Conflict(status) => write!(f, "Job is {} and can't be cancelled", status.name()),
+            Self::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CancelError {}
+
+impl From<sqlx::Error> for CancelError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+/// How many unfinished uploads of each kind a cancellation stopped.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct CancelledUploads {
+    pub resumes: usize,
+    pub zip_archives: usize,
+    pub projects: usize,
+}
+
+/// The cancellation tokens of jobs that have work running on this replica. Tokens are
+/// handed out per job and dropped with the last piece of work holding one.
+#[derive(Clone, Default)]
+pub struct Cancellations {
+    jobs: Arc<Mutex<HashMap<Uuid, (CancellationToken, usize)>>>,
+}
+
+/// Held while work for a job runs; `cancelled` resolves when the job is cancelled.
+pub struct JobCancellation {
+    registry: Cancellations,
+    job_id: Uuid,
+    token: CancellationToken,
+}
+
+impl JobCancellation {
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+}
+
+impl Drop for JobCancellation {
+    fn drop(&mut self) {
+        let mut jobs = self.registry.jobs.lock().unwrap();
+        if let Some((_, holders)) = jobs.get_mut(&self.job_id) {
+            *holders -= 1;
+            if *holders == 0 {
+                jobs.remove(&self.job_id);
+            }
+        }
+    }
+}
+
+impl Cancellations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, job_id: Uuid) -> JobCancellation {
+        let mut jobs = self.jobs.lock().unwrap();
+        let (token, holders) = jobs.entry(job_id).or_insert_with(|| (CancellationToken::new(), 0));
+        *holders += 1;
+        JobCancellation {
+            registry: self.clone(),
+            job_id,
+            token: token.clone(),
+        }
+    }
+
+    /// Stops this replica's running work for `job_id`. Returns whether any was running.
+    pub fn cancel(&self, job_id: Uuid) -> bool {
+        match self.jobs.lock().unwrap().get(&job_id) {
+            Some((token, _)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancels local work for jobs cancelled on other replicas, as announced on the event
+    /// bus. Runs until the bus closes.
+    pub async fn watch(self, mut events: broadcast::Receiver<JobEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(JobEvent::Job {
+                    job_id,
+                    status: JobStatus::Cancelled,
+                }) => {
+                    if self.cancel(job_id) {
+                        tracing::info!("Stopped running work of cancelled job {}", job_id);
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("Cancellation watcher missed {} job events", missed);
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+}
+
+/// Runs the processing of one upload of `job_id` unless the job, currently in `status`, is
+/// cancelled, and drops it at its next await point if the job is cancelled while it runs.
+/// Returns `None` when the work was stopped or never started because of a cancellation.
+pub(crate) async fn unless_cancelled<F: Future>(
+    state: &AppState,
+    job_id: Uuid,
+    status: Option<JobStatus>,
+    upload_id: Uuid,
+    work: F,
+) -> Option<F::Output> {
+    // Registering before checking the status means a cancellation in between still
+    // reaches this token
+    let cancellation = state.cancellations.register(job_id);
+    if status == Some(JobStatus::Cancelled) {
+        tracing::info!("Skipping upload {} of cancelled job {}", upload_id, job_id);
+        return None;
+    }
+    tokio::select! {
+        biased;
+        _ = cancellation.cancelled() => {
+            tracing::info!("Stopped upload {} of cancelled job {}", upload_id, job_id);
+            None
+        }
+        output = work => Some(output),
+    }
+}
+
+/// The job an upload belongs to and the job's status.
+pub(crate) async fn upload_job(
+    state: &AppState,
+    kind: TaskKind,
+    upload_id: Uuid,
+) -> Result<Option<(Uuid, Option<JobStatus>)>, sqlx::Error> {
+    let row = match kind {
+        TaskKind::Resume => {
+            sqlx::query!(
+                r#"SELECT jobs.id, jobs.status as "status: JobStatus" FROM resume_uploads JOIN jobs ON jobs.id = resume_uploads.job_id WHERE resume_uploads.id = $1"#,
+                upload_id
+            )
+            .fetch_optional(&state.pool)
+            .await?
+            .map(|r| (r.id, r.status))
+        }
+        TaskKind::Zip => {
+            sqlx::query!(
+                r#"SELECT jobs.id, jobs.status as "status: JobStatus" FROM zip_archives JOIN jobs ON jobs.id = zip_archives.job_id WHERE zip_archives.id = $1"#,
+                upload_id
+            )
+            .fetch_optional(&state.pool)
+            .await?
+            .map(|r| (r.id, r.status))
+        }
+        TaskKind::Project => {
+            sqlx::query!(
+                r#"SELECT jobs.id, jobs.status as "status: JobStatus" FROM project_uploads JOIN jobs ON jobs.id = project_uploads.job_id WHERE project_uploads.id = $1"#,
+                upload_id
+            )
+            .fetch_optional(&state.pool)
+            .await?
+            .map(|r| (r.id, r.status))
+        }
+    };
+    Ok(row)
+}
+
+/// Cancels a job that hasn't finished: the job becomes `cancelled`, its unfinished uploads
+/// are marked `cancelled`, and its running work is stopped. Jobs being matched can't be
+/// cancelled.
+pub async fn cancel_job(state: &AppState, job_id: Uuid) -> Result<CancelledUploads, CancelError> {
+    if !transition_job(state, job_id, None, JobStatus::Cancelled).await? {
+        let status = sqlx::query_scalar!(
+            r#"SELECT status as "status: JobStatus" FROM jobs WHERE id = $1"#,
+            job_id
+        )
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or(CancelError::NotFound)?;
+        return Err(CancelError::Conflict(status.unwrap_or(JobStatus::Pending)));
+    }
+
+    let cancelled = DocumentStatus::Cancelled;
+    let resumes = sqlx::query_scalar!(
+        "UPDATE resume_uploads SET status = $1, updated_at = now() WHERE job_id = $2 AND (status IS NULL OR status IN ('pending', 'processing')) RETURNING id",
+        cancelled as DocumentStatus,
+        job_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let zip_archives = sqlx::query_scalar!(
+        "UPDATE zip_archives SET status = $1, updated_at = now() WHERE job_id = $2 AND (status IS NULL OR status IN ('pending', 'processing')) RETURNING id",
+        cancelled as DocumentStatus,
+        job_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let projects = sqlx::query_scalar!(
+        "UPDATE project_uploads SET status = $1, updated_at = now() WHERE job_id = $2 AND (status IS NULL OR status IN ('pending', 'processing')) RETURNING id",
+        cancelled as DocumentStatus,
+        job_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    // Only now stop running work, which would otherwise mark its own upload first
+    state.cancellations.cancel(job_id);
+
+    let events = resumes
+        .iter()
+        .map(|&id| JobEvent::ResumeUpload {
+            job_id,
+            id,
+            status: cancelled,
+            error: None,
+        })
+        .chain(zip_archives.iter().map(|&id| JobEvent::ZipArchive {
+            job_id,
+            id,
+            status: cancelled,
+            error: None,
+        }))
+        .chain(projects.iter().map(|&id| JobEvent::ProjectUpload {
+            job_id,
+            id,
+            status: cancelled,
+            error: None,
+        }));
+    for event in events {
+        state.events.publish(event).await;
+    }
+
+    tracing::info!("Cancelled job {}", job_id);
+    Ok(CancelledUploads {
+        resumes: resumes.len(),
+        zip_archives: zip_archives.len(),
+        projects: projects.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel_reaches_registered_work_only() {
+        let cancellations = Cancellations::new();
+        let job_id = Uuid::new_v4();
+        assert!(!cancellations.cancel(job_id));
+
+        let first = cancellations.register(job_id);
+        let second = cancellations.register(job_id);
+        assert!(cancellations.cancel(job_id));
+        first.cancelled().await;
+        second.cancelled().await;
+
+        drop(first);
+        drop(second);
+        assert!(cancellations.jobs.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watch_cancels_on_job_events() {
+        let cancellations = Cancellations::new();
+        let (sender, receiver) = broadcast::channel(4);
+        tokio::spawn(cancellations.clone().watch(receiver));
+
+        let job_id = Uuid::new_v4();
+        let work = cancellations.register(job_id);
+        sender
+            .send(JobEvent::Job {
+                job_id,
+                status: JobStatus::Cancelled,
+            })
+            .unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(1), work.cancelled())
+            .await
+            .expect("work should be cancelled");
+    }
+}
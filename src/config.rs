@@ -1,48 +1,1378 @@
+use crate::archive::ArchiveLimits;
+use crate::dedup::DuplicatePolicy;
+use crate::embeddings::{DEFAULT_EMBEDDING_MODEL, DEFAULT_EMBEDDINGS_ENDPOINT};
+use crate::job_state::FailureThresholds;
+use crate::llm::azure::DEFAULT_AZURE_API_VERSION;
+use crate::llm::ollama::{DEFAULT_OLLAMA_ENDPOINT, DEFAULT_OLLAMA_MODEL};
+use crate::llm::openai::{DEFAULT_OPENAI_ENDPOINT, DEFAULT_OPENAI_MODEL};
+use crate::llm::{AzureOpenAIProvider, LlmProvider, OllamaProvider, OpenAIProvider};
+use crate::reaper::{DEFAULT_STALE_TIMEOUT, DEFAULT_SWEEP_INTERVAL, ReaperConfig, StaleAction};
+use crate::retry::RetryPolicy;
+use crate::shutdown::DEFAULT_SHUTDOWN_DEADLINE;
+use serde::Deserialize;
+use serde::de::value::Error as ValueError;
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 use url::Url;
 
+/// Read when `CONFIG_FILE` isn't set; unlike an explicit `CONFIG_FILE`, it may be missing.
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Service settings, layered: built-in defaults, then the TOML config file, then the
+/// environment variables listed on each field. See `config.example.toml`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// `DATABASE_URL`
+    pub database_url: String,
+    /// `BIND_ADDRESS`, default `0.0.0.0:3000`
+    pub bind_address: String,
+    /// `MAX_CONCURRENT_TASKS`, default 10
+    pub max_concurrent_tasks: usize,
+    /// `DUPLICATE_POLICY`: `skip`, `link` or `reprocess`
+    pub duplicate_policy: DuplicatePolicy,
+    /// `REPARSE_OUTDATED_RESUMES`, re-parse resumes from an older schema version on startup
+    pub reparse_outdated_resumes: bool,
+    pub supabase: SupabaseConfig,
+    pub storage: StorageConfig,
+    pub openai: OpenAIConfig,
+    pub llm: LlmConfig,
+    pub ollama: OllamaConfig,
+    pub azure: AzureConfig,
+    pub ocr: OcrConfig,
+    /// `ZIP_MAX_ENTRIES`, `ZIP_MAX_ENTRY_BYTES`, `ZIP_MAX_TOTAL_BYTES`,
+    /// `ZIP_MAX_COMPRESSION_RATIO` and `ZIP_MAX_DEPTH`
+    pub archive: ArchiveLimits,
+    /// `JOB_MAX_FAILED_SHARE`, `JOB_REQUIRE_RESUMES` and `JOB_REQUIRE_PROJECTS`
+    pub jobs: FailureThresholds,
+    pub events: EventsConfig,
+    pub stale_uploads: StaleUploadsConfig,
+    pub matching: MatchingConfig,
+    pub shutdown: ShutdownConfig,
+    pub retry: RetryConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SupabaseConfig {
+    /// `SUPABASE_ENDPOINT`, a hosted project, the local CLI stack or a self-hosted
+    /// instance; used for storage unless `storage.endpoint` is set.
+    pub endpoint: String,
+    /// `SERVICE_KEY`
+    pub service_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// `STORAGE_BACKEND`: `s3` or `local`
+    pub backend: StorageBackend,
+    /// `STORAGE_LOCAL_ROOT`, default `storage`; the directory `local` storage keeps buckets in
+    pub local_root: String,
+    /// `S3_ENDPOINT`, any S3-compatible endpoint such as MinIO. Defaults to Supabase's
+    /// S3 API when `supabase.endpoint` is set, and to AWS S3 otherwise.
+    pub endpoint: Option<String>,
+    /// `S3_REGION`, default `us-east-1`; Supabase ignores it.
+    pub region: String,
+    /// `S3_FORCE_PATH_STYLE`, default on for custom endpoints and off for AWS S3
+    pub force_path_style: Option<bool>,
+    /// `S3_CREDENTIALS`: `static`, `env`, `profile` or `imds`
+    pub credentials: CredentialSource,
+    /// `S3_ACCESS_KEY`, default the Supabase project ref
+    pub access_key: Option<String>,
+    /// `S3_SECRET_KEY`, default the service key
+    pub secret_key: Option<String>,
+    /// `S3_PROFILE`, for `profile` credentials
+    pub profile: Option<String>,
+    pub buckets: Buckets,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Buckets {
+    /// `RESUMES_BUCKET`, default `resumes`
+    pub resumes: String,
+    /// `ZIP_ARCHIVES_BUCKET`, default `zip-archives`
+    pub zip_archives: String,
+    /// `PROJECT_SPREADSHEETS_BUCKET`, default `project-spreadsheets`
+    pub project_spreadsheets: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenAIConfig {
+    /// `OPENAI_API_KEY`, required by the `openai` LLM provider. Embeddings are skipped
+    /// without it.
+    pub api_key: String,
+    /// `OPENAI_ENDPOINT`, for the `openai` LLM provider
+    pub endpoint: String,
+    /// `OPENAI_MODEL`, for the `openai` LLM provider
+    pub model: String,
+    /// `EMBEDDINGS_ENDPOINT`
+    pub embeddings_endpoint: String,
+    /// `EMBEDDING_MODEL`
+    pub embedding_model: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmConfig {
+    /// `LLM_PROVIDER`: `openai`, `ollama` or `azure`
+    pub provider: LlmBackend,
+    /// `LLM_SCHEMA_REPAIR`, retry once when LLM output fails schema validation
+    pub schema_repair: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OllamaConfig {
+    /// `OLLAMA_ENDPOINT`
+    pub endpoint: String,
+    /// `OLLAMA_MODEL`
+    pub model: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AzureConfig {
+    /// `AZURE_OPENAI_API_KEY`
+    pub api_key: String,
+    /// `AZURE_OPENAI_ENDPOINT`
+    pub endpoint: String,
+    /// `AZURE_OPENAI_DEPLOYMENT`
+    pub deployment: String,
+    /// `AZURE_OPENAI_API_VERSION`
+    pub api_version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OcrConfig {
+    /// `OCR_ENABLED`, OCR scanned PDFs
+    pub enabled: bool,
+    /// `TESSERACT_CMD`
+    pub tesseract_cmd: String,
+    /// `PDFTOPPM_CMD`
+    pub pdftoppm_cmd: String,
+    /// `OCR_LANGUAGE`, Tesseract language(s), e.g. `eng+spa`
+    pub language: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventsConfig {
+    /// `JOB_EVENTS_NOTIFY`, share job events between replicas via Postgres LISTEN/NOTIFY
+    pub notify: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StaleUploadsConfig {
+    /// `STALE_UPLOAD_TIMEOUT_SECS`
+    pub timeout_secs: u64,
+    /// `STALE_UPLOAD_SWEEP_SECS`
+    pub sweep_secs: u64,
+    /// `STALE_UPLOAD_ACTION`: `requeue` or `fail`
+    pub action: StaleAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MatchingConfig {
+    /// `MATCHING_POLL_INTERVAL_SECS`
+    pub poll_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownConfig {
+    /// `SHUTDOWN_DEADLINE_SECS`, how long running uploads get to finish on SIGTERM/SIGINT
+    pub deadline_secs: u64,
+}
+
+/// Backoff for transient storage and LLM failures.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// `RETRY_MAX_ATTEMPTS`, including the first attempt
+    pub max_attempts: u32,
+    /// `RETRY_BASE_DELAY_MS`
+    pub base_delay_ms: u64,
+    /// `RETRY_MAX_DELAY_MS`
+    pub max_delay_ms: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            database_url: String::new(),
+            bind_address: "0.0.0.0:3000".to_string(),
+            max_concurrent_tasks: 10,
+            duplicate_policy: DuplicatePolicy::default(),
+            reparse_outdated_resumes: false,
+            supabase: SupabaseConfig::default(),
+            storage: StorageConfig::default(),
+            openai: OpenAIConfig::default(),
+            llm: LlmConfig::default(),
+            ollama: OllamaConfig::default(),
+            azure: AzureConfig::default(),
+            ocr: OcrConfig::default(),
+            archive: ArchiveLimits::default(),
+            jobs: FailureThresholds::default(),
+            events: EventsConfig::default(),
+            stale_uploads: StaleUploadsConfig::default(),
+            matching: MatchingConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            retry: RetryConfig::default(),
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::default(),
+            local_root: "storage".to_string(),
+            endpoint: None,
+            region: "us-east-1".to_string(),
+            force_path_style: None,
+            credentials: CredentialSource::default(),
+            access_key: None,
+            secret_key: None,
+            profile: None,
+            buckets: Buckets::default(),
+        }
+    }
+}
+
+impl Default for Buckets {
+    fn default() -> Self {
+        Self {
+            resumes: "resumes".to_string(),
+            zip_archives: "zip-archives".to_string(),
+            project_spreadsheets: "project-spreadsheets".to_string(),
+        }
+    }
+}
+
+impl Default for OpenAIConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            endpoint: DEFAULT_OPENAI_ENDPOINT.to_string(),
+            model: DEFAULT_OPENAI_MODEL.to_string(),
+            embeddings_endpoint: DEFAULT_EMBEDDINGS_ENDPOINT.to_string(),
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
+        }
+    }
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            provider: LlmBackend::default(),
+            schema_repair: true,
+        }
+    }
+}
+
+impl Default for OllamaConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_OLLAMA_ENDPOINT.to_string(),
+            model: DEFAULT_OLLAMA_MODEL.to_string(),
+        }
+    }
+}
+
+impl Default for AzureConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            endpoint: String::new(),
+            deployment: String::new(),
+            api_version: DEFAULT_AZURE_API_VERSION.to_string(),
+        }
+    }
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tesseract_cmd: "tesseract".to_string(),
+            pdftoppm_cmd: "pdftoppm".to_string(),
+            language: "eng".to_string(),
+        }
+    }
+}
+
+impl Default for StaleUploadsConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_STALE_TIMEOUT.as_secs(),
+            sweep_secs: DEFAULT_SWEEP_INTERVAL.as_secs(),
+            action: StaleAction::default(),
+        }
+    }
+}
+
+impl Default for MatchingConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 30,
+        }
+    }
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            deadline_secs: DEFAULT_SHUTDOWN_DEADLINE.as_secs(),
+        }
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        let policy = RetryPolicy::default();
+        Self {
+            max_attempts: policy.max_attempts,
+            base_delay_ms: policy.base_delay.as_millis() as u64,
+            max_delay_ms: policy.max_delay.as_millis() as u64,
+        }
+    }
+}
+
+/// Everything wrong with a configuration, so it can be fixed in one go.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Reads the file named by `CONFIG_FILE` (default `config.toml`, skipped if missing)
+    /// with the process environment on top.
+    pub fn load() -> Result<Self, ConfigError> {
+        let lookup = |key: &str| std::env::var(key).ok();
+        let (path, explicit) = match lookup("CONFIG_FILE") {
+            Some(path) => (path, true),
+            None => (DEFAULT_CONFIG_FILE.to_string(), false),
+        };
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !explicit => None,
+            Err(e) => {
+                return Err(ConfigError {
+                    problems: vec![format!("Can't read config file {}: {}", path, e)],
+                });
+            }
+        };
+        Self::from_sources(contents.as_deref(), lookup)
+    }
+
+    /// Parses `file`, the config file's contents if there is one, applies the overrides
+    /// found through `lookup` and validates the result, reporting every problem at once.
+    pub fn from_sources(
+        file: Option<&str>,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut config = match file {
+            Some(contents) => toml::from_str(contents).map_err(|e| ConfigError {
+                problems: vec![format!("Invalid config file: {}", e)],
+            })?,
+            None => Config::default(),
+        };
+        let mut problems = Vec::new();
+        config.apply_overrides(&lookup, &mut problems);
+        config.validate(&mut problems);
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError { problems })
+        }
+    }
+
+    fn apply_overrides(
+        &mut self,
+        lookup: &impl Fn(&str) -> Option<String>,
+        problems: &mut Vec<String>,
+    ) {
+        let strings = [
+            (&mut self.database_url, "DATABASE_URL"),
+            (&mut self.bind_address, "BIND_ADDRESS"),
+            (&mut self.supabase.endpoint, "SUPABASE_ENDPOINT"),
+            (&mut self.supabase.service_key, "SERVICE_KEY"),
+            (&mut self.storage.local_root, "STORAGE_LOCAL_ROOT"),
+            (&mut self.storage.region, "S3_REGION"),
+            (&mut self.storage.buckets.resumes, "RESUMES_BUCKET"),
+            (&mut self.storage.buckets.zip_archives, "ZIP_ARCHIVES_BUCKET"),
+            (&mut self.storage.buckets.project_spreadsheets, "PROJECT_SPREADSHEETS_BUCKET"),
+            (&mut self.openai.api_key, "OPENAI_API_KEY"),
+            (&mut self.openai.endpoint, "OPENAI_ENDPOINT"),
+            (&mut self.openai.model, "OPENAI_MODEL"),
+            (&mut self.openai.embeddings_endpoint, "EMBEDDINGS_ENDPOINT"),
+            (&mut self.openai.embedding_model, "EMBEDDING_MODEL"),
+            (&mut self.ollama.endpoint, "OLLAMA_ENDPOINT"),
+            (&mut self.ollama.model, "OLLAMA_MODEL"),
+            (&mut self.azure.api_key, "AZURE_OPENAI_API_KEY"),
+            (&mut self.azure.endpoint, "AZURE_OPENAI_ENDPOINT"),
+            (&mut self.azure.deployment, "AZURE_OPENAI_DEPLOYMENT"),
+            (&mut self.azure.api_version, "AZURE_OPENAI_API_VERSION"),
+            (&mut self.ocr.tesseract_cmd, "TESSERACT_CMD"),
+            (&mut self.ocr.pdftoppm_cmd, "PDFTOPPM_CMD"),
+            (&mut self.ocr.language, "OCR_LANGUAGE"),
+        ];
+        for (field, key) in strings {
+            if let Some(value) = lookup(key) {
+                *field = value;
+            }
+        }
+        let optional = [
+            (&mut self.storage.endpoint, "S3_ENDPOINT"),
+            (&mut self.storage.access_key, "S3_ACCESS_KEY"),
+            (&mut self.storage.secret_key, "S3_SECRET_KEY"),
+            (&mut self.storage.profile, "S3_PROFILE"),
+        ];
+        for (field, key) in optional {
+            if let Some(value) = lookup(key) {
+                *field = Some(value);
+            }
+        }
+        let flags = [
+            (&mut self.reparse_outdated_resumes, "REPARSE_OUTDATED_RESUMES"),
+            (&mut self.llm.schema_repair, "LLM_SCHEMA_REPAIR"),
+            (&mut self.ocr.enabled, "OCR_ENABLED"),
+            (&mut self.jobs.require_resumes, "JOB_REQUIRE_RESUMES"),
+            (&mut self.jobs.require_projects, "JOB_REQUIRE_PROJECTS"),
+            (&mut self.events.notify, "JOB_EVENTS_NOTIFY"),
+        ];
+        for (field, key) in flags {
+            override_with(lookup, problems, key, field, flag, "true or false");
+        }
+        let numbers = [
+            (&mut self.archive.max_entry_bytes, "ZIP_MAX_ENTRY_BYTES"),
+            (&mut self.archive.max_total_bytes, "ZIP_MAX_TOTAL_BYTES"),
+            (&mut self.archive.max_compression_ratio, "ZIP_MAX_COMPRESSION_RATIO"),
+            (&mut self.stale_uploads.timeout_secs, "STALE_UPLOAD_TIMEOUT_SECS"),
+            (&mut self.stale_uploads.sweep_secs, "STALE_UPLOAD_SWEEP_SECS"),
+            (&mut self.matching.poll_interval_secs, "MATCHING_POLL_INTERVAL_SECS"),
+            (&mut self.shutdown.deadline_secs, "SHUTDOWN_DEADLINE_SECS"),
+            (&mut self.retry.base_delay_ms, "RETRY_BASE_DELAY_MS"),
+            (&mut self.retry.max_delay_ms, "RETRY_MAX_DELAY_MS"),
+        ];
+        for (field, key) in numbers {
+            override_with(lookup, problems, key, field, number, "a number");
+        }
+        override_with(
+            lookup,
+            problems,
+            "RETRY_MAX_ATTEMPTS",
+            &mut self.retry.max_attempts,
+            number,
+            "a number",
+        );
+        let counts = [
+            (&mut self.max_concurrent_tasks, "MAX_CONCURRENT_TASKS"),
+            (&mut self.archive.max_entries, "ZIP_MAX_ENTRIES"),
+            (&mut self.archive.max_depth, "ZIP_MAX_DEPTH"),
+        ];
+        for (field, key) in counts {
+            override_with(lookup, problems, key, field, number, "a number");
+        }
+        override_with(
+            lookup,
+            problems,
+            "JOB_MAX_FAILED_SHARE",
+            &mut self.jobs.max_failed_share,
+            number,
+            "a number",
+        );
+        override_with(
+            lookup,
+            problems,
+            "S3_FORCE_PATH_STYLE",
+            &mut self.storage.force_path_style,
+            |value| flag(value).map(Some),
+            "true or false",
+        );
+        override_with(
+            lookup,
+            problems,
+            "STORAGE_BACKEND",
+            &mut self.storage.backend,
+            choice,
+            "s3 or local",
+        );
+        override_with(
+            lookup,
+            problems,
+            "S3_CREDENTIALS",
+            &mut self.storage.credentials,
+            choice,
+            "static, env, profile or imds",
+        );
+        override_with(
+            lookup,
+            problems,
+            "LLM_PROVIDER",
+            &mut self.llm.provider,
+            choice,
+            "openai, ollama or azure",
+        );
+        override_with(
+            lookup,
+            problems,
+            "DUPLICATE_POLICY",
+            &mut self.duplicate_policy,
+            choice,
+            "skip, link or reprocess",
+        );
+        override_with(
+            lookup,
+            problems,
+            "STALE_UPLOAD_ACTION",
+            &mut self.stale_uploads.action,
+            choice,
+            "requeue or fail",
+        );
+    }
+
+    fn validate(&self, problems: &mut Vec<String>) {
+        if self.database_url.is_empty() {
+            problems.push("database_url (DATABASE_URL) must be set".to_string());
+        } else if !self.database_url.starts_with("postgres://")
+            && !self.database_url.starts_with("postgresql://")
+        {
+            problems.push("database_url (DATABASE_URL) must be a postgres:// URL".to_string());
+        }
+        if self.bind_address.parse::<SocketAddr>().is_err() {
+            problems.push(format!(
+                "bind_address (BIND_ADDRESS) '{}' is not an address like 0.0.0.0:3000",
+                self.bind_address
+            ));
+        }
+        if self.max_concurrent_tasks == 0 {
+            problems.push("max_concurrent_tasks (MAX_CONCURRENT_TASKS) must be at least 1".to_string());
+        }
+        match self.storage.backend {
+            StorageBackend::S3 => {
+                if let Err(storage_problems) = self.s3_connection() {
+                    problems.extend(storage_problems);
+                }
+            }
+            StorageBackend::Local => {
+                if self.storage.local_root.is_empty() {
+                    problems.push(
+                        "storage.local_root (STORAGE_LOCAL_ROOT) must not be empty".to_string(),
+                    );
+                }
+            }
+        }
+        let buckets = [
+            ("storage.buckets.resumes (RESUMES_BUCKET)", &self.storage.buckets.resumes),
+            ("storage.buckets.zip_archives (ZIP_ARCHIVES_BUCKET)", &self.storage.buckets.zip_archives),
+            (
+                "storage.buckets.project_spreadsheets (PROJECT_SPREADSHEETS_BUCKET)",
+                &self.storage.buckets.project_spreadsheets,
+            ),
+        ];
+        for (name, bucket) in buckets {
+            if !is_valid_bucket_name(bucket) {
+                problems.push(format!(
+                    "{} '{}' is not a valid bucket name (3 to 63 lowercase letters, digits, '-' or '.')",
+                    name, bucket
+                ));
+            }
+        }
+        if Url::parse(&self.openai.embeddings_endpoint).is_err() {
+            problems.push(format!(
+                "openai.embeddings_endpoint (EMBEDDINGS_ENDPOINT) '{}' is not a valid URL",
+                self.openai.embeddings_endpoint
+            ));
+        }
+        if self.openai.embedding_model.is_empty() {
+            problems.push("openai.embedding_model (EMBEDDING_MODEL) must not be empty".to_string());
+        }
+        if let Err(llm_problems) = self.llm_connection() {
+            problems.extend(llm_problems);
+        }
+        if self.ocr.enabled {
+            let ocr = [
+                ("ocr.tesseract_cmd (TESSERACT_CMD)", &self.ocr.tesseract_cmd),
+                ("ocr.pdftoppm_cmd (PDFTOPPM_CMD)", &self.ocr.pdftoppm_cmd),
+                ("ocr.language (OCR_LANGUAGE)", &self.ocr.language),
+            ];
+            for (name, value) in ocr {
+                if value.is_empty() {
+                    problems.push(format!("{} must not be empty while OCR is enabled", name));
+                }
+            }
+        }
+
+        let limits = [
+            ("archive.max_entries (ZIP_MAX_ENTRIES)", self.archive.max_entries as u64),
+            ("archive.max_entry_bytes (ZIP_MAX_ENTRY_BYTES)", self.archive.max_entry_bytes),
+            ("archive.max_total_bytes (ZIP_MAX_TOTAL_BYTES)", self.archive.max_total_bytes),
+            (
+                "archive.max_compression_ratio (ZIP_MAX_COMPRESSION_RATIO)",
+                self.archive.max_compression_ratio,
+            ),
+            ("stale_uploads.timeout_secs (STALE_UPLOAD_TIMEOUT_SECS)", self.stale_uploads.timeout_secs),
+            ("stale_uploads.sweep_secs (STALE_UPLOAD_SWEEP_SECS)", self.stale_uploads.sweep_secs),
+            (
+                "matching.poll_interval_secs (MATCHING_POLL_INTERVAL_SECS)",
+                self.matching.poll_interval_secs,
+            ),
+            ("retry.max_attempts (RETRY_MAX_ATTEMPTS)", self.retry.max_attempts as u64),
+        ];
+        for (name, value) in limits {
+            if value == 0 {
+                problems.push(format!("{} must be greater than zero", name));
+            }
+        }
+        let share = self.jobs.max_failed_share;
+        if !(share > 0.0 && share <= 1.0) {
+            problems.push(format!(
+                "jobs.max_failed_share (JOB_MAX_FAILED_SHARE) must be greater than 0 and at most 1, got {}",
+                share
+            ));
+        }
+    }
+
+    /// How the stale upload reaper runs.
+    pub fn reaper(&self) -> ReaperConfig {
+        ReaperConfig {
+            timeout: Duration::from_secs(self.stale_uploads.timeout_secs),
+            interval: Duration::from_secs(self.stale_uploads.sweep_secs),
+            action: self.stale_uploads.action,
+        }
+    }
+
+    /// How transient storage and LLM failures are retried.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.retry.max_attempts,
+            base_delay: Duration::from_millis(self.retry.base_delay_ms),
+            max_delay: Duration::from_millis(self.retry.max_delay_ms),
+        }
+    }
+
+    /// Resolves the selected LLM provider's settings, reporting the ones it needs but lacks.
+    pub fn llm_connection(&self) -> Result<LlmConnection, Vec<String>> {
+        let missing = |name: &str, value: &str| {
+            value.is_empty().then(|| {
+                format!(
+                    "{} must be set for LLM_PROVIDER={}",
+                    name,
+                    self.llm.provider.name()
+                )
+            })
+        };
+        let (connection, problems): (_, Vec<String>) = match self.llm.provider {
+            LlmBackend::OpenAI => (
+                LlmConnection::OpenAI {
+                    api_key: self.openai.api_key.clone(),
+                    endpoint: self.openai.endpoint.clone(),
+                    model: self.openai.model.clone(),
+                },
+                missing("openai.api_key (OPENAI_API_KEY)", &self.openai.api_key)
+                    .into_iter()
+                    .collect(),
+            ),
+            LlmBackend::Ollama => (
+                LlmConnection::Ollama {
+                    endpoint: self.ollama.endpoint.clone(),
+                    model: self.ollama.model.clone(),
+                },
+                Vec::new(),
+            ),
+            LlmBackend::Azure => (
+                LlmConnection::Azure {
+                    api_key: self.azure.api_key.clone(),
+                    endpoint: self.azure.endpoint.clone(),
+                    deployment: self.azure.deployment.clone(),
+                    api_version: self.azure.api_version.clone(),
+                },
+                [
+                    missing("azure.api_key (AZURE_OPENAI_API_KEY)", &self.azure.api_key),
+                    missing("azure.endpoint (AZURE_OPENAI_ENDPOINT)", &self.azure.endpoint),
+                    missing("azure.deployment (AZURE_OPENAI_DEPLOYMENT)", &self.azure.deployment),
+                ]
+                .into_iter()
+                .flatten()
+                .collect(),
+            ),
+        };
+        if problems.is_empty() {
+            Ok(connection)
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// Applies the environment variable `key` to `field` when it is set, or records that its
+/// value isn't `expected`.
+fn override_with<T>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    problems: &mut Vec<String>,
+    key: &str,
+    field: &mut T,
+    parse: impl Fn(&str) -> Option<T>,
+    expected: &str,
+) {
+    if let Some(value) = lookup(key) {
+        match parse(&value) {
+            Some(parsed) => *field = parsed,
+            None => problems.push(format!("{} must be {}, got '{}'", key, expected, value)),
+        }
+    }
+}
+
+fn flag(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Option<T> {
+    value.parse().ok()
+}
+
+/// Parses a variant of one of the lowercase setting enums, the way the config file does.
+fn choice<T: DeserializeOwned>(value: &str) -> Option<T> {
+    T::deserialize(IntoDeserializer::<ValueError>::into_deserializer(value.to_lowercase())).ok()
+}
+
+impl Config {
+    /// Resolves where and how to reach S3: an explicit `storage.endpoint` wins, then the
+    /// Supabase project's S3 API, then AWS itself.
+    pub fn s3_connection(&self) -> Result<S3Connection, Vec<String>> {
+        let mut problems = Vec::new();
+        let supabase = if self.supabase.endpoint.is_empty() {
+            None
+        } else {
+            match parse_s3_config(&self.supabase.endpoint) {
+                Ok(supabase) => Some(supabase),
+                Err(e) => {
+                    problems.push(format!(
+                        "supabase.endpoint (SUPABASE_ENDPOINT) is not a valid URL: {}",
+                        e
+                    ));
+                    None
+                }
+            }
+        };
+
+        let endpoint = match &self.storage.endpoint {
+            Some(endpoint) => {
+                if Url::parse(endpoint).is_err() {
+                    problems.push(format!(
+                        "storage.endpoint (S3_ENDPOINT) '{}' is not a valid URL",
+                        endpoint
+                    ));
+                }
+                Some(endpoint.clone())
+            }
+            None => supabase.as_ref().map(|supabase| supabase.endpoint.clone()),
+        };
+        if self.storage.region.is_empty() {
+            problems.push("storage.region (S3_REGION) must not be empty".to_string());
+        }
+
+        let credentials = match self.storage.credentials {
+            CredentialSource::Static => {
+                let access_key = self.storage.access_key.clone().or_else(|| {
+                    supabase
+                        .as_ref()
+                        .and_then(|supabase| supabase.project_ref.clone())
+                });
+                let secret_key = self.storage.secret_key.clone().or_else(|| {
+                    Some(self.supabase.service_key.clone()).filter(|key| !key.is_empty())
+                });
+                if access_key.is_none() {
+                    problems.push(
+                        "storage.access_key (S3_ACCESS_KEY) must be set for static credentials unless supabase.endpoint is a hosted project".to_string(),
+                    );
+                }
+                if secret_key.is_none() {
+                    problems.push(
+                        "storage.secret_key (S3_SECRET_KEY) or supabase.service_key (SERVICE_KEY) must be set for static credentials".to_string(),
+                    );
+                }
+                S3Credentials::Static {
+                    access_key: access_key.unwrap_or_default(),
+                    secret_key: secret_key.unwrap_or_default(),
+                }
+            }
+            CredentialSource::Env => S3Credentials::Env,
+            CredentialSource::Profile => S3Credentials::Profile(self.storage.profile.clone()),
+            CredentialSource::Imds => S3Credentials::Imds,
+        };
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(S3Connection {
+            force_path_style: self.storage.force_path_style.unwrap_or(endpoint.is_some()),
+            endpoint,
+            region: self.storage.region.clone(),
+            credentials,
+        })
+    }
+}
+
+/// S3 bucket naming rules, which Supabase storage follows too.
+fn is_valid_bucket_name(name: &str) -> bool {
+    (3..=63).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric())
+}
+
+/// Path of Supabase's S3-compatible storage API under the project URL.
+const SUPABASE_S3_PATH: &str = "/storage/v1/s3/";
+
 pub struct S3Config {
     pub endpoint: String,
-    pub project_ref: String,
+    /// The hosted project's ref, which doubles as its S3 access key. Self-hosted
+    /// instances on their own domain have none.
+    pub project_ref: Option<String>,
 }
 
 pub fn parse_s3_config(supabase_endpoint: &str) -> anyhow::Result<S3Config> {
     let parsed_url = Url::parse(supabase_endpoint)?;
     let host_str = parsed_url.host_str().ok_or_else(|| anyhow::anyhow!("SUPABASE_ENDPOINT missing host"))?;
-    
-    if host_str == "127.0.0.1" || host_str == "localhost" {
-        let clean_endpoint = supabase_endpoint.trim_end_matches('/');
+
+    if let Some(project_ref) = host_str.strip_suffix(".supabase.co") {
+        // Hosted projects: https://<project_ref>.supabase.co
+        let project_ref = project_ref.split('.').next_back().unwrap_or(project_ref).to_string();
         Ok(S3Config {
-            endpoint: format!("{}/storage/v1/s3/", clean_endpoint),
-            project_ref: "local-stub".to_string(),
+            endpoint: format!("https://{}.supabase.co{}", project_ref, SUPABASE_S3_PATH),
+            project_ref: Some(project_ref),
         })
     } else {
-        // Assume standard supabase URL format: https://<project_ref>.supabase.co
-        let project_ref = host_str.split('.').next().ok_or_else(|| anyhow::anyhow!("Invalid project ref format"))?.to_string();
+        // The local CLI stack and self-hosted instances serve storage under the URL as given,
+        // possibly behind a path prefix, or the URL already points at the S3 API
+        let clean_endpoint = supabase_endpoint.trim_end_matches('/');
+        let endpoint = if clean_endpoint.ends_with(SUPABASE_S3_PATH.trim_end_matches('/')) {
+            format!("{}/", clean_endpoint)
+        } else {
+            format!("{}{}", clean_endpoint, SUPABASE_S3_PATH)
+        };
+        let local = host_str == "127.0.0.1" || host_str == "localhost";
         Ok(S3Config {
-            endpoint: format!("https://{}.supabase.co/storage/v1/s3/", project_ref),
-            project_ref,
+            endpoint,
+            project_ref: local.then(|| "local-stub".to_string()),
         })
     }
 }
 
+/// Where uploaded files are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Supabase storage, MinIO or AWS S3.
+    #[default]
+    S3,
+    /// Directories under `storage.local_root`, one per bucket.
+    Local,
+}
+
+/// How the S3 client finds its credentials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialSource {
+    /// `storage.access_key` and `storage.secret_key`, defaulting to the Supabase project
+    /// ref and service key.
+    #[default]
+    Static,
+    /// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`.
+    Env,
+    /// A profile from `~/.aws/credentials` and `~/.aws/config`.
+    Profile,
+    /// The EC2 instance metadata service.
+    Imds,
+}
+
+/// Everything needed to build the S3 client, with the Supabase defaults filled in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct S3Connection {
+    /// `None` uses AWS's own endpoint for the region.
+    pub endpoint: Option<String>,
+    pub region: String,
+    pub force_path_style: bool,
+    pub credentials: S3Credentials,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum S3Credentials {
+    Static {
+        access_key: String,
+        secret_key: String,
+    },
+    Env,
+    /// `None` picks the profile the AWS SDK would, from `AWS_PROFILE` or `default`.
+    Profile(Option<String>),
+    Imds,
+}
+
+/// Which LLM provider structures resumes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmBackend {
+    #[default]
+    OpenAI,
+    Ollama,
+    Azure,
+}
+
+impl LlmBackend {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::OpenAI => "openai",
+            Self::Ollama => "ollama",
+            Self::Azure => "azure",
+        }
+    }
+}
+
+/// Everything needed to build the selected LLM provider.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LlmConnection {
+    OpenAI {
+        api_key: String,
+        endpoint: String,
+        model: String,
+    },
+    Ollama {
+        endpoint: String,
+        model: String,
+    },
+    Azure {
+        api_key: String,
+        endpoint: String,
+        deployment: String,
+        api_version: String,
+    },
+}
+
+impl LlmConnection {
+    pub fn build(&self, client: reqwest::Client) -> Arc<dyn LlmProvider> {
+        match self {
+            LlmConnection::OpenAI {
+                api_key,
+                endpoint,
+                model,
+            } => Arc::new(OpenAIProvider::new(
+                client,
+                api_key.clone(),
+                endpoint.clone(),
+                model.clone(),
+            )),
+            LlmConnection::Ollama { endpoint, model } => {
+                Arc::new(OllamaProvider::new(client, endpoint, model.clone()))
+            }
+            LlmConnection::Azure {
+                api_key,
+                endpoint,
+                deployment,
+                api_version,
+            } => Arc::new(AzureOpenAIProvider::new(
+                client,
+                api_key.clone(),
+                endpoint,
+                deployment,
+                api_version,
+            )),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> + use<> {
+        let map: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| map.get(key).cloned()
+    }
+
+    const REQUIRED: [(&str, &str); 4] = [
+        ("DATABASE_URL", "postgres://postgres@localhost/matchmaker"),
+        ("SUPABASE_ENDPOINT", "http://localhost:54321"),
+        ("SERVICE_KEY", "service-key"),
+        ("OPENAI_API_KEY", "sk-test"),
+    ];
+
+    fn with_required(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> + use<> {
+        lookup(&[&REQUIRED[..], vars].concat())
+    }
+
+    #[test]
+    fn test_llm_defaults_to_openai() {
+        let config = Config::from_sources(None, lookup(&REQUIRED)).unwrap();
+        assert_eq!(
+            config.llm_connection().unwrap(),
+            LlmConnection::OpenAI {
+                api_key: "sk-test".to_string(),
+                endpoint: DEFAULT_OPENAI_ENDPOINT.to_string(),
+                model: DEFAULT_OPENAI_MODEL.to_string(),
+            }
+        );
+        assert!(config.llm.schema_repair);
+    }
+
+    #[test]
+    fn test_llm_ollama_needs_no_key() {
+        let config = Config::from_sources(
+            Some("[llm]\nprovider = \"ollama\"\n"),
+            lookup(&[
+                ("DATABASE_URL", "postgres://localhost/matchmaker"),
+                ("SUPABASE_ENDPOINT", "http://localhost:54321"),
+                ("SERVICE_KEY", "service-key"),
+                ("OLLAMA_MODEL", "qwen2.5"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            config.llm_connection().unwrap(),
+            LlmConnection::Ollama {
+                endpoint: DEFAULT_OLLAMA_ENDPOINT.to_string(),
+                model: "qwen2.5".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_llm_azure_requires_deployment() {
+        let err = Config::from_sources(
+            None,
+            with_required(&[
+                ("LLM_PROVIDER", "Azure"),
+                ("AZURE_OPENAI_API_KEY", "key"),
+                ("AZURE_OPENAI_ENDPOINT", "https://acme.openai.azure.com"),
+            ]),
+        )
+        .unwrap_err();
+        assert_eq!(err.problems.len(), 1);
+        assert!(err.to_string().contains("AZURE_OPENAI_DEPLOYMENT"));
+
+        let err = Config::from_sources(None, with_required(&[("LLM_PROVIDER", "palm")])).unwrap_err();
+        assert!(err.to_string().contains("LLM_PROVIDER"));
+    }
+
+    #[test]
+    fn test_processing_settings_from_file_and_env() {
+        let file = r#"
+            duplicate_policy = "skip"
+
+            [archive]
+            max_entries = 5
+            max_depth = 0
+
+            [jobs]
+            max_failed_share = 0.25
+
+            [stale_uploads]
+            action = "fail"
+
+            [ocr]
+            enabled = false
+        "#;
+        let config = Config::from_sources(
+            Some(file),
+            with_required(&[
+                ("DUPLICATE_POLICY", "Reprocess"),
+                ("ZIP_MAX_COMPRESSION_RATIO", "20"),
+                ("JOB_REQUIRE_PROJECTS", "true"),
+                ("STALE_UPLOAD_TIMEOUT_SECS", "600"),
+                ("SHUTDOWN_DEADLINE_SECS", "0"),
+                ("RETRY_BASE_DELAY_MS", "10"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.duplicate_policy, DuplicatePolicy::Reprocess);
+        assert_eq!(config.archive.max_entries, 5);
+        assert_eq!(config.archive.max_compression_ratio, 20);
+        assert_eq!(config.archive.max_depth, 0);
+        assert_eq!(config.archive.max_entry_bytes, ArchiveLimits::default().max_entry_bytes);
+        assert_eq!(config.jobs.max_failed_share, 0.25);
+        assert!(config.jobs.require_projects && !config.jobs.require_resumes);
+        assert_eq!(
+            config.reaper(),
+            ReaperConfig {
+                timeout: Duration::from_secs(600),
+                interval: DEFAULT_SWEEP_INTERVAL,
+                action: StaleAction::Fail,
+            }
+        );
+        assert!(!config.ocr.enabled);
+        assert_eq!(config.shutdown.deadline_secs, 0);
+        assert_eq!(config.matching.poll_interval_secs, 30);
+        let retry = config.retry_policy();
+        assert_eq!(retry.base_delay, Duration::from_millis(10));
+        assert_eq!(retry.max_attempts, RetryPolicy::default().max_attempts);
+    }
+
+    #[test]
+    fn test_processing_settings_are_validated() {
+        let err = Config::from_sources(
+            None,
+            with_required(&[
+                ("DUPLICATE_POLICY", "merge"),
+                ("ZIP_MAX_ENTRIES", "lots"),
+                ("ZIP_MAX_TOTAL_BYTES", "0"),
+                ("JOB_MAX_FAILED_SHARE", "1.5"),
+                ("STALE_UPLOAD_SWEEP_SECS", "0"),
+                ("STALE_UPLOAD_ACTION", "retry"),
+                ("MATCHING_POLL_INTERVAL_SECS", "soon"),
+                ("OCR_ENABLED", "yes"),
+                ("OCR_LANGUAGE", ""),
+                ("RETRY_MAX_ATTEMPTS", "0"),
+            ]),
+        )
+        .unwrap_err();
+        let message = err.to_string();
+        for key in [
+            "DUPLICATE_POLICY",
+            "ZIP_MAX_ENTRIES",
+            "ZIP_MAX_TOTAL_BYTES",
+            "JOB_MAX_FAILED_SHARE",
+            "STALE_UPLOAD_SWEEP_SECS",
+            "STALE_UPLOAD_ACTION",
+            "MATCHING_POLL_INTERVAL_SECS",
+            "OCR_ENABLED",
+            "OCR_LANGUAGE",
+            "RETRY_MAX_ATTEMPTS",
+        ] {
+            assert!(message.contains(key), "{} missing from {}", key, message);
+        }
+        assert_eq!(err.problems.len(), 10);
+    }
+
+    #[test]
+    fn test_config_defaults_with_required_env() {
+        let config = Config::from_sources(None, lookup(&REQUIRED)).unwrap();
+        assert_eq!(config.bind_address, "0.0.0.0:3000");
+        assert_eq!(config.max_concurrent_tasks, 10);
+        assert_eq!(config.storage.region, "us-east-1");
+        assert_eq!(config.storage.buckets, Buckets::default());
+        assert_eq!(config.openai.embedding_model, DEFAULT_EMBEDDING_MODEL);
+        assert_eq!(config.supabase.service_key, "service-key");
+    }
+
+    #[test]
+    fn test_config_env_overrides_file() {
+        let file = r#"
+            database_url = "postgres://file@db/matchmaker"
+            bind_address = "127.0.0.1:8080"
+            max_concurrent_tasks = 4
+
+            [supabase]
+            endpoint = "https://abc.supabase.co"
+            service_key = "from-file"
+
+            [storage]
+            region = "eu-west-1"
+
+            [storage.buckets]
+            resumes = "staging-resumes"
+
+            [llm]
+            provider = "ollama"
+        "#;
+        let config = Config::from_sources(
+            Some(file),
+            lookup(&[("SERVICE_KEY", "from-env"), ("MAX_CONCURRENT_TASKS", "2")]),
+        )
+        .unwrap();
+        assert_eq!(config.database_url, "postgres://file@db/matchmaker");
+        assert_eq!(config.bind_address, "127.0.0.1:8080");
+        assert_eq!(config.max_concurrent_tasks, 2);
+        assert_eq!(config.supabase.service_key, "from-env");
+        assert_eq!(config.storage.region, "eu-west-1");
+        assert_eq!(config.storage.buckets.resumes, "staging-resumes");
+        assert_eq!(config.storage.buckets.zip_archives, "zip-archives");
+    }
+
+    #[test]
+    fn test_config_reports_every_problem() {
+        let err = Config::from_sources(
+            Some("bind_address = \"nowhere\"\n[storage.buckets]\nresumes = \"Resumes\"\n"),
+            lookup(&[("MAX_CONCURRENT_TASKS", "many")]),
+        )
+        .unwrap_err();
+        let message = err.to_string();
+        for key in [
+            "DATABASE_URL",
+            "S3_ACCESS_KEY",
+            "SERVICE_KEY",
+            "BIND_ADDRESS",
+            "RESUMES_BUCKET",
+            "MAX_CONCURRENT_TASKS",
+            "OPENAI_API_KEY",
+        ] {
+            assert!(message.contains(key), "{} missing from {}", key, message);
+        }
+        assert_eq!(err.problems.len(), 7);
+    }
+
+    #[test]
+    fn test_s3_connection_for_supabase() {
+        let config = Config::from_sources(None, lookup(&REQUIRED)).unwrap();
+        assert_eq!(
+            config.s3_connection().unwrap(),
+            S3Connection {
+                endpoint: Some("http://localhost:54321/storage/v1/s3/".to_string()),
+                region: "us-east-1".to_string(),
+                force_path_style: true,
+                credentials: S3Credentials::Static {
+                    access_key: "local-stub".to_string(),
+                    secret_key: "service-key".to_string(),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_s3_connection_for_minio_and_aws() {
+        let minio = Config::from_sources(
+            Some("[storage]\nendpoint = \"http://minio:9000\"\naccess_key = \"minioadmin\"\nsecret_key = \"minioadmin\"\n"),
+            lookup(&[
+                ("DATABASE_URL", "postgres://localhost/matchmaker"),
+                ("LLM_PROVIDER", "ollama"),
+            ]),
+        )
+        .unwrap();
+        let connection = minio.s3_connection().unwrap();
+        assert_eq!(connection.endpoint.as_deref(), Some("http://minio:9000"));
+        assert!(connection.force_path_style);
+
+        let aws = Config::from_sources(
+            None,
+            lookup(&[
+                ("DATABASE_URL", "postgres://localhost/matchmaker"),
+                ("LLM_PROVIDER", "ollama"),
+                ("S3_CREDENTIALS", "profile"),
+                ("S3_PROFILE", "staging"),
+                ("S3_REGION", "eu-central-1"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            aws.s3_connection().unwrap(),
+            S3Connection {
+                endpoint: None,
+                region: "eu-central-1".to_string(),
+                force_path_style: false,
+                credentials: S3Credentials::Profile(Some("staging".to_string())),
+            }
+        );
+
+        let err = Config::from_sources(
+            None,
+            lookup(&[
+                ("DATABASE_URL", "postgres://localhost/matchmaker"),
+                ("S3_CREDENTIALS", "vault"),
+                ("S3_FORCE_PATH_STYLE", "yes"),
+            ]),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("S3_CREDENTIALS"));
+        assert!(err.to_string().contains("S3_FORCE_PATH_STYLE"));
+    }
+
+    #[test]
+    fn test_local_storage_needs_no_s3_settings() {
+        let config = Config::from_sources(
+            Some("[storage]\nbackend = \"local\"\n"),
+            lookup(&[
+                ("DATABASE_URL", "postgres://localhost/matchmaker"),
+                ("LLM_PROVIDER", "ollama"),
+                ("STORAGE_LOCAL_ROOT", "/var/lib/matchmaker"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.storage.backend, StorageBackend::Local);
+        assert_eq!(config.storage.local_root, "/var/lib/matchmaker");
+
+        let err = Config::from_sources(None, lookup(&[("STORAGE_BACKEND", "disk")])).unwrap_err();
+        assert!(err.to_string().contains("STORAGE_BACKEND"));
+    }
+
+    #[test]
+    fn test_example_config_is_complete() {
+        let config = Config::from_sources(
+            Some(include_str!("../config.example.toml")),
+            lookup(&[("SERVICE_KEY", "service-key"), ("OPENAI_API_KEY", "sk-test")]),
+        )
+        .unwrap();
+        assert_eq!(
+            Config {
+                database_url: String::new(),
+                supabase: SupabaseConfig::default(),
+                openai: OpenAIConfig::default(),
+                ..config
+            },
+            Config::default()
+        );
+    }
+
+    #[test]
+    fn test_config_rejects_unknown_keys() {
+        let err = Config::from_sources(Some("[storage]\nbucket = \"resumes\"\n"), lookup(&REQUIRED))
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid config file"));
+    }
 
     #[test]
     fn test_parse_s3_config_cloud() {
         let config = parse_s3_config("https://pkckwgszwgrvxwwdofcj.supabase.co").unwrap();
-        assert_eq!(config.project_ref, "pkckwgszwgrvxwwdofcj");
+        assert_eq!(config.project_ref.as_deref(), Some("pkckwgszwgrvxwwdofcj"));
         assert_eq!(config.endpoint, "https://pkckwgszwgrvxwwdofcj.supabase.co/storage/v1/s3/");
     }
 
     #[test]
     fn test_parse_s3_config_local() {
         let config = parse_s3_config("http://localhost:54321").unwrap();
-        assert_eq!(config.project_ref, "local-stub");
+        assert_eq!(config.project_ref.as_deref(), Some("local-stub"));
         assert_eq!(config.endpoint, "http://localhost:54321/storage/v1/s3/");
     }
 
+    #[test]
+    fn test_parse_s3_config_self_hosted() {
+        let config = parse_s3_config("https://supabase.example.com/").unwrap();
+        assert_eq!(config.project_ref, None);
+        assert_eq!(config.endpoint, "https://supabase.example.com/storage/v1/s3/");
+
+        let config = parse_s3_config("https://example.com:8443/supabase/storage/v1/s3").unwrap();
+        assert_eq!(config.endpoint, "https://example.com:8443/supabase/storage/v1/s3/");
+    }
+
     #[test]
     fn test_parse_s3_config_invalid() {
         let res = parse_s3_config("not-a-url");
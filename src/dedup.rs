@@ -0,0 +1,92 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// What to do with an upload whose file or text matches a resume that was already parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// Complete the upload without creating a `resumes` row.
+    Skip,
+    /// Create a `resumes` row for the upload that copies the original's parsed data
+    /// and embedding, without calling the LLM.
+    #[default]
+    Link,
+    /// Parse the upload again as if it were new, but still record the duplicate.
+    Reprocess,
+}
+
+impl DuplicatePolicy {
+    /// The action recorded in the job's duplicate summary.
+    pub fn action(&self) -> &'static str {
+        match self {
+            Self::Skip => "skipped",
+            Self::Link => "linked",
+            Self::Reprocess => "reprocessed",
+        }
+    }
+}
+
+/// Which hash identified an upload as a duplicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchedOn {
+    /// Byte-for-byte the same file.
+    File,
+    /// A different file with the same text, e.g. a re-exported PDF.
+    Text,
+}
+
+impl MatchedOn {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Text => "text",
+        }
+    }
+}
+
+/// SHA-256 of the uploaded file, hex encoded.
+pub fn file_hash(data: &[u8]) -> String {
+    sha256_hex(data)
+}
+
+/// SHA-256 of the extracted text after lowercasing it and collapsing whitespace, so the
+/// same resume exported twice, or once with OCR, still matches.
+pub fn text_hash(text: &str) -> String {
+    let normalized = text
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ");
+    sha256_hex(normalized.as_bytes())
+}
+
+/// Hex-encoded SHA-256 of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_hash() {
+        assert_eq!(
+            file_hash(b"hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn test_text_hash_ignores_case_and_whitespace() {
+        assert_eq!(
+            text_hash("Alex Rivera\n\nRust  Engineer "),
+            text_hash("alex rivera rust\tengineer")
+        );
+        assert_eq!(text_hash("hello"), file_hash(b"hello"));
+        assert_ne!(text_hash("Alex Rivera"), text_hash("Alex Riviera"));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::dedup::sha256_hex;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+pub const DEFAULT_EMBEDDINGS_ENDPOINT: &str = "https://api.openai.com/v1/embeddings";
+
+#[async_trait]
+pub trait EmbeddingClient: Send + Sync {
+    /// Name of the model that produced the vectors, stored next to each embedding.
+    fn model(&self) -> &str;
+    /// Returns one vector per input, in the same order as `inputs`.
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmbeddingResponse {
+    pub data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmbeddingData {
+    pub index: usize,
+    pub embedding: Vec<f32>,
+}
+
+/// Client for any OpenAI-compatible `/v1/embeddings` endpoint.
+pub struct OpenAIEmbeddingClient {
+    client: reqwest::Client,
+    api_key: String,
+    endpoint: String,
+    model: String,
+}
+
+impl OpenAIEmbeddingClient {
+    pub fn new(client: reqwest::Client, api_key: String, endpoint: String, model: String) -> Self {
+        Self {
+            client,
+            api_key,
+            endpoint,
+            model,
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingClient for OpenAIEmbeddingClient {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let request = EmbeddingRequest {
+            model: &self.model,
+            input: inputs,
+        };
+
+        let mut response = self
+            .client
+            .post(&self.endpoint)
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send request to embeddings endpoint")?
+            .error_for_status()
+            .context("Embeddings endpoint returned an error status")?
+            .json::<EmbeddingResponse>()
+            .await
+            .context("Failed to parse embeddings response")?;
+
+        if response.data.len() != inputs.len() {
+            anyhow::bail!(
+                "Embeddings endpoint returned {} vectors for {} inputs",
+                response.data.len(),
+                inputs.len()
+            );
+        }
+
+        // The API does not guarantee ordering, so sort by the index it echoes back
+        response.data.sort_by_key(|d| d.index);
+        Ok(response.data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+/// Hex-encoded SHA-256 of the text that was embedded, used to skip recomputation.
+pub fn content_hash(text: &str) -> String {
+    sha256_hex(text.as_bytes())
+}
+
+pub fn resume_embedding_input(structured: &Value) -> String {
+    structured.to_string()
+}
+
+pub fn project_embedding_input(title: &str, description: &str, requirements: &str) -> String {
+    format!(
+        "Title: {}\nDescription: {}\nRequirements: {}",
+        title, description, requirements
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(
+            content_hash("hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_ne!(content_hash("hello"), content_hash("hello "));
+    }
+
+    #[test]
+    fn test_project_embedding_input() {
+        let input = project_embedding_input("Cloud Migration", "Move to AWS", "Rust");
+        assert_eq!(
+            input,
+            "Title: Cloud Migration\nDescription: Move to AWS\nRequirements: Rust"
+        );
+    }
+
+    #[test]
+    fn test_resume_embedding_input_changes_with_content() {
+        let a = resume_embedding_input(&json!({"skills": ["Rust"]}));
+        let b = resume_embedding_input(&json!({"skills": ["Rust", "SQL"]}));
+        assert_ne!(content_hash(&a), content_hash(&b));
+    }
+}
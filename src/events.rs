@@ -0,0 +1,222 @@
+use crate::queue::TaskKind;
+use crate::service::{DocumentStatus, JobStatus};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Postgres channel that replicas share job events on.
+pub const NOTIFY_CHANNEL: &str = "job_events";
+/// How many events a slow subscriber may fall behind before it misses some.
+const BUS_CAPACITY: usize = 1024;
+/// Error messages are cut to this many characters so a notification stays well under
+/// Postgres' 8000 byte payload limit.
+const MAX_ERROR_CHARS: usize = 1000;
+
+/// A status transition of a job or one of its uploads.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobEvent {
+    ResumeUpload {
+        job_id: Uuid,
+        id: Uuid,
+        status: DocumentStatus,
+        error: Option<String>,
+    },
+    ZipArchive {
+        job_id: Uuid,
+        id: Uuid,
+        status: DocumentStatus,
+        error: Option<String>,
+    },
+    ProjectUpload {
+        job_id: Uuid,
+        id: Uuid,
+        status: DocumentStatus,
+        error: Option<String>,
+    },
+    Job {
+        job_id: Uuid,
+        status: JobStatus,
+    },
+}
+
+impl JobEvent {
+    /// The status transition of an upload of the given kind.
+    pub fn upload(
+        kind: TaskKind,
+        job_id: Uuid,
+        id: Uuid,
+        status: DocumentStatus,
+        error: Option<String>,
+    ) -> Self {
+        match kind {
+            TaskKind::Resume => Self::ResumeUpload {
+                job_id,
+                id,
+                status,
+                error,
+            },
+            TaskKind::Zip => Self::ZipArchive {
+                job_id,
+                id,
+                status,
+                error,
+            },
+            TaskKind::Project => Self::ProjectUpload {
+                job_id,
+                id,
+                status,
+                error,
+            },
+        }
+    }
+
+    pub fn job_id(&self) -> Uuid {
+        match self {
+            Self::ResumeUpload { job_id, .. }
+            | Self::ZipArchive { job_id, .. }
+            | Self::ProjectUpload { job_id, .. }
+            | Self::Job { job_id, .. } => *job_id,
+        }
+    }
+
+    /// The event name used for Server-Sent Events.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ResumeUpload { .. } => "resume_upload",
+            Self::ZipArchive { .. } => "zip_archive",
+            Self::ProjectUpload { .. } => "project_upload",
+            Self::Job { .. } => "job",
+        }
+    }
+
+    fn truncate_error(&mut self) {
+        if let Self::ResumeUpload { error, .. }
+        | Self::ZipArchive { error, .. }
+        | Self::ProjectUpload { error, .. } = self
+            && let Some(message) = error
+            && let Some((cut, _)) = message.char_indices().nth(MAX_ERROR_CHARS)
+        {
+            message.truncate(cut);
+        }
+    }
+}
+
+/// In-process broadcast of job events. With `notify` set, events are published through
+/// Postgres `NOTIFY` instead and `listen` feeds them back in, so every replica's
+/// subscribers see every replica's events.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<JobEvent>,
+    notify: Option<PgPool>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(BUS_CAPACITY);
+        Self {
+            sender,
+            notify: None,
+        }
+    }
+
+    pub fn with_notify(pool: PgPool) -> Self {
+        Self {
+            notify: Some(pool),
+            ..Self::new()
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Announces an event. Publishing never fails the caller: if the notification can't be
+    /// sent, the event is still delivered to this replica's subscribers.
+    pub async fn publish(&self, mut event: JobEvent) {
+        event.truncate_error();
+        if let Some(pool) = &self.notify {
+            let sent = match serde_json::to_string(&event) {
+                Ok(payload) => sqlx::query("SELECT pg_notify($1, $2)")
+                    .bind(NOTIFY_CHANNEL)
+                    .bind(payload)
+                    .execute(pool)
+                    .await
+                    .map_err(anyhow::Error::from),
+                Err(e) => Err(e.into()),
+            };
+            match sent {
+                Ok(_) => return,
+                Err(e) => tracing::warn!("Failed to notify job event, delivering locally: {}", e),
+            }
+        }
+        // Nobody listening is not an error
+        let _ = self.sender.send(event);
+    }
+
+    /// Forwards events from other replicas, and this one, to local subscribers. Runs until
+    /// the connection fails; `PgListener` reconnects on its own after transient drops.
+    pub async fn listen(self, pool: PgPool) -> anyhow::Result<()> {
+        let mut listener = PgListener::connect_with(&pool).await?;
+        listener.listen(NOTIFY_CHANNEL).await?;
+        loop {
+            let notification = listener.recv().await?;
+            match serde_json::from_str::<JobEvent>(notification.payload()) {
+                Ok(event) => {
+                    let _ = self.sender.send(event);
+                }
+                Err(e) => tracing::warn!("Ignoring malformed job event: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_wire_format() {
+        let job_id = Uuid::new_v4();
+        let event = JobEvent::Job {
+            job_id,
+            status: JobStatus::Ready,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"kind": "job", "job_id": job_id, "status": "ready"})
+        );
+        assert_eq!(serde_json::from_value::<JobEvent>(json).unwrap(), event);
+    }
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers_and_truncates_errors() {
+        let bus = EventBus::new();
+        let mut receiver = bus.subscribe();
+        let job_id = Uuid::new_v4();
+        bus.publish(JobEvent::ResumeUpload {
+            job_id,
+            id: Uuid::new_v4(),
+            status: DocumentStatus::Failed,
+            error: Some("é".repeat(MAX_ERROR_CHARS + 10)),
+        })
+        .await;
+
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.job_id(), job_id);
+        assert_eq!(event.name(), "resume_upload");
+        let JobEvent::ResumeUpload { error, .. } = event else {
+            panic!("unexpected event {:?}", event);
+        };
+        assert_eq!(error.unwrap().chars().count(), MAX_ERROR_CHARS);
+    }
+}
@@ -0,0 +1,509 @@
+pub mod doc;
+pub mod docx;
+pub mod rtf;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use encoding_rs::{Encoding, WINDOWS_1252};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::process::Command;
+
+/// Below this many printable characters per page a PDF is treated as scanned.
+pub const MIN_CHARS_PER_PAGE: f32 = 100.0;
+/// Below this share of printable characters the extracted text is treated as garbage
+/// (e.g. fonts without a usable encoding).
+pub const MIN_PRINTABLE_RATIO: f32 = 0.9;
+
+/// How `resumes.text` was obtained.
+#[derive(Debug, sqlx::Type, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "extraction_method", rename_all = "lowercase")]
+pub enum ExtractionMethod {
+    Text,
+    Ocr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedText {
+    pub text: String,
+    pub method: ExtractionMethod,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextQuality {
+    pub pages: usize,
+    pub chars_per_page: f32,
+    pub printable_ratio: f32,
+}
+
+fn is_printable(c: char) -> bool {
+    c != char::REPLACEMENT_CHARACTER && !c.is_control()
+}
+
+impl TextQuality {
+    /// Measures text extracted page by page. Whitespace is ignored in both metrics.
+    pub fn measure(pages: &[String]) -> Self {
+        let (total, printable) = pages
+            .iter()
+            .flat_map(|p| p.chars())
+            .filter(|c| !c.is_whitespace())
+            .fold((0usize, 0usize), |(total, printable), c| {
+                (total + 1, printable + is_printable(c) as usize)
+            });
+
+        Self {
+            pages: pages.len(),
+            chars_per_page: printable as f32 / pages.len().max(1) as f32,
+            printable_ratio: if total == 0 {
+                0.0
+            } else {
+                printable as f32 / total as f32
+            },
+        }
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.chars_per_page < MIN_CHARS_PER_PAGE || self.printable_ratio < MIN_PRINTABLE_RATIO
+    }
+}
+
+#[async_trait]
+pub trait OcrEngine: Send + Sync {
+    /// Short engine name used in logs.
+    fn name(&self) -> &str;
+    /// Recognizes the text of every page of a PDF.
+    async fn ocr_pdf(&self, pdf_data: &[u8]) -> Result<String>;
+}
+
+/// Runs the `pdftoppm` (poppler-utils) and `tesseract` CLIs: each page is rendered
+/// to a PNG in a temporary directory and then OCR'd.
+pub struct TesseractOcr {
+    tesseract: String,
+    pdftoppm: String,
+    language: String,
+    dpi: u32,
+}
+
+impl TesseractOcr {
+    pub fn new(tesseract: String, pdftoppm: String, language: String) -> Self {
+        Self {
+            tesseract,
+            pdftoppm,
+            language,
+            dpi: 300,
+        }
+    }
+}
+
+async fn run(command: &mut Command, program: &str) -> Result<Vec<u8>> {
+    let output = command
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+#[async_trait]
+impl OcrEngine for TesseractOcr {
+    fn name(&self) -> &str {
+        "tesseract"
+    }
+
+    async fn ocr_pdf(&self, pdf_data: &[u8]) -> Result<String> {
+        let dir = tempfile::tempdir().context("Failed to create OCR working directory")?;
+        let input = dir.path().join("input.pdf");
+        tokio::fs::write(&input, pdf_data).await?;
+
+        run(
+            Command::new(&self.pdftoppm)
+                .arg("-r")
+                .arg(self.dpi.to_string())
+                .arg("-png")
+                .arg(&input)
+                .arg(dir.path().join("page")),
+            &self.pdftoppm,
+        )
+        .await?;
+
+        // pdftoppm zero-pads page numbers, so lexical order is page order
+        let mut images = Vec::new();
+        let mut entries = tokio::fs::read_dir(dir.path()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "png") {
+                images.push(path);
+            }
+        }
+        images.sort();
+
+        let mut text = String::new();
+        for image in images {
+            let page = run(
+                Command::new(&self.tesseract)
+                    .arg(&image)
+                    .arg("stdout")
+                    .arg("-l")
+                    .arg(&self.language),
+                &self.tesseract,
+            )
+            .await?;
+            text.push_str(&String::from_utf8_lossy(&page));
+            text.push('\n');
+        }
+        Ok(text)
+    }
+}
+
+/// Extracts the text layer of a PDF. When it is missing or unusable and an OCR engine
+/// is configured, the PDF is OCR'd instead. Without OCR, low-quality text is still
+/// returned as long as there is some, and a PDF with no text at all is an error.
+pub async fn extract_pdf_text(
+    pdf_data: &[u8],
+    ocr: Option<&dyn OcrEngine>,
+) -> Result<ExtractedText> {
+    let extracted = pdf_extract::extract_text_from_mem_by_pages(pdf_data);
+
+    let text = match &extracted {
+        Ok(pages) => {
+            let quality = TextQuality::measure(pages);
+            if !quality.is_low() {
+                return Ok(ExtractedText {
+                    text: pages.join("\n"),
+                    method: ExtractionMethod::Text,
+                });
+            }
+            tracing::warn!(
+                "PDF text layer looks scanned or unreadable ({} pages, {:.0} chars/page, {:.0}% printable)",
+                quality.pages,
+                quality.chars_per_page,
+                quality.printable_ratio * 100.0
+            );
+            Some(pages.join("\n"))
+        }
+        Err(e) => {
+            tracing::warn!("Failed to extract text layer from PDF: {}", e);
+            None
+        }
+    };
+
+    if let Some(ocr) = ocr {
+        match ocr.ocr_pdf(pdf_data).await {
+            Ok(ocr_text) if !ocr_text.trim().is_empty() => {
+                tracing::info!("Recovered PDF text with {} OCR", ocr.name());
+                return Ok(ExtractedText {
+                    text: ocr_text,
+                    method: ExtractionMethod::Ocr,
+                });
+            }
+            Ok(_) => tracing::warn!("{} OCR found no text in PDF", ocr.name()),
+            Err(e) => tracing::error!("{} OCR failed: {:#}", ocr.name(), e),
+        }
+    }
+
+    match (text, extracted) {
+        (Some(text), _) if !text.trim().is_empty() => Ok(ExtractedText {
+            text,
+            method: ExtractionMethod::Text,
+        }),
+        (_, Err(e)) => Err(e).context("Failed to extract text from PDF"),
+        _ => anyhow::bail!("PDF contains no extractable text"),
+    }
+}
+
+/// Resume formats that can be turned into text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentType {
+    Pdf,
+    Docx,
+    Doc,
+    Rtf,
+    Markdown,
+    PlainText,
+}
+
+impl DocumentType {
+    /// Identifies a file by its content. The filename is only used to tell Markdown
+    /// from plain text, which have no signature of their own. Returns `None` for
+    /// anything else (images, spreadsheets, archives, executables, ...).
+    pub fn detect(data: &[u8], filename: &str) -> Option<Self> {
+        // Reading from memory cannot fail
+        Self::detect_reader(&mut Cursor::new(data), filename).unwrap_or(None)
+    }
+
+    /// `detect` for a file that is not in memory. Only the first KiB is read, or the
+    /// container directory of a DOCX or DOC; files without a signature are read in
+    /// full to check that they are text.
+    pub fn detect_reader<R: Read + Seek>(
+        reader: &mut R,
+        filename: &str,
+    ) -> std::io::Result<Option<Self>> {
+        reader.rewind()?;
+        let mut head = Vec::with_capacity(1024);
+        reader.by_ref().take(1024).read_to_end(&mut head)?;
+        reader.rewind()?;
+
+        // Some producers put junk before the header; readers accept it within the first 1 KiB
+        if head.windows(5).any(|w| w == b"%PDF-") {
+            return Ok(Some(Self::Pdf));
+        }
+        if head.starts_with(b"PK\x03\x04") {
+            return Ok(docx::is_docx(reader).then_some(Self::Docx));
+        }
+        if head.starts_with(doc::OLE_MAGIC) {
+            return Ok(doc::is_doc(reader).then_some(Self::Doc));
+        }
+        if head.starts_with(b"{\\rtf") {
+            return Ok(Some(Self::Rtf));
+        }
+        let mut data = head;
+        reader.seek(SeekFrom::Start(data.len() as u64))?;
+        reader.read_to_end(&mut data)?;
+        if decode_text(&data).is_none() {
+            return Ok(None);
+        }
+
+        let lower = filename.to_lowercase();
+        if lower.ends_with(".md") || lower.ends_with(".markdown") {
+            Ok(Some(Self::Markdown))
+        } else {
+            Ok(Some(Self::PlainText))
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pdf => "PDF",
+            Self::Docx => "DOCX",
+            Self::Doc => "DOC",
+            Self::Rtf => "RTF",
+            Self::Markdown => "Markdown",
+            Self::PlainText => "plain text",
+        }
+    }
+}
+
+/// Decodes a plain-text file: UTF-8 or UTF-16 with a BOM, otherwise UTF-8 with a
+/// Windows-1252 fallback. Returns `None` for binary data, i.e. anything containing
+/// control characters other than whitespace.
+pub fn decode_text(data: &[u8]) -> Option<String> {
+    let text = match Encoding::for_bom(data) {
+        Some((encoding, bom_len)) => encoding
+            .decode_without_bom_handling(&data[bom_len..])
+            .0
+            .into_owned(),
+        None => match std::str::from_utf8(data) {
+            Ok(text) => text.to_string(),
+            Err(_) => WINDOWS_1252.decode_without_bom_handling(data).0.into_owned(),
+        },
+    };
+
+    text.chars()
+        .all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r' | '\u{0c}'))
+        .then_some(text)
+}
+
+/// Extracts the text of a resume in any supported format. PDFs go through
+/// `extract_pdf_text`, including its OCR fallback. `max_part_bytes` caps what the parts of
+/// a DOCX package may unpack to, normally `ArchiveLimits::max_entry_bytes`.
+pub async fn extract_text(
+    data: &[u8],
+    filename: &str,
+    ocr: Option<&dyn OcrEngine>,
+    max_part_bytes: u64,
+) -> Result<ExtractedText> {
+    let Some(document_type) = DocumentType::detect(data, filename) else {
+        anyhow::bail!("Unsupported file type");
+    };
+
+    let text = match document_type {
+        DocumentType::Pdf => return extract_pdf_text(data, ocr).await,
+        DocumentType::Docx => docx::extract_text(data, max_part_bytes)?,
+        DocumentType::Doc => doc::extract_text(data)?,
+        DocumentType::Rtf => rtf::extract_text(data)?,
+        DocumentType::Markdown | DocumentType::PlainText => {
+            decode_text(data).context("File is not valid text")?
+        }
+    };
+
+    if text.trim().is_empty() {
+        anyhow::bail!("{} file contains no text", document_type.name());
+    }
+    Ok(ExtractedText {
+        text,
+        method: ExtractionMethod::Text,
+    })
+}
+
+/// OCR engine for tests that returns fixed text and counts how often it was called.
+pub struct MockOcrEngine {
+    text: String,
+    pub calls: AtomicUsize,
+}
+
+impl MockOcrEngine {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            calls: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[async_trait]
+impl OcrEngine for MockOcrEngine {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn ocr_pdf(&self, _pdf_data: &[u8]) -> Result<String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(self.text.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A one-page PDF with no text layer, like a scan without its image.
+    fn blank_pdf() -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Resources << >> /Contents 4 0 R >>",
+            "<< /Length 0 >>\nstream\n\nendstream",
+        ];
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::new();
+        for (i, body) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, body));
+        }
+        let xref = pdf.len();
+        pdf.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+        for offset in offsets {
+            pdf.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        pdf.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        ));
+        pdf.into_bytes()
+    }
+
+    #[test]
+    fn test_quality_flags_sparse_and_garbled_text() {
+        let good = TextQuality::measure(&["Experienced Rust developer. ".repeat(10)]);
+        assert!(!good.is_low());
+
+        let sparse = TextQuality::measure(&["Page 1".to_string(), String::new()]);
+        assert_eq!(sparse.pages, 2);
+        assert!(sparse.is_low());
+
+        let garbled = TextQuality::measure(&["\u{fffd}\u{1}abc".repeat(100)]);
+        assert!(garbled.printable_ratio < MIN_PRINTABLE_RATIO);
+        assert!(garbled.is_low());
+
+        assert!(TextQuality::measure(&[]).is_low());
+    }
+
+    #[tokio::test]
+    async fn test_text_pdf_skips_ocr() {
+        let pdf = std::fs::read("archive.zip-resumes/Alex_Rivera_CV.pdf").unwrap();
+        let ocr = MockOcrEngine::new("ocr text");
+        let extracted = extract_pdf_text(&pdf, Some(&ocr)).await.unwrap();
+        assert_eq!(extracted.method, ExtractionMethod::Text);
+        assert!(extracted.text.contains("Alex"));
+        assert_eq!(ocr.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_blank_pdf_falls_back_to_ocr() {
+        let ocr = MockOcrEngine::new("Jordan Lee\nPython, SQL");
+        let extracted = extract_pdf_text(&blank_pdf(), Some(&ocr)).await.unwrap();
+        assert_eq!(extracted.method, ExtractionMethod::Ocr);
+        assert_eq!(extracted.text, "Jordan Lee\nPython, SQL");
+        assert_eq!(ocr.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_blank_pdf_without_ocr_is_an_error() {
+        let err = extract_pdf_text(&blank_pdf(), None).await.unwrap_err();
+        assert!(err.to_string().contains("no extractable text"));
+
+        let empty_ocr = MockOcrEngine::new("  \n");
+        assert!(extract_pdf_text(&blank_pdf(), Some(&empty_ocr)).await.is_err());
+    }
+
+    #[test]
+    fn test_detects_document_type_by_content() {
+        let pdf = std::fs::read("archive.zip-resumes/Alex_Rivera_CV.pdf").unwrap();
+        // Content wins over a misleading extension
+        assert_eq!(DocumentType::detect(&pdf, "resume.docx"), Some(DocumentType::Pdf));
+        assert_eq!(
+            DocumentType::detect(&docx::tests::docx("<w:p/>"), "resume.pdf"),
+            Some(DocumentType::Docx)
+        );
+        assert_eq!(
+            DocumentType::detect(&doc::tests::doc("Jordan\r", ""), "resume"),
+            Some(DocumentType::Doc)
+        );
+        assert_eq!(
+            DocumentType::detect(b"{\\rtf1 Jordan}", "resume.txt"),
+            Some(DocumentType::Rtf)
+        );
+        assert_eq!(
+            DocumentType::detect(b"# Jordan Lee\n- Rust", "README.MD"),
+            Some(DocumentType::Markdown)
+        );
+        assert_eq!(
+            DocumentType::detect("Zo\u{eb} Smith\r\n".as_bytes(), "cv.txt"),
+            Some(DocumentType::PlainText)
+        );
+
+        // PNG, a plain ZIP and an Excel-style compound file are not resumes
+        assert_eq!(DocumentType::detect(b"\x89PNG\r\n\x1a\n\0\0", "photo.pdf"), None);
+        assert_eq!(DocumentType::detect(b"PK\x03\x04garbage", "cv.docx"), None);
+        assert_eq!(DocumentType::detect(doc::OLE_MAGIC, "cv.doc"), None);
+    }
+
+    #[test]
+    fn test_decode_text_handles_boms_and_legacy_encodings() {
+        assert_eq!(decode_text(b"\xef\xbb\xbfCaf\xc3\xa9").as_deref(), Some("Café"));
+        assert_eq!(decode_text(b"\xff\xfeC\0a\0f\0\xe9\0").as_deref(), Some("Café"));
+        assert_eq!(decode_text(b"Caf\xe9\tBar").as_deref(), Some("Café\tBar"));
+        assert_eq!(decode_text(b"ELF\0\x01\x02"), None);
+    }
+
+    const LIMIT: u64 = 1024 * 1024;
+
+    #[tokio::test]
+    async fn test_extract_text_dispatches_on_type() {
+        let extracted = extract_text(b"Jordan Lee\nPython, SQL", "cv.txt", None, LIMIT)
+            .await
+            .unwrap();
+        assert_eq!(extracted.text, "Jordan Lee\nPython, SQL");
+        assert_eq!(extracted.method, ExtractionMethod::Text);
+
+        let data = docx::tests::docx("<w:p><w:r><w:t>Jordan Lee</w:t></w:r></w:p>");
+        let extracted = extract_text(&data, "cv.docx", None, LIMIT).await.unwrap();
+        assert_eq!(extracted.text, "Jordan Lee\n");
+
+        let err = extract_text(b"\x89PNG\r\n\x1a\n\0", "cv.png", None, LIMIT)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Unsupported file type");
+
+        let err = extract_text(b" \n ", "cv.md", None, LIMIT).await.unwrap_err();
+        assert_eq!(err.to_string(), "Markdown file contains no text");
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{Context, Result};
+use encoding_rs::WINDOWS_1252;
+use std::io::{Cursor, Read, Seek};
+
+/// Signature of an OLE2 compound file, the container of Word 97-2003 documents.
+pub const OLE_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+const WORD_IDENT: u16 = 0xA5EC;
+const F_ENCRYPTED: u16 = 0x0100;
+const F_WHICH_TBL_STM: u16 = 0x0200;
+/// Index of `ccpText` in `FibRgLw97`.
+const CCP_TEXT_INDEX: usize = 3;
+/// Index of the `fcClx`/`lcbClx` pair in `FibRgFcLcb97`.
+const CLX_INDEX: usize = 33;
+
+/// Whether an OLE2 file is a Word document rather than e.g. an Excel workbook.
+pub fn is_doc(reader: impl Read + Seek) -> bool {
+    cfb::CompoundFile::open(reader).is_ok_and(|file| file.is_stream("WordDocument"))
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .context("Word document is truncated")
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .context("Word document is truncated")
+}
+
+fn read_stream(file: &mut cfb::CompoundFile<Cursor<&[u8]>>, name: &str) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    file.open_stream(name)
+        .with_context(|| format!("Word document has no {} stream", name))?
+        .read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Extracts the main document text of a Word 97-2003 (`.doc`) file by walking
+/// the piece table. Headers, footnotes and field instructions are left out.
+pub fn extract_text(data: &[u8]) -> Result<String> {
+    let mut file =
+        cfb::CompoundFile::open(Cursor::new(data)).context("Not an OLE2 compound file")?;
+    let word = read_stream(&mut file, "WordDocument")?;
+
+    if u16_at(&word, 0)? != WORD_IDENT {
+        anyhow::bail!("WordDocument stream has an unknown signature");
+    }
+    let flags = u16_at(&word, 0x0A)?;
+    if flags & F_ENCRYPTED != 0 {
+        anyhow::bail!("Word document is password protected");
+    }
+
+    // The FIB is a fixed header followed by three variable-length arrays
+    let csw = u16_at(&word, 32)? as usize;
+    let rg_lw = 32 + 2 + csw * 2 + 2;
+    let cslw = u16_at(&word, rg_lw - 2)? as usize;
+    let rg_fc_lcb = rg_lw + cslw * 4 + 2;
+    if cslw <= CCP_TEXT_INDEX || (u16_at(&word, rg_fc_lcb - 2)? as usize) <= CLX_INDEX {
+        anyhow::bail!("Word document header is too short");
+    }
+    let ccp_text = u32_at(&word, rg_lw + CCP_TEXT_INDEX * 4)?;
+    let fc_clx = u32_at(&word, rg_fc_lcb + CLX_INDEX * 8)? as usize;
+    let lcb_clx = u32_at(&word, rg_fc_lcb + CLX_INDEX * 8 + 4)? as usize;
+
+    let table_name = if flags & F_WHICH_TBL_STM != 0 {
+        "1Table"
+    } else {
+        "0Table"
+    };
+    let table = read_stream(&mut file, table_name)?;
+    let clx = table
+        .get(fc_clx..fc_clx + lcb_clx)
+        .context("Piece table lies outside the table stream")?;
+
+    let mut raw = String::new();
+    let plc = piece_table(clx)?;
+    let pieces = (plc.len() - 4) / 12;
+    let pcds = 4 * (pieces + 1);
+    for k in 0..pieces {
+        let start = u32_at(plc, k * 4)?.min(ccp_text);
+        let end = u32_at(plc, (k + 1) * 4)?.min(ccp_text);
+        if end <= start {
+            continue;
+        }
+        let count = (end - start) as usize;
+
+        let fc = u32_at(plc, pcds + k * 8 + 2)?;
+        // Bit 30 marks 8-bit Windows-1252 text stored at half the offset
+        if fc & 0x4000_0000 != 0 {
+            let offset = ((fc & 0x3FFF_FFFF) / 2) as usize;
+            let bytes = word
+                .get(offset..offset + count)
+                .context("Text piece lies outside the WordDocument stream")?;
+            raw.push_str(&WINDOWS_1252.decode_without_bom_handling(bytes).0);
+        } else {
+            let offset = fc as usize;
+            let bytes = word
+                .get(offset..offset + count * 2)
+                .context("Text piece lies outside the WordDocument stream")?;
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                .collect();
+            raw.push_str(&String::from_utf16_lossy(&units));
+        }
+
+        if end == ccp_text {
+            break;
+        }
+    }
+
+    Ok(clean(&raw))
+}
+
+/// Skips the formatting runs (`Prc`) at the start of a `Clx` and returns the `PlcPcd`.
+fn piece_table(clx: &[u8]) -> Result<&[u8]> {
+    let mut i = 0;
+    loop {
+        match clx.get(i) {
+            Some(0x01) => i += 3 + u16_at(clx, i + 1)? as usize,
+            Some(0x02) => {
+                let lcb = u32_at(clx, i + 1)? as usize;
+                let plc = clx
+                    .get(i + 5..i + 5 + lcb)
+                    .context("Piece table is truncated")?;
+                if lcb < 16 || !(lcb - 4).is_multiple_of(12) {
+                    anyhow::bail!("Piece table has an invalid size");
+                }
+                return Ok(plc);
+            }
+            _ => anyhow::bail!("Word document has no piece table"),
+        }
+    }
+}
+
+/// Maps Word's special characters to plain text and drops field instructions,
+/// keeping the displayed field result.
+fn clean(raw: &str) -> String {
+    let mut text = String::with_capacity(raw.len());
+    // One entry per open field: whether its result (after the separator) is showing
+    let mut fields: Vec<bool> = Vec::new();
+
+    for c in raw.chars() {
+        match c {
+            '\u{13}' => fields.push(false),
+            '\u{14}' => {
+                if let Some(showing) = fields.last_mut() {
+                    *showing = true;
+                }
+            }
+            '\u{15}' => {
+                fields.pop();
+            }
+            _ if !fields.iter().all(|&showing| showing) => {}
+            '\r' | '\u{0b}' | '\u{0c}' => text.push('\n'),
+            '\u{07}' => text.push('\t'),
+            '\u{1e}' => text.push('-'),
+            '\t' => text.push('\t'),
+            c if c.is_control() => {}
+            c => text.push(c),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Builds a Word 97 file whose text is stored as one 8-bit piece followed
+    /// by one UTF-16 piece.
+    pub(crate) fn doc(compressed: &str, unicode: &str) -> Vec<u8> {
+        let ccp_compressed = compressed.chars().count() as u32;
+        let units: Vec<u16> = unicode.encode_utf16().collect();
+        let ccp_text = ccp_compressed + units.len() as u32;
+
+        // FibBase, no FibRgW, FibRgLw with ccpText, FibRgFcLcb up to fcClx/lcbClx
+        let cslw = CCP_TEXT_INDEX + 1;
+        let pairs = CLX_INDEX + 1;
+        let fib_len = 32 + 2 + 2 + cslw * 4 + 2 + pairs * 8;
+        let compressed_at = fib_len as u32;
+        let unicode_at = compressed_at + ccp_compressed;
+
+        let mut plc = Vec::new();
+        for cp in [0, ccp_compressed, ccp_text] {
+            plc.extend(cp.to_le_bytes());
+        }
+        for fc in [(compressed_at * 2) | 0x4000_0000, unicode_at] {
+            plc.extend([0u8, 0]);
+            plc.extend(fc.to_le_bytes());
+            plc.extend([0u8, 0]);
+        }
+        let mut table = vec![0x01, 0x02, 0x00, 0xAA, 0xBB, 0x02];
+        table.extend((plc.len() as u32).to_le_bytes());
+        table.extend(plc);
+
+        let mut word = Vec::new();
+        word.extend(WORD_IDENT.to_le_bytes());
+        word.resize(0x0A, 0);
+        word.extend(F_WHICH_TBL_STM.to_le_bytes());
+        word.resize(32, 0);
+        word.extend(0u16.to_le_bytes());
+        word.extend((cslw as u16).to_le_bytes());
+        for i in 0..cslw {
+            word.extend(if i == CCP_TEXT_INDEX { ccp_text } else { 0 }.to_le_bytes());
+        }
+        word.extend((pairs as u16).to_le_bytes());
+        word.resize(fib_len - 8, 0);
+        word.extend(0u32.to_le_bytes());
+        word.extend((table.len() as u32).to_le_bytes());
+        word.extend(WINDOWS_1252.encode(compressed).0.iter());
+        word.extend(units.iter().flat_map(|u| u.to_le_bytes()));
+
+        let mut file = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        file.create_stream("WordDocument")
+            .unwrap()
+            .write_all(&word)
+            .unwrap();
+        file.create_stream("1Table")
+            .unwrap()
+            .write_all(&table)
+            .unwrap();
+        file.flush().unwrap();
+        file.into_inner().into_inner()
+    }
+
+    #[test]
+    fn test_extracts_compressed_and_unicode_pieces() {
+        let data = doc(
+            "Jordan Lee\rCafé\x07Barista\x07\r",
+            "Portfolio: \u{13} HYPERLINK \"https://x.dev\" \u{14}x.dev\u{15}\rZoë – 日本\r",
+        );
+        assert!(data.starts_with(OLE_MAGIC));
+        assert!(is_doc(Cursor::new(&data)));
+        assert_eq!(
+            extract_text(&data).unwrap(),
+            "Jordan Lee\nCafé\tBarista\t\nPortfolio: x.dev\nZoë – 日本\n"
+        );
+    }
+
+    #[test]
+    fn test_rejects_non_word_compound_file() {
+        let mut file = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        file.create_stream("Workbook")
+            .unwrap()
+            .write_all(b"xls")
+            .unwrap();
+        file.flush().unwrap();
+        let data = file.into_inner().into_inner();
+        assert!(!is_doc(Cursor::new(&data)));
+        assert!(extract_text(&data).is_err());
+    }
+}
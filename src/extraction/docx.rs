@@ -0,0 +1,171 @@
+use anyhow::{Context, Result};
+use quick_xml::Reader;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+use std::io::{Cursor, Read, Seek};
+
+/// Part of the package that holds the main document body.
+pub const DOCUMENT_PART: &str = "word/document.xml";
+
+/// Whether a ZIP container is a Word document rather than an arbitrary archive.
+pub fn is_docx(reader: impl Read + Seek) -> bool {
+    zip::ZipArchive::new(reader)
+        .is_ok_and(|mut archive| archive.by_name(DOCUMENT_PART).is_ok())
+}
+
+/// Extracts the body text of a DOCX file. Paragraphs, line breaks and table rows
+/// become newlines and table cells are separated by tabs; headers, footers and
+/// comments live in other parts and are ignored. The document part may unpack to at
+/// most `max_bytes`, so a small file can't expand into gigabytes of XML.
+pub fn extract_text(data: &[u8], max_bytes: u64) -> Result<String> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(data)).context("DOCX is not a valid ZIP package")?;
+    let part = archive
+        .by_name(DOCUMENT_PART)
+        .with_context(|| format!("DOCX has no {}", DOCUMENT_PART))?;
+    if part.size() > max_bytes {
+        anyhow::bail!(
+            "DOCX {} unpacks to {} bytes, more than the limit of {}",
+            DOCUMENT_PART,
+            part.size(),
+            max_bytes
+        );
+    }
+    // The declared size may lie, so the read is capped as well
+    let mut xml = String::new();
+    part.take(max_bytes + 1)
+        .read_to_string(&mut xml)
+        .with_context(|| format!("Failed to read {}", DOCUMENT_PART))?;
+    if xml.len() as u64 > max_bytes {
+        anyhow::bail!(
+            "DOCX {} unpacks to more than the limit of {} bytes",
+            DOCUMENT_PART,
+            max_bytes
+        );
+    }
+    document_text(&xml)
+}
+
+fn document_text(xml: &str) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut text = String::new();
+    // Only character data inside <w:t> is document text; everything else is markup
+    let mut in_text = false;
+    // <w:tab/> also appears in paragraph properties as a tab stop, outside any run
+    let mut in_run = false;
+    // Paragraphs inside a table cell are joined with spaces so each row stays on one line
+    let mut cell_depth = 0usize;
+
+    loop {
+        match reader.read_event().context("Malformed DOCX document XML")? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"t" => in_text = true,
+                b"r" => in_run = true,
+                b"tc" => cell_depth += 1,
+                _ => {}
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"r" => in_run = false,
+                b"p" if cell_depth > 0 => text.push(' '),
+                b"p" => text.push('\n'),
+                b"tc" => {
+                    cell_depth = cell_depth.saturating_sub(1);
+                    text.truncate(text.trim_end_matches(' ').len());
+                    text.push('\t');
+                }
+                b"tr" => {
+                    if text.ends_with('\t') {
+                        text.pop();
+                    }
+                    text.push('\n');
+                }
+                _ => {}
+            },
+            Event::Empty(e) if in_run => match e.local_name().as_ref() {
+                b"tab" => text.push('\t'),
+                b"br" | b"cr" => text.push('\n'),
+                _ => {}
+            },
+            Event::Text(e) if in_text => text.push_str(&e.xml_content()?),
+            Event::CData(e) if in_text => text.push_str(&e.xml_content()?),
+            Event::GeneralRef(e) if in_text => {
+                if let Some(c) = e.resolve_char_ref()? {
+                    text.push(c);
+                } else if let Some(entity) = resolve_predefined_entity(&e.decode()?) {
+                    text.push_str(entity);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Wraps `body` (the children of `<w:body>`) in a minimal DOCX package.
+    pub(crate) fn docx(body: &str) -> Vec<u8> {
+        let mut buf = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(Cursor::new(&mut buf));
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file("[Content_Types].xml", options).unwrap();
+            zip.write_all(b"<?xml version=\"1.0\"?><Types/>").unwrap();
+            zip.start_file(DOCUMENT_PART, options).unwrap();
+            write!(
+                zip,
+                "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+                 <w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\
+                 <w:body>{}</w:body></w:document>",
+                body
+            )
+            .unwrap();
+            zip.finish().unwrap();
+        }
+        buf
+    }
+
+    #[test]
+    fn test_extracts_paragraphs_tabs_and_tables() {
+        let data = docx(
+            "<w:p><w:r><w:t>Jordan Lee</w:t></w:r></w:p>\
+             <w:p><w:r><w:t xml:space=\"preserve\">Rust </w:t></w:r><w:r><w:t>&amp; SQL</w:t></w:r></w:p>\
+             <w:p><w:pPr><w:tabs><w:tab w:val=\"left\" w:pos=\"720\"/></w:tabs></w:pPr><w:r><w:t>2024</w:t><w:tab/><w:t>Intern</w:t><w:br/><w:t>Acme</w:t></w:r></w:p>\
+             <w:tbl><w:tr><w:tc><w:p><w:r><w:t>A</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>B</w:t></w:r></w:p></w:tc></w:tr></w:tbl>\
+             <w:sectPr><w:pgSz w:w=\"12240\"/></w:sectPr>",
+        );
+
+        assert!(is_docx(Cursor::new(&data)));
+        assert_eq!(
+            extract_text(&data, 1024 * 1024).unwrap(),
+            "Jordan Lee\nRust & SQL\n2024\tIntern\nAcme\nA\tB\n"
+        );
+    }
+
+    #[test]
+    fn test_document_part_size_is_limited() {
+        let data = docx(&"<w:p><w:r><w:t>Rust</w:t></w:r></w:p>".repeat(1000));
+        assert!(extract_text(&data, 64 * 1024).is_ok());
+        let err = extract_text(&data, 1024).unwrap_err();
+        assert!(err.to_string().contains("limit of 1024"), "{}", err);
+    }
+
+    #[test]
+    fn test_plain_zip_is_not_docx() {
+        let mut buf = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(Cursor::new(&mut buf));
+            zip.start_file("resume.pdf", zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(b"%PDF-1.4").unwrap();
+            zip.finish().unwrap();
+        }
+        assert!(!is_docx(Cursor::new(&buf)));
+        assert!(extract_text(&buf, 1024).is_err());
+    }
+}
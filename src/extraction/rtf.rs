@@ -0,0 +1,224 @@
+use anyhow::Result;
+use encoding_rs::WINDOWS_1252;
+
+/// Destinations whose content is metadata or embedded data, never body text.
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "listtable",
+    "listoverridetable",
+    "revtbl",
+    "rsidtbl",
+    "filetbl",
+    "info",
+    "pict",
+    "object",
+    "themedata",
+    "colorschememapping",
+    "datastore",
+    "latentstyles",
+    "xmlnstbl",
+    "generator",
+    "fldinst",
+    "bkmkstart",
+    "bkmkend",
+    "header",
+    "headerl",
+    "headerr",
+    "headerf",
+    "footer",
+    "footerl",
+    "footerr",
+    "footerf",
+];
+
+#[derive(Clone, Copy)]
+struct Group {
+    skip: bool,
+    /// Number of fallback characters that follow each `\u` escape (`\ucN`).
+    unicode_skip: usize,
+}
+
+/// Extracts the visible text of an RTF document. Hex escapes are decoded as
+/// Windows-1252, which is what resume tools emit in practice.
+pub fn extract_text(data: &[u8]) -> Result<String> {
+    if !data.starts_with(b"{\\rtf") {
+        anyhow::bail!("Not an RTF document");
+    }
+
+    let mut text = String::new();
+    let mut stack = Vec::new();
+    let mut group = Group {
+        skip: false,
+        unicode_skip: 1,
+    };
+    // Fallback characters still to be dropped after a `\u` escape
+    let mut pending_skip = 0usize;
+    // High half of a UTF-16 surrogate pair written as two `\u` escapes
+    let mut high_surrogate: Option<u32> = None;
+    let mut i = 0;
+
+    while i < data.len() {
+        match data[i] {
+            b'{' => {
+                stack.push(group);
+                pending_skip = 0;
+                i += 1;
+            }
+            b'}' => {
+                group = stack.pop().unwrap_or(group);
+                pending_skip = 0;
+                i += 1;
+            }
+            b'\\' => {
+                i += 1;
+                let Some(&next) = data.get(i) else { break };
+
+                if next.is_ascii_alphabetic() {
+                    let start = i;
+                    while i < data.len() && data[i].is_ascii_alphabetic() {
+                        i += 1;
+                    }
+                    let word = std::str::from_utf8(&data[start..i]).unwrap_or_default();
+
+                    let param_start = i;
+                    if data.get(i) == Some(&b'-') {
+                        i += 1;
+                    }
+                    while i < data.len() && data[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                    let param = std::str::from_utf8(&data[param_start..i])
+                        .ok()
+                        .and_then(|p| p.parse::<i32>().ok());
+                    // A single space delimits the control word and is not text
+                    if data.get(i) == Some(&b' ') {
+                        i += 1;
+                    }
+
+                    match word {
+                        "par" | "line" | "sect" | "page" | "row" => {
+                            emit(&mut text, &mut pending_skip, &group, '\n')
+                        }
+                        "tab" | "cell" => emit(&mut text, &mut pending_skip, &group, '\t'),
+                        "emdash" => emit(&mut text, &mut pending_skip, &group, '—'),
+                        "endash" => emit(&mut text, &mut pending_skip, &group, '–'),
+                        "bullet" => emit(&mut text, &mut pending_skip, &group, '•'),
+                        "lquote" => emit(&mut text, &mut pending_skip, &group, '‘'),
+                        "rquote" => emit(&mut text, &mut pending_skip, &group, '’'),
+                        "ldblquote" => emit(&mut text, &mut pending_skip, &group, '“'),
+                        "rdblquote" => emit(&mut text, &mut pending_skip, &group, '”'),
+                        "uc" => group.unicode_skip = param.unwrap_or(1).max(0) as usize,
+                        "u" => {
+                            if let Some(code) = param {
+                                // Code points above 32767 are written as negative numbers
+                                let code = if code < 0 { code + 65536 } else { code } as u32;
+                                let c = match (high_surrogate.take(), code) {
+                                    (None, 0xD800..=0xDBFF) => {
+                                        high_surrogate = Some(code);
+                                        None
+                                    }
+                                    (Some(high), 0xDC00..=0xDFFF) => char::from_u32(
+                                        0x10000 + ((high - 0xD800) << 10) + (code - 0xDC00),
+                                    ),
+                                    _ => Some(
+                                        char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER),
+                                    ),
+                                };
+                                if let Some(c) = c
+                                    && !group.skip
+                                {
+                                    text.push(c);
+                                }
+                                pending_skip = group.unicode_skip;
+                            }
+                        }
+                        w if SKIPPED_DESTINATIONS.contains(&w) => group.skip = true,
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                i += 1;
+                match next {
+                    b'\'' => {
+                        let byte = data
+                            .get(i..i + 2)
+                            .and_then(|hex| std::str::from_utf8(hex).ok())
+                            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                        if let Some(byte) = byte {
+                            i += 2;
+                            emit(&mut text, &mut pending_skip, &group, decode_byte(byte));
+                        }
+                    }
+                    b'*' => group.skip = true,
+                    b'~' => emit(&mut text, &mut pending_skip, &group, ' '),
+                    b'_' => emit(&mut text, &mut pending_skip, &group, '-'),
+                    b'\n' | b'\r' => emit(&mut text, &mut pending_skip, &group, '\n'),
+                    b'\\' | b'{' | b'}' => emit(&mut text, &mut pending_skip, &group, next as char),
+                    // Optional hyphens and other control symbols carry no text
+                    _ => {}
+                }
+            }
+            // Raw line breaks are formatting only; paragraphs are marked with \par
+            b'\r' | b'\n' => i += 1,
+            byte => {
+                emit(&mut text, &mut pending_skip, &group, decode_byte(byte));
+                i += 1;
+            }
+        }
+    }
+
+    Ok(text)
+}
+
+fn emit(text: &mut String, pending_skip: &mut usize, group: &Group, c: char) {
+    if *pending_skip > 0 {
+        *pending_skip -= 1;
+    } else if !group.skip {
+        text.push(c);
+    }
+}
+
+fn decode_byte(byte: u8) -> char {
+    if byte.is_ascii() {
+        return byte as char;
+    }
+    WINDOWS_1252
+        .decode_without_bom_handling(&[byte])
+        .0
+        .chars()
+        .next()
+        .unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_body_text() {
+        let rtf = br"{\rtf1\ansi\ansicpg1252\deff0{\fonttbl{\f0\fswiss Helvetica;}}{\colortbl;\red0\green0\blue0;}
+{\*\generator Riched20 10.0;}{\info{\title Resume}{\author HR}}
+\pard\f0\fs24 {\b Jordan Lee}\par
+Caf\'e9 barista \endash  2023\tab Remote\line
+Skills: Rust, SQL \{and more\}\par
+}";
+        assert_eq!(
+            extract_text(rtf).unwrap(),
+            "Jordan Lee\nCafé barista – 2023\tRemote\nSkills: Rust, SQL {and more}\n"
+        );
+    }
+
+    #[test]
+    fn test_unicode_escapes_skip_fallback_characters() {
+        let rtf = br"{\rtf1\uc1 Zo\u235?\'80 {\uc2 \u-10179\'3f\'3f\u-8704??}}";
+        assert_eq!(extract_text(rtf).unwrap(), "Zoë€ \u{1f600}");
+    }
+
+    #[test]
+    fn test_rejects_non_rtf() {
+        assert!(extract_text(b"plain text").is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use serde_json::Value;
+
+/// Who a resume belongs to, taken from its structured `contact` block and normalized so
+/// that two resumes of the same intern compare equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Identity {
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub name: Option<String>,
+}
+
+/// How strongly an intern record matches a resume's identity. A shared name is never
+/// enough on its own, since two interns can have the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IdentityMatch {
+    Phone,
+    Email,
+}
+
+/// Phone numbers are compared on their last ten digits, so `+1 (555) 010-2000` and
+/// `555.010.2000` are the same number.
+const PHONE_DIGITS: usize = 10;
+/// Shorter digit runs are extensions or typos rather than phone numbers.
+const MIN_PHONE_DIGITS: usize = 7;
+
+impl Identity {
+    pub fn from_structured(structured: &Value) -> Self {
+        let contact = &structured["contact"];
+        let field = |key: &str| contact[key].as_str().map(str::trim).filter(|s| !s.is_empty());
+        Self {
+            email: field("email").and_then(normalize_email),
+            phone: field("phone").and_then(normalize_phone),
+            name: field("name").and_then(normalize_name),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.email.is_none() && self.phone.is_none() && self.name.is_none()
+    }
+
+    /// Whether there is an email or phone to match interns on; a name alone can't tell two
+    /// interns apart.
+    pub fn has_contact(&self) -> bool {
+        self.email.is_some() || self.phone.is_some()
+    }
+
+    /// Compares this resume's identity with an intern's. Email beats phone; the name only
+    /// labels the intern, so a resume whose email and phone match nobody gets a new intern
+    /// even when someone with the same name already exists.
+    pub fn matches(&self, intern: &Identity) -> Option<IdentityMatch> {
+        let same = |a: &Option<String>, b: &Option<String>| a.is_some() && a == b;
+
+        if same(&self.email, &intern.email) {
+            Some(IdentityMatch::Email)
+        } else if same(&self.phone, &intern.phone) {
+            Some(IdentityMatch::Phone)
+        } else {
+            None
+        }
+    }
+}
+
+fn normalize_email(email: &str) -> Option<String> {
+    let email = email.trim_start_matches("mailto:").to_lowercase();
+    let (local, domain) = email.split_once('@')?;
+    (!local.is_empty() && domain.contains('.')).then_some(email)
+}
+
+fn normalize_phone(phone: &str) -> Option<String> {
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+    if digits.len() < MIN_PHONE_DIGITS {
+        return None;
+    }
+    Some(digits[digits.len().saturating_sub(PHONE_DIGITS)..].to_string())
+}
+
+fn normalize_name(name: &str) -> Option<String> {
+    let name = name
+        .split(|c: char| c.is_whitespace() || c == '.' || c == ',')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn identity(email: Option<&str>, phone: Option<&str>, name: Option<&str>) -> Identity {
+        Identity {
+            email: email.map(String::from),
+            phone: phone.map(String::from),
+            name: name.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_from_structured_normalizes_contact() {
+        let structured = json!({"contact": {
+            "name": "  Alex  J. Rivera ",
+            "email": "mailto:Alex.Rivera@Example.COM",
+            "phone": "+1 (555) 010-2000",
+            "location": null
+        }});
+        assert_eq!(
+            Identity::from_structured(&structured),
+            identity(
+                Some("alex.rivera@example.com"),
+                Some("5550102000"),
+                Some("alex j rivera")
+            )
+        );
+    }
+
+    #[test]
+    fn test_from_structured_drops_unusable_fields() {
+        let structured = json!({"contact": {"name": "", "email": "n/a", "phone": "x123"}});
+        assert!(Identity::from_structured(&structured).is_empty());
+        assert!(Identity::from_structured(&json!({})).is_empty());
+        assert!(!identity(None, None, Some("alex rivera")).has_contact());
+    }
+
+    #[test]
+    fn test_matches_prefers_email_then_phone() {
+        let resume = identity(Some("a@x.io"), Some("5550102000"), Some("alex rivera"));
+        assert_eq!(
+            resume.matches(&identity(Some("a@x.io"), None, Some("alexander rivera"))),
+            Some(IdentityMatch::Email)
+        );
+        assert_eq!(
+            resume.matches(&identity(Some("alex@school.edu"), Some("5550102000"), None)),
+            Some(IdentityMatch::Phone)
+        );
+    }
+
+    #[test]
+    fn test_name_alone_never_matches() {
+        let resume = identity(Some("a@x.io"), None, Some("alex rivera"));
+        assert_eq!(
+            resume.matches(&identity(None, Some("5550102000"), Some("alex rivera"))),
+            None
+        );
+        assert_eq!(
+            identity(None, None, Some("alex rivera"))
+                .matches(&identity(None, None, Some("alex rivera"))),
+            None
+        );
+        assert_eq!(
+            resume.matches(&identity(Some("other@x.io"), None, Some("alex rivera"))),
+            None
+        );
+        assert_eq!(resume.matches(&Identity::default()), None);
+    }
+}
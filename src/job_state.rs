@@ -0,0 +1,393 @@
+use crate::AppState;
+use crate::cancel::{unless_cancelled, upload_job};
+use crate::events::JobEvent;
+use crate::jobs::{self, JobItems};
+use crate::queue::TaskKind;
+use serde::Deserialize;
+use std::future::Future;
+use uuid::Uuid;
+
+#[derive(Debug, sqlx::Type, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Processing,
+    Ready,
+    Matching,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub const ALL: [JobStatus; 7] = [
+        Self::Pending,
+        Self::Processing,
+        Self::Ready,
+        Self::Matching,
+        Self::Completed,
+        Self::Failed,
+        Self::Cancelled,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Processing => "processing",
+            Self::Ready => "ready",
+            Self::Matching => "matching",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// The statuses a job may move to `self` from. Nothing moves back to `pending`, and
+    /// a cancelled job stays cancelled.
+    pub fn sources(&self) -> &'static [JobStatus] {
+        use JobStatus::*;
+        match self {
+            Pending => &[],
+            // First upload started, or a retry re-opened the job
+            Processing => &[Pending, Ready, Failed, Completed],
+            // Every upload finished, or a retry of failed matching
+            Ready => &[Processing, Failed],
+            Matching => &[Ready],
+            Completed => &[Matching],
+            // Too many uploads failed, or matching did
+            Failed => &[Processing, Matching],
+            Cancelled => &[Pending, Processing, Ready, Failed],
+        }
+    }
+
+    pub fn can_become(&self, to: JobStatus) -> bool {
+        to.sources().contains(self)
+    }
+}
+
+/// When a job whose uploads all finished is failed instead of sent to matching.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FailureThresholds {
+    /// Share of finished uploads that may fail before the job does, in `(0, 1]`. At the
+    /// default of 1 a job only fails when every upload failed.
+    pub max_failed_share: f64,
+    /// Fail jobs in which no resume upload completed.
+    pub require_resumes: bool,
+    /// Fail jobs in which no project spreadsheet completed.
+    pub require_projects: bool,
+}
+
+impl Default for FailureThresholds {
+    fn default() -> Self {
+        Self {
+            max_failed_share: 1.0,
+            require_resumes: false,
+            require_projects: false,
+        }
+    }
+}
+
+/// Where a job's uploads leave it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Settlement {
+    /// Some uploads are still pending or processing.
+    Unfinished,
+    Ready,
+    /// The job fails, for the given reason.
+    Failed(String),
+}
+
+/// Decides whether a job whose uploads are in `items` can be matched. Partial archives
+/// count as successes; cancelled uploads don't count at all.
+pub fn settle_items(items: &JobItems, thresholds: &FailureThresholds) -> Settlement {
+    let kinds = [&items.resumes, &items.zip_archives, &items.projects];
+    let unfinished: i64 = kinds.iter().map(|c| c.pending + c.processing).sum();
+    if unfinished > 0 {
+        return Settlement::Unfinished;
+    }
+
+    let failed: i64 = kinds.iter().map(|c| c.failed).sum();
+    let finished = failed + kinds.iter().map(|c| c.completed + c.partial).sum::<i64>();
+    if finished == 0 {
+        return Settlement::Unfinished;
+    }
+    if failed == finished {
+        return Settlement::Failed("Every upload failed".to_string());
+    }
+    if failed as f64 / finished as f64 >= thresholds.max_failed_share {
+        return Settlement::Failed(format!("{} of {} uploads failed", failed, finished));
+    }
+    if thresholds.require_resumes
+        && items.resumes.completed == 0
+        && items.zip_archives.completed + items.zip_archives.partial == 0
+    {
+        return Settlement::Failed("No resumes were parsed".to_string());
+    }
+    if thresholds.require_projects && items.projects.completed == 0 {
+        return Settlement::Failed("No projects were parsed".to_string());
+    }
+    Settlement::Ready
+}
+
+/// Moves a job to `to` if the transition is allowed from its current status and, when
+/// `from` is given, the job is in `from`. A job without a status counts as `pending`.
+/// Announces the transition and returns whether the job moved.
+pub async fn transition_job(
+    state: &AppState,
+    job_id: Uuid,
+    from: Option<JobStatus>,
+    to: JobStatus,
+) -> Result<bool, sqlx::Error> {
+    let sources: Vec<String> = to
+        .sources()
+        .iter()
+        .filter(|source| from.is_none_or(|from| from == **source))
+        .map(|source| source.name().to_string())
+        .collect();
+    if sources.is_empty() {
+        return Ok(false);
+    }
+
+    let moved = sqlx::query!(
+        "UPDATE jobs SET status = $1, updated_at = now() WHERE id = $2 AND coalesce(status, 'pending')::text = ANY($3)",
+        to as JobStatus,
+        job_id,
+        &sources
+    )
+    .execute(&state.pool)
+    .await?
+    .rows_affected()
+        > 0;
+    if moved {
+        state
+            .events
+            .publish(JobEvent::Job { job_id, status: to })
+            .await;
+    }
+    Ok(moved)
+}
+
+/// Moves a `processing` job whose uploads all finished to `ready`, or to `failed` under
+/// `AppState::job_failure`, recording the reason in `rust_error.failure`.
+pub async fn settle(state: &AppState, job_id: Uuid) {
+    let job = match jobs::get_job(&state.pool, job_id).await {
+        Ok(Some(job)) => job,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Failed to load job {} to settle it: {}", job_id, e);
+            return;
+        }
+    };
+    if job.status != Some(JobStatus::Processing) {
+        return;
+    }
+
+    let result = match settle_items(&job.items, &state.config.jobs) {
+        Settlement::Unfinished => return,
+        Settlement::Ready => {
+            transition_job(state, job_id, Some(JobStatus::Processing), JobStatus::Ready).await
+        }
+        Settlement::Failed(reason) => {
+            tracing::warn!("Job {} failed: {}", job_id, reason);
+            match transition_job(state, job_id, Some(JobStatus::Processing), JobStatus::Failed).await {
+                Ok(true) => sqlx::query!(
+                    "UPDATE jobs SET rust_error = jsonb_set(rust_error, '{failure}', to_jsonb($1::text)) WHERE id = $2",
+                    reason,
+                    job_id
+                )
+                .execute(&state.pool)
+                .await
+                .map(|_| true),
+                other => other,
+            }
+        }
+    };
+    if let Err(e) = result {
+        tracing::error!("Failed to settle job {}: {}", job_id, e);
+    }
+}
+
+/// Runs the processing of one upload on behalf of its job: skipped or stopped if the job
+/// is cancelled, and followed by settling the job however the upload ended. Returns
+/// `None` when a cancellation stopped the work.
+pub(crate) async fn run_upload<F: Future>(
+    state: &AppState,
+    kind: TaskKind,
+    upload_id: Uuid,
+    work: F,
+) -> Option<F::Output> {
+    let job = match upload_job(state, kind, upload_id).await {
+        Ok(job) => job,
+        Err(e) => {
+            tracing::warn!("Failed to look up job of upload {}: {}", upload_id, e);
+            None
+        }
+    };
+    let Some((job_id, status)) = job else {
+        return Some(work.await);
+    };
+    let output = unless_cancelled(state, job_id, status, upload_id, work).await?;
+    settle(state, job_id).await;
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::StatusCounts;
+
+    fn counts(completed: i64, partial: i64, failed: i64, unfinished: i64) -> StatusCounts {
+        StatusCounts {
+            completed,
+            partial,
+            failed,
+            processing: unfinished,
+            total: completed + partial + failed + unfinished,
+            ..Default::default()
+        }
+    }
+
+    fn items(resumes: StatusCounts, zip_archives: StatusCounts, projects: StatusCounts) -> JobItems {
+        JobItems {
+            resumes,
+            zip_archives,
+            projects,
+        }
+    }
+
+    #[test]
+    fn test_transition_table() {
+        use JobStatus::*;
+        let allowed = [
+            (Pending, Processing),
+            (Pending, Cancelled),
+            (Processing, Ready),
+            (Processing, Failed),
+            (Processing, Cancelled),
+            (Ready, Processing),
+            (Ready, Matching),
+            (Ready, Cancelled),
+            (Matching, Completed),
+            (Matching, Failed),
+            (Completed, Processing),
+            (Failed, Processing),
+            (Failed, Ready),
+            (Failed, Cancelled),
+        ];
+        for from in JobStatus::ALL {
+            for to in JobStatus::ALL {
+                assert_eq!(
+                    from.can_become(to),
+                    allowed.contains(&(from, to)),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_names_match_serde() {
+        for status in JobStatus::ALL {
+            assert_eq!(serde_json::to_value(status).unwrap(), status.name());
+        }
+    }
+
+    #[test]
+    fn test_unfinished_uploads_keep_the_job_processing() {
+        let thresholds = FailureThresholds::default();
+        let none = StatusCounts::default();
+        assert_eq!(
+            settle_items(&items(counts(1, 0, 0, 1), none.clone(), none.clone()), &thresholds),
+            Settlement::Unfinished
+        );
+        let pending = StatusCounts {
+            pending: 1,
+            total: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            settle_items(&items(none.clone(), none.clone(), pending), &thresholds),
+            Settlement::Unfinished
+        );
+        assert_eq!(settle_items(&JobItems::default(), &thresholds), Settlement::Unfinished);
+        let cancelled = StatusCounts {
+            cancelled: 2,
+            total: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            settle_items(&items(cancelled, none.clone(), none), &thresholds),
+            Settlement::Unfinished
+        );
+    }
+
+    #[test]
+    fn test_job_fails_only_when_everything_failed_by_default() {
+        let thresholds = FailureThresholds::default();
+        let none = StatusCounts::default();
+        assert_eq!(
+            settle_items(&items(counts(0, 0, 2, 0), counts(0, 0, 1, 0), none.clone()), &thresholds),
+            Settlement::Failed("Every upload failed".to_string())
+        );
+        assert_eq!(
+            settle_items(&items(counts(1, 0, 5, 0), none.clone(), none.clone()), &thresholds),
+            Settlement::Ready
+        );
+        // A partial archive is a success
+        assert_eq!(
+            settle_items(&items(counts(0, 0, 1, 0), counts(0, 1, 0, 0), none), &thresholds),
+            Settlement::Ready
+        );
+    }
+
+    #[test]
+    fn test_failed_share_threshold() {
+        let thresholds = FailureThresholds {
+            max_failed_share: 0.5,
+            ..Default::default()
+        };
+        let none = StatusCounts::default();
+        assert_eq!(
+            settle_items(&items(counts(1, 0, 1, 0), none.clone(), none.clone()), &thresholds),
+            Settlement::Failed("1 of 2 uploads failed".to_string())
+        );
+        assert_eq!(
+            settle_items(&items(counts(2, 0, 1, 0), none.clone(), none), &thresholds),
+            Settlement::Ready
+        );
+    }
+
+    #[test]
+    fn test_required_kinds() {
+        let none = StatusCounts::default();
+        let projects = FailureThresholds {
+            require_projects: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            settle_items(&items(counts(2, 0, 0, 0), none.clone(), none.clone()), &projects),
+            Settlement::Failed("No projects were parsed".to_string())
+        );
+        assert_eq!(
+            settle_items(&items(counts(2, 0, 0, 0), none.clone(), counts(1, 0, 1, 0)), &projects),
+            Settlement::Ready
+        );
+
+        let resumes = FailureThresholds {
+            require_resumes: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            settle_items(&items(counts(0, 0, 1, 0), none.clone(), counts(1, 0, 0, 0)), &resumes),
+            Settlement::Failed("No resumes were parsed".to_string())
+        );
+        assert_eq!(
+            settle_items(&items(none.clone(), counts(0, 1, 0, 0), counts(1, 0, 0, 0)), &resumes),
+            Settlement::Ready
+        );
+    }
+}
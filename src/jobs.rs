@@ -0,0 +1,194 @@
+use crate::service::JobStatus;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// How many jobs `list_jobs` returns when the caller doesn't say.
+pub const DEFAULT_JOB_LIMIT: i64 = 50;
+/// The most jobs `list_jobs` returns in one call.
+pub const MAX_JOB_LIMIT: i64 = 200;
+
+/// How many items of one kind attached to a job are in each state.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct StatusCounts {
+    pub pending: i64,
+    pub processing: i64,
+    pub completed: i64,
+    /// Only zip archives end up partial.
+    pub partial: i64,
+    pub failed: i64,
+    pub cancelled: i64,
+    pub total: i64,
+}
+
+impl StatusCounts {
+    fn add(&mut self, status: &str, count: i64) {
+        match status {
+            "pending" => self.pending += count,
+            "processing" => self.processing += count,
+            "completed" => self.completed += count,
+            "partial" => self.partial += count,
+            "failed" => self.failed += count,
+            "cancelled" => self.cancelled += count,
+            _ => return,
+        }
+        self.total += count;
+    }
+
+    /// Items that will not change state any more.
+    pub fn finished(&self) -> i64 {
+        self.completed + self.partial + self.failed + self.cancelled
+    }
+}
+
+/// A job's uploads, by kind.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct JobItems {
+    pub resumes: StatusCounts,
+    pub zip_archives: StatusCounts,
+    pub projects: StatusCounts,
+}
+
+impl JobItems {
+    /// Share of the job's uploads that finished processing, from 0 to 1. A job without
+    /// uploads has made no progress.
+    pub fn progress(&self) -> f64 {
+        let kinds = [&self.resumes, &self.zip_archives, &self.projects];
+        let total: i64 = kinds.iter().map(|c| c.total).sum();
+        if total == 0 {
+            return 0.0;
+        }
+        kinds.iter().map(|c| c.finished()).sum::<i64>() as f64 / total as f64
+    }
+}
+
+/// Everything a client needs to render a job's progress.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProgress {
+    pub id: Uuid,
+    pub term: Option<String>,
+    pub status: Option<JobStatus>,
+    pub items: JobItems,
+    pub progress: f64,
+    pub rust_error: Option<Value>,
+    pub python_error: Option<String>,
+    /// RFC 3339, UTC.
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+pub async fn get_job(pool: &PgPool, id: Uuid) -> Result<Option<JobProgress>, sqlx::Error> {
+    Ok(fetch_jobs(pool, Some(id), None, 1).await?.pop())
+}
+
+/// Lists jobs newest first, optionally only those in `status`. `limit` is capped at
+/// `MAX_JOB_LIMIT`.
+pub async fn list_jobs(
+    pool: &PgPool,
+    status: Option<JobStatus>,
+    limit: i64,
+) -> Result<Vec<JobProgress>, sqlx::Error> {
+    fetch_jobs(pool, None, status, limit.clamp(1, MAX_JOB_LIMIT)).await
+}
+
+async fn fetch_jobs(
+    pool: &PgPool,
+    id: Option<Uuid>,
+    status: Option<JobStatus>,
+    limit: i64,
+) -> Result<Vec<JobProgress>, sqlx::Error> {
+    let jobs = sqlx::query!(
+        r#"
+        SELECT id, term, status as "status: JobStatus", rust_error, python_error,
+            to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created_at,
+            to_char(updated_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as updated_at
+        FROM jobs
+        WHERE ($1::uuid IS NULL OR id = $1) AND ($2::job_status IS NULL OR status = $2)
+        ORDER BY jobs.created_at DESC
+        LIMIT $3
+        "#,
+        id,
+        status as Option<JobStatus>,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let ids: Vec<Uuid> = jobs.iter().map(|job| job.id).collect();
+    let counts = sqlx::query!(
+        r#"
+        SELECT 'resumes' as "kind!", job_id as "job_id!", coalesce(status::text, 'pending') as "status!", count(*) as "count!"
+        FROM resume_uploads WHERE job_id = ANY($1) GROUP BY job_id, status
+        UNION ALL
+        SELECT 'zip_archives', job_id, coalesce(status::text, 'pending'), count(*)
+        FROM zip_archives WHERE job_id = ANY($1) GROUP BY job_id, status
+        UNION ALL
+        SELECT 'projects', job_id, coalesce(status::text, 'pending'), count(*)
+        FROM project_uploads WHERE job_id = ANY($1) GROUP BY job_id, status
+        "#,
+        &ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut items: HashMap<Uuid, JobItems> = HashMap::new();
+    for row in counts {
+        let job = items.entry(row.job_id).or_default();
+        let kind = match row.kind.as_str() {
+            "resumes" => &mut job.resumes,
+            "zip_archives" => &mut job.zip_archives,
+            _ => &mut job.projects,
+        };
+        kind.add(&row.status, row.count);
+    }
+
+    Ok(jobs
+        .into_iter()
+        .map(|job| {
+            let items = items.remove(&job.id).unwrap_or_default();
+            JobProgress {
+                id: job.id,
+                term: job.term,
+                status: job.status,
+                progress: items.progress(),
+                items,
+                rust_error: job.rust_error,
+                python_error: job.python_error,
+                created_at: job.created_at,
+                updated_at: job.updated_at,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_counts_add() {
+        let mut counts = StatusCounts::default();
+        counts.add("pending", 2);
+        counts.add("partial", 1);
+        counts.add("failed", 1);
+        counts.add("cancelled", 1);
+        counts.add("unknown", 5);
+        assert_eq!(counts.pending, 2);
+        assert_eq!(counts.total, 5);
+        assert_eq!(counts.finished(), 3);
+    }
+
+    #[test]
+    fn test_progress_covers_every_kind() {
+        let mut items = JobItems::default();
+        assert_eq!(items.progress(), 0.0);
+
+        items.resumes.add("completed", 2);
+        items.resumes.add("processing", 1);
+        items.zip_archives.add("partial", 1);
+        items.projects.add("pending", 1);
+        assert_eq!(items.progress(), 0.6);
+    }
+}
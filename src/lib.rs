@@ -1,9 +1,34 @@
+pub mod archive;
 pub mod auth;
+pub mod cancel;
+pub mod dedup;
+pub mod embeddings;
+pub mod events;
+pub mod extraction;
+pub mod identity;
+pub mod job_state;
+pub mod jobs;
+pub mod llm;
+pub mod matching;
+pub mod queue;
+pub mod reaper;
+pub mod reprocess;
+pub mod retry;
 pub mod requests;
 pub mod service;
+pub mod shutdown;
 pub mod storage;
+pub mod validation;
 pub mod config;
 
+use crate::cancel::Cancellations;
+use crate::config::Config;
+use crate::embeddings::EmbeddingClient;
+use crate::events::EventBus;
+use crate::extraction::OcrEngine;
+use crate::llm::LlmProvider;
+use crate::queue::TaskQueue;
+use crate::shutdown::Shutdown;
 use crate::storage::StorageProvider;
 use serde_json::Value;
 use sqlx::PgPool;
@@ -12,12 +37,26 @@ use tokio::sync::Semaphore;
 
 #[derive(Clone)]
 pub struct AppState {
+    /// Settings from the config file and environment, e.g. the storage bucket names, the
+    /// archive limits or the duplicate policy.
+    pub config: Arc<Config>,
     pub pool: PgPool,
     pub storage: Arc<dyn StorageProvider>,
+    /// `None` without an OpenAI API key; resumes and projects are then matched without
+    /// embeddings.
+    pub embeddings: Option<Arc<dyn EmbeddingClient>>,
+    pub queue: TaskQueue,
+    /// Job and upload status transitions, streamed to `/jobs/{id}/events`.
+    pub events: EventBus,
+    /// Stops running work of jobs cancelled through `/jobs/{id}/cancel`.
+    pub cancellations: Cancellations,
+    /// Stops the queue workers and event streams on SIGTERM/SIGINT.
+    pub shutdown: Shutdown,
+    pub llm: Arc<dyn LlmProvider>,
     pub http_client: reqwest::Client,
-    pub openai_api_key: String,
-    pub openai_endpoint: String,
     pub resume_schema: Value,
+    /// Fallback for scanned PDFs without a usable text layer; `None` disables OCR.
+    pub ocr: Option<Arc<dyn OcrEngine>>,
     pub semaphore: Arc<Semaphore>,
     pub jwt_secret: String,
 }
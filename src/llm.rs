@@ -0,0 +1,169 @@
+pub mod azure;
+pub mod ollama;
+pub mod openai;
+
+use crate::retry::{TransientError, parse_retry_after};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+pub use azure::AzureOpenAIProvider;
+pub use ollama::OllamaProvider;
+pub use openai::OpenAIProvider;
+
+pub const RESUME_SYSTEM_PROMPT: &str = "You are a resume conversion assistant. Extract information from the user's resume text and format it into the given structure.";
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+}
+
+impl Message {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: "system".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Short provider name used in logs.
+    fn name(&self) -> &str;
+    /// Sends the conversation and returns the model's raw reply, which should be a
+    /// JSON document conforming to `schema`.
+    async fn complete_json(&self, messages: &[Message], schema: &Value) -> Result<String>;
+}
+
+/// The initial conversation for structuring a resume.
+pub fn resume_messages(resume_text: &str) -> Vec<Message> {
+    vec![
+        Message::system(RESUME_SYSTEM_PROMPT),
+        Message::user(resume_text),
+    ]
+}
+
+/// Follow-up turns asking the model to fix a reply that failed schema validation.
+pub fn repair_messages(invalid_reply: &str, errors: &[String]) -> Vec<Message> {
+    let mut prompt = String::from(
+        "Your previous reply did not match the required JSON schema. Fix these problems and reply with the corrected JSON document only:",
+    );
+    for error in errors {
+        prompt.push_str("\n- ");
+        prompt.push_str(error);
+    }
+    vec![Message::assistant(invalid_reply), Message::user(prompt)]
+}
+
+/// Maps rate limits and server errors to `TransientError` so callers can retry them,
+/// and any other non-success status to a permanent error including the body.
+pub(crate) async fn check_status(
+    response: reqwest::Response,
+    provider: &str,
+) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        let retry_after = parse_retry_after(response.headers().get(reqwest::header::RETRY_AFTER));
+        return Err(TransientError::new(format!("{} returned {}", provider, status))
+            .with_retry_after(retry_after)
+            .into());
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("{} returned {}: {}", provider, status, body);
+    }
+    Ok(response)
+}
+
+pub(crate) fn send_error(provider: &str, e: reqwest::Error) -> anyhow::Error {
+    TransientError::new(format!("Failed to send request to {}: {}", provider, e)).into()
+}
+
+/// In-memory provider for tests. Replies are returned in order; the last one repeats
+/// once the queue is exhausted. Every conversation it receives is recorded.
+pub struct MockLlmProvider {
+    responses: std::sync::Mutex<VecDeque<String>>,
+    pub requests: std::sync::Mutex<Vec<Vec<Message>>>,
+}
+
+impl MockLlmProvider {
+    pub fn new(responses: Vec<String>) -> Self {
+        Self {
+            responses: std::sync::Mutex::new(responses.into()),
+            requests: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_json(response: Value) -> Self {
+        Self::new(vec![response.to_string()])
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockLlmProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn complete_json(&self, messages: &[Message], _schema: &Value) -> Result<String> {
+        self.requests.lock().unwrap().push(messages.to_vec());
+        let mut responses = self.responses.lock().unwrap();
+        let reply = if responses.len() > 1 {
+            responses.pop_front()
+        } else {
+            responses.front().cloned()
+        };
+        reply.ok_or_else(|| anyhow::anyhow!("MockLlmProvider has no responses configured"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_mock_provider_replays_responses_in_order() {
+        let provider = MockLlmProvider::new(vec!["first".to_string(), "second".to_string()]);
+        let messages = resume_messages("resume");
+        let schema = json!({});
+
+        assert_eq!(provider.complete_json(&messages, &schema).await.unwrap(), "first");
+        assert_eq!(provider.complete_json(&messages, &schema).await.unwrap(), "second");
+        assert_eq!(provider.complete_json(&messages, &schema).await.unwrap(), "second");
+        assert_eq!(provider.requests.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_repair_messages_lists_errors() {
+        let messages = repair_messages("{}", &["/: missing skills".to_string()]);
+        assert_eq!(messages[0], Message::assistant("{}"));
+        assert_eq!(messages[1].role, "user");
+        assert!(messages[1].content.ends_with("\n- /: missing skills"));
+    }
+
+    #[test]
+    fn test_resume_messages() {
+        let messages = resume_messages("My resume");
+        assert_eq!(messages[0], Message::system(RESUME_SYSTEM_PROMPT));
+        assert_eq!(messages[1], Message::user("My resume"));
+    }
+}
@@ -0,0 +1,100 @@
+use super::openai::{ChatCompletionRequest, send_chat_completion};
+use super::{LlmProvider, Message};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+
+pub const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
+
+/// Azure OpenAI deployment. Uses the chat-completions wire format, but the model is
+/// selected by the deployment in the URL and auth goes through the `api-key` header.
+pub struct AzureOpenAIProvider {
+    client: reqwest::Client,
+    api_key: String,
+    url: String,
+}
+
+impl AzureOpenAIProvider {
+    pub fn new(
+        client: reqwest::Client,
+        api_key: String,
+        endpoint: &str,
+        deployment: &str,
+        api_version: &str,
+    ) -> Self {
+        Self {
+            client,
+            api_key,
+            url: Self::deployment_url(endpoint, deployment, api_version),
+        }
+    }
+
+    pub fn deployment_url(endpoint: &str, deployment: &str, api_version: &str) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            endpoint.trim_end_matches('/'),
+            deployment,
+            api_version
+        )
+    }
+}
+
+#[async_trait]
+impl LlmProvider for AzureOpenAIProvider {
+    fn name(&self) -> &str {
+        "Azure OpenAI"
+    }
+
+    async fn complete_json(&self, messages: &[Message], schema: &Value) -> Result<String> {
+        let body = ChatCompletionRequest::new(None, messages, schema);
+        let request = self
+            .client
+            .post(&self.url)
+            .header("api-key", &self.api_key);
+        send_chat_completion(request, &body, self.name()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::resume_messages;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_deployment_url() {
+        assert_eq!(
+            AzureOpenAIProvider::deployment_url("https://acme.openai.azure.com/", "resumes", "2024-10-21"),
+            "https://acme.openai.azure.com/openai/deployments/resumes/chat/completions?api-version=2024-10-21"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_azure_provider_uses_api_key_header() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/resumes/chat/completions"))
+            .and(query_param("api-version", "2024-10-21"))
+            .and(header("api-key", "azure-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "role": "assistant", "content": "{}" } }]
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = AzureOpenAIProvider::new(
+            reqwest::Client::new(),
+            "azure-key".to_string(),
+            &server.uri(),
+            "resumes",
+            "2024-10-21",
+        );
+        let reply = provider
+            .complete_json(&resume_messages("resume"), &json!({}))
+            .await
+            .unwrap();
+        assert_eq!(reply, "{}");
+    }
+}
@@ -0,0 +1,110 @@
+use super::{LlmProvider, Message, check_status, send_error};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const DEFAULT_OLLAMA_ENDPOINT: &str = "http://localhost:11434";
+pub const DEFAULT_OLLAMA_MODEL: &str = "llama3.1";
+
+#[derive(Serialize)]
+struct OllamaChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [Message],
+    stream: bool,
+    /// Ollama accepts a JSON schema here to constrain the output.
+    format: &'a Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct OllamaChatResponse {
+    message: Message,
+}
+
+/// Ollama's native `/api/chat` endpoint, for local development without API costs.
+pub struct OllamaProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    model: String,
+}
+
+impl OllamaProvider {
+    pub fn new(client: reqwest::Client, endpoint: &str, model: String) -> Self {
+        Self {
+            client,
+            endpoint: format!("{}/api/chat", endpoint.trim_end_matches('/')),
+            model,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        "Ollama"
+    }
+
+    async fn complete_json(&self, messages: &[Message], schema: &Value) -> Result<String> {
+        let body = OllamaChatRequest {
+            model: &self.model,
+            messages,
+            stream: false,
+            format: schema,
+        };
+
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| send_error(self.name(), e))?;
+
+        let parsed = check_status(response, self.name())
+            .await?
+            .json::<OllamaChatResponse>()
+            .await
+            .context("Failed to parse Ollama response")?;
+
+        Ok(parsed.message.content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::resume_messages;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_ollama_provider_uses_native_chat_api() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({
+                "model": "llama-test",
+                "stream": false,
+                "format": { "type": "object" }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "llama-test",
+                "message": { "role": "assistant", "content": "{\"skills\": [\"Rust\"]}" },
+                "done": true
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OllamaProvider::new(
+            reqwest::Client::new(),
+            &format!("{}/", server.uri()),
+            "llama-test".to_string(),
+        );
+        let reply = provider
+            .complete_json(&resume_messages("resume"), &json!({ "type": "object" }))
+            .await
+            .unwrap();
+        assert_eq!(reply, "{\"skills\": [\"Rust\"]}");
+    }
+}
@@ -0,0 +1,195 @@
+use super::{LlmProvider, Message, check_status, send_error};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const DEFAULT_OPENAI_ENDPOINT: &str = "https://api.openai.com/v1/chat/completions";
+pub const DEFAULT_OPENAI_MODEL: &str = "gpt-5-nano";
+
+#[derive(Serialize)]
+pub struct ChatCompletionRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<&'a str>,
+    pub messages: &'a [Message],
+    pub response_format: ResponseFormat,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type")] // This tells serde to use "type": "json_schema"
+pub enum ResponseFormat {
+    #[serde(rename = "json_schema")]
+    JsonSchema { json_schema: JsonSchemaDefinition },
+}
+
+#[derive(Serialize, Debug)]
+pub struct JsonSchemaDefinition {
+    pub name: String,
+    pub strict: bool,
+    pub schema: Value,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChatCompletionResponse {
+    pub choices: Vec<Choice>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Choice {
+    pub message: Message,
+}
+
+impl<'a> ChatCompletionRequest<'a> {
+    pub fn new(model: Option<&'a str>, messages: &'a [Message], schema: &Value) -> Self {
+        Self {
+            model,
+            messages,
+            response_format: ResponseFormat::JsonSchema {
+                json_schema: JsonSchemaDefinition {
+                    name: "resume_data_structuring".to_string(),
+                    strict: true,
+                    schema: schema.clone(),
+                },
+            },
+        }
+    }
+}
+
+/// Sends a chat-completions request and returns the first choice's content.
+/// Shared by the OpenAI and Azure OpenAI providers, which only differ in URL and auth.
+pub(crate) async fn send_chat_completion(
+    request: reqwest::RequestBuilder,
+    body: &ChatCompletionRequest<'_>,
+    provider: &str,
+) -> Result<String> {
+    let response = request
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| send_error(provider, e))?;
+
+    let mut parsed = check_status(response, provider)
+        .await?
+        .json::<ChatCompletionResponse>()
+        .await
+        .with_context(|| format!("Failed to parse {} response", provider))?;
+
+    if parsed.choices.is_empty() {
+        anyhow::bail!("No choices returned from {}", provider);
+    }
+    Ok(parsed.choices.swap_remove(0).message.content)
+}
+
+pub struct OpenAIProvider {
+    client: reqwest::Client,
+    api_key: String,
+    endpoint: String,
+    model: String,
+}
+
+impl OpenAIProvider {
+    pub fn new(client: reqwest::Client, api_key: String, endpoint: String, model: String) -> Self {
+        Self {
+            client,
+            api_key,
+            endpoint,
+            model,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAIProvider {
+    fn name(&self) -> &str {
+        "OpenAI"
+    }
+
+    async fn complete_json(&self, messages: &[Message], schema: &Value) -> Result<String> {
+        let body = ChatCompletionRequest::new(Some(&self.model), messages, schema);
+        let request = self.client.post(&self.endpoint).bearer_auth(&self.api_key);
+        send_chat_completion(request, &body, self.name()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::resume_messages;
+    use crate::retry::as_transient;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_openai_provider_sends_json_schema_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("Authorization", "Bearer key"))
+            .and(body_partial_json(json!({
+                "model": "gpt-test",
+                "response_format": { "type": "json_schema", "json_schema": { "strict": true } }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "role": "assistant", "content": "{\"skills\": []}" } }]
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            reqwest::Client::new(),
+            "key".to_string(),
+            format!("{}/v1/chat/completions", server.uri()),
+            "gpt-test".to_string(),
+        );
+        let reply = provider
+            .complete_json(&resume_messages("resume"), &json!({}))
+            .await
+            .unwrap();
+        assert_eq!(reply, "{\"skills\": []}");
+    }
+
+    #[tokio::test]
+    async fn test_openai_provider_classifies_rate_limits_as_transient() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "3"))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            reqwest::Client::new(),
+            "key".to_string(),
+            server.uri(),
+            "gpt-test".to_string(),
+        );
+        let err = provider
+            .complete_json(&resume_messages("resume"), &json!({}))
+            .await
+            .unwrap_err();
+        let transient = as_transient(&err).expect("429 should be transient");
+        assert_eq!(transient.retry_after, Some(std::time::Duration::from_secs(3)));
+    }
+
+    #[tokio::test]
+    async fn test_openai_provider_bad_request_is_permanent() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("bad schema"))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            reqwest::Client::new(),
+            "key".to_string(),
+            server.uri(),
+            "gpt-test".to_string(),
+        );
+        let err = provider
+            .complete_json(&resume_messages("resume"), &json!({}))
+            .await
+            .unwrap_err();
+        assert!(as_transient(&err).is_none());
+        assert!(err.to_string().contains("bad schema"));
+    }
+}
@@ -1,40 +1,46 @@
-use aws_config::Region;
-use aws_sdk_s3::Client as S3Client;
 use axum::{Router, routing::get, routing::post};
 use dotenvy::dotenv;
 use serde_json::Value;
 use sqlx::postgres::PgPoolOptions;
-use std::env;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Semaphore;
 use tower_http::trace::{self, TraceLayer};
 use tracing::Level;
 
 use matchmaker_orchestrator::AppState;
 use matchmaker_orchestrator::auth;
+use matchmaker_orchestrator::config::{Config, StorageBackend};
+use matchmaker_orchestrator::embeddings::{EmbeddingClient, OpenAIEmbeddingClient};
+use matchmaker_orchestrator::cancel::Cancellations;
+use matchmaker_orchestrator::shutdown::{self, Shutdown};
+use matchmaker_orchestrator::events::EventBus;
+use matchmaker_orchestrator::extraction::{OcrEngine, TesseractOcr};
+use matchmaker_orchestrator::matching::MatchingService;
+use matchmaker_orchestrator::queue::{self, TaskQueue};
+use matchmaker_orchestrator::reaper;
+use matchmaker_orchestrator::service::ResumeService;
 use matchmaker_orchestrator::requests::{
-    handle_batch_upload, handle_project_upload, handle_single_upload,
+    get_intern, get_job, handle_batch_upload, handle_project_upload, handle_single_upload,
+    cancel_job, job_events, list_jobs, retry_failed, retry_upload,
 };
-use matchmaker_orchestrator::storage::S3StorageProvider;
+use matchmaker_orchestrator::storage::{LocalStorageProvider, S3StorageProvider, StorageProvider};
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
-    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let endpoint = env::var("SUPABASE_ENDPOINT").expect("SUPABASE_ENDPOINT must be set");
-    let service_key = env::var("SERVICE_KEY").expect("SERVICE_KEY must be set");
-    let openai_api_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set");
-    let max_concurrent_tasks = env::var("MAX_CONCURRENT_TASKS")
-        .unwrap_or_else(|_| "10".to_string())
-        .parse::<usize>()
-        .expect("MAX_CONCURRENT_TASKS must be a number");
-
-    let s3_config_parsed = matchmaker_orchestrator::config::parse_s3_config(&endpoint).expect("Failed to parse S3 config from endpoint");
-    let s3_endpoint = s3_config_parsed.endpoint;
-    let project_ref = s3_config_parsed.project_ref;
-
-    tracing::info!("Configured S3 Endpoint: {}", s3_endpoint);
-    tracing::info!("Project Ref: {}", project_ref);
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    // `Config::load` already validated these
+    let llm_connection = config.llm_connection().expect("Invalid LLM configuration");
+    let reaper_config = config.reaper();
+    let max_concurrent_tasks = config.max_concurrent_tasks;
+    let shutdown_deadline = Duration::from_secs(config.shutdown.deadline_secs);
 
     // Load and parse schema once
     let raw_schema_string = include_str!("resume_schema.json");
@@ -46,39 +52,59 @@ async fn main() {
         .compact() // Use .json() here for production!
         .init();
 
-    let s3_access_key = env::var("S3_ACCESS_KEY").unwrap_or_else(|_| project_ref.clone());
-    let s3_secret_key = env::var("S3_SECRET_KEY").unwrap_or_else(|_| service_key.clone());
-
-    // Configure AWS SDK for Supabase S3
-    let credentials = aws_sdk_s3::config::Credentials::new(
-        s3_access_key,
-        s3_secret_key,
-        None,
-        None,
-        "supabase-storage",
-    );
-
-    let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
-        .region(Region::new("us-east-1")) // Region is required but ignored by Supabase
-        .endpoint_url(&s3_endpoint)
-        .credentials_provider(credentials)
-        .load()
-        .await;
-
-    let s3_config = aws_sdk_s3::config::Builder::from(&config)
-        .force_path_style(true)
-        .build();
-
-    let s3_client = S3Client::from_conf(s3_config);
-    let storage = Arc::new(S3StorageProvider::new(s3_client));
+    // `Config::load` already validated the storage settings
+    let storage: Arc<dyn StorageProvider> = match config.storage.backend {
+        StorageBackend::S3 => {
+            let s3_connection = config
+                .s3_connection()
+                .expect("Invalid storage configuration");
+            tracing::info!(
+                "Configured S3 endpoint: {}",
+                s3_connection.endpoint.as_deref().unwrap_or("AWS")
+            );
+            Arc::new(S3StorageProvider::connect(&s3_connection).await)
+        }
+        StorageBackend::Local => {
+            tracing::info!("Storing files under {}", config.storage.local_root);
+            Arc::new(LocalStorageProvider::new(&config.storage.local_root))
+        }
+    };
 
     let pool = PgPoolOptions::new()
         .max_connections((max_concurrent_tasks + 5) as u32)
-        .connect(&db_url)
+        .connect(&config.database_url)
         .await
         .unwrap();
     let http_client = reqwest::Client::new();
+    // Embeddings always go through an OpenAI-compatible endpoint, and need its key
+    let embeddings: Option<Arc<dyn EmbeddingClient>> = if config.openai.api_key.is_empty() {
+        tracing::warn!("No OpenAI API key set, matching without embeddings");
+        None
+    } else {
+        Some(Arc::new(OpenAIEmbeddingClient::new(
+            http_client.clone(),
+            config.openai.api_key.clone(),
+            config.openai.embeddings_endpoint.clone(),
+            config.openai.embedding_model.clone(),
+        )))
+    };
+    let ocr: Option<Arc<dyn OcrEngine>> = if config.ocr.enabled {
+        Some(Arc::new(TesseractOcr::new(
+            config.ocr.tesseract_cmd.clone(),
+            config.ocr.pdftoppm_cmd.clone(),
+            config.ocr.language.clone(),
+        )))
+    } else {
+        None
+    };
     let semaphore = Arc::new(Semaphore::new(max_concurrent_tasks));
+    let task_queue = TaskQueue::new(pool.clone());
+    let job_events_notify = config.events.notify;
+    let events = if job_events_notify {
+        EventBus::with_notify(pool.clone())
+    } else {
+        EventBus::new()
+    };
 
     tracing::info!("Database connection established");
 
@@ -86,26 +112,97 @@ async fn main() {
         .await
         .expect("Failed to get JWT Secret");
 
+    let bind_address = config.bind_address.clone();
+    let reparse_outdated = config.reparse_outdated_resumes;
+    let matching_poll_interval = Duration::from_secs(config.matching.poll_interval_secs);
     let app_state = AppState {
+        config: Arc::new(config),
         pool,
         storage,
+        embeddings,
+        queue: task_queue,
+        events,
+        cancellations: Cancellations::new(),
+        shutdown: Shutdown::new(),
+        llm: llm_connection.build(http_client.clone()),
         http_client,
-        openai_api_key,
-        openai_endpoint: "https://api.openai.com/v1/chat/completions".to_string(),
         resume_schema,
+        ocr,
         semaphore,
         jwt_secret,
     };
 
+    // Drain the durable task queue; tasks left over from a previous run are picked up here too
+    queue::spawn_workers(app_state.clone(), max_concurrent_tasks);
+
+    // Share job events with the other replicas through Postgres, and stop local work of
+    // jobs cancelled elsewhere
+    if job_events_notify {
+        tokio::spawn(
+            app_state
+                .cancellations
+                .clone()
+                .watch(app_state.events.subscribe()),
+        );
+        let events = app_state.events.clone();
+        let pool = app_state.pool.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = events.clone().listen(pool.clone()).await {
+                    tracing::error!("Job event listener stopped, restarting: {:#}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        });
+    }
+
+    // Bring resumes parsed with an older resume schema up to date
+    if reparse_outdated {
+        let state = app_state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = ResumeService::new(state).reparse_outdated().await {
+                tracing::error!("Failed to re-parse outdated resumes: {:#}", e);
+            }
+        });
+    }
+
+    // Group resumes stored before interns were tracked
+    let state = app_state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = ResumeService::new(state).resolve_unassigned().await {
+            tracing::error!("Failed to resolve interns of existing resumes: {:#}", e);
+        }
+    });
+
+    // Take uploads left in `processing` by a worker that died off the queue again
+    tokio::spawn(reaper::run_reaper(app_state.clone(), reaper_config));
+
+    // Pick up jobs that reach `ready` and drive them through matching
+    tokio::spawn(
+        MatchingService::new(app_state.clone())
+            .run_poller(matching_poll_interval),
+    );
+
     let protected_routes = Router::new()
         .route("/ingest/interns/individual", post(handle_single_upload))
         .route("/ingest/interns/batch", post(handle_batch_upload))
         .route("/ingest/projects", post(handle_project_upload))
+        .route("/interns/{id}", get(get_intern))
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/{id}", get(get_job))
+        .route("/jobs/{id}/events", get(job_events))
+        .route("/jobs/{id}/cancel", post(cancel_job))
+        .route("/jobs/{id}/retry-failed", post(retry_failed))
+        .route("/uploads/{kind}/{id}/retry", post(retry_upload))
         .route_layer(axum::middleware::from_fn_with_state(
             app_state.clone(),
             auth::auth,
         ));
 
+    let shutdown = app_state.shutdown.clone();
+    let semaphore = app_state.semaphore.clone();
+    let pool = app_state.pool.clone();
+
     // Create the axum router
     let app = Router::new()
         .merge(protected_routes)
@@ -122,12 +219,37 @@ async fn main() {
         .with_state(app_state);
 
     // Define the IP and port listener (TCP)
-    let address = "0.0.0.0:3000";
-    let listener = tokio::net::TcpListener::bind(address).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(&bind_address).await.unwrap();
     tracing::info!("listening on {}", listener.local_addr().unwrap());
 
-    // Call axum serve to launch the web server
-    axum::serve(listener, app).await.unwrap();
+    // Call axum serve to launch the web server; it stops accepting connections on
+    // SIGTERM/SIGINT and returns once open requests and event streams are done
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown::signal(shutdown.clone()))
+        .await
+        .unwrap();
+
+    // Let running uploads finish, and hand whatever outlives the deadline back to the queue
+    if shutdown
+        .drain(&semaphore, max_concurrent_tasks, shutdown_deadline)
+        .await
+    {
+        tracing::info!("All running work finished");
+    } else {
+        tracing::warn!(
+            "Interrupted work still running after {}s; it resumes on the next worker",
+            shutdown_deadline.as_secs()
+        );
+    }
+
+    // Background loops like the event listener may still hold connections
+    if tokio::time::timeout(Duration::from_secs(5), pool.close())
+        .await
+        .is_err()
+    {
+        tracing::warn!("Closed the database pool with connections still in use");
+    }
+    tracing::info!("Shut down");
 }
 
 async fn hello_world() -> &'static str {
@@ -0,0 +1,591 @@
+use crate::AppState;
+use crate::job_state::{JobStatus, transition_job};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[derive(Debug, sqlx::Type, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "match_decision", rename_all = "lowercase")]
+pub enum MatchDecision {
+    Rejected,
+    Accepted,
+    Pending,
+}
+
+const SKILLS_WEIGHT: f32 = 0.6;
+const EXPERIENCE_WEIGHT: f32 = 0.2;
+const EMBEDDING_WEIGHT: f32 = 0.4;
+/// Years of experience at which the experience component saturates.
+const EXPERIENCE_CAP_YEARS: f32 = 5.0;
+/// Candidates scoring below this are never proposed, even to a project with room left.
+const MIN_ASSIGNMENT_SCORE: f32 = 0.1;
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub id: Uuid,
+    pub skills: Vec<String>,
+    pub years_of_experience: f32,
+    /// Normalized `YYYY-MM`, so candidates can be compared by graduation date.
+    pub graduation_date: Option<String>,
+    pub work_authorization: Option<String>,
+    pub embedding: Option<Vec<f32>>,
+}
+
+impl Candidate {
+    /// Builds a candidate from `resumes.structured`. Technologies from personal projects
+    /// and certification names count as skills. Fields missing from resumes parsed with
+    /// an older schema version are simply left empty.
+    pub fn from_structured(id: Uuid, structured: &Value, embedding: Option<Vec<f32>>) -> Self {
+        let listed = structured
+            .get("skills")
+            .and_then(|s| s.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|s| s.as_str());
+        let from_projects = structured
+            .get("projects")
+            .and_then(|p| p.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.get("technologies").and_then(|t| t.as_array()))
+            .flatten()
+            .filter_map(|t| t.as_str());
+        let from_certifications = structured
+            .get("certifications")
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|c| c.get("name").and_then(|n| n.as_str()));
+
+        let mut seen = HashSet::new();
+        let skills = listed
+            .chain(from_projects)
+            .chain(from_certifications)
+            .map(normalize)
+            .filter(|s| !s.is_empty() && seen.insert(s.clone()))
+            .collect();
+
+        let years_of_experience = structured
+            .get("experience")
+            .and_then(|e| e.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|e| e.get("years_of_experience"))
+                    .map(parse_years)
+                    .sum()
+            })
+            .unwrap_or(0.0);
+
+        let string_field = |key: &str| {
+            structured
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        Self {
+            id,
+            skills,
+            years_of_experience,
+            graduation_date: string_field("graduation_date"),
+            work_authorization: string_field("work_authorization"),
+            embedding,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProjectSlot {
+    pub id: Uuid,
+    pub requirements: Vec<String>,
+    /// 1 is the most important; 0 means no priority was given and sorts last.
+    pub priority: i16,
+    pub intern_cap: i16,
+    pub embedding: Option<Vec<f32>>,
+}
+
+impl ProjectSlot {
+    pub fn new(
+        id: Uuid,
+        requirements: &str,
+        priority: i16,
+        intern_cap: i16,
+        embedding: Option<Vec<f32>>,
+    ) -> Self {
+        Self {
+            id,
+            requirements: split_requirements(requirements),
+            priority,
+            intern_cap,
+            embedding,
+        }
+    }
+
+    fn priority_rank(&self) -> i16 {
+        if self.priority > 0 {
+            self.priority
+        } else {
+            i16::MAX
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub resume_id: Uuid,
+    pub project_id: Uuid,
+    pub score: f32,
+}
+
+fn normalize(s: &str) -> String {
+    s.trim().to_lowercase()
+}
+
+fn parse_years(value: &Value) -> f32 {
+    match value {
+        Value::Number(n) => n.as_f64().unwrap_or(0.0) as f32,
+        Value::String(s) => s
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .find(|t| !t.is_empty())
+            .and_then(|t| t.parse::<f32>().ok())
+            .unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
+
+pub fn split_requirements(requirements: &str) -> Vec<String> {
+    requirements
+        .split([',', ';', '/', '\n'])
+        .map(normalize)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Splits a normalized term into words. `+` and `#` are part of a word, so `c`, `c++` and
+/// `c#` stay different languages.
+fn terms(s: &str) -> Vec<&str> {
+    s.split(|c: char| !(c.is_alphanumeric() || c == '+' || c == '#'))
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Whether a skill covers a requirement: every word of the requirement is a word of the
+/// skill, so `aws` is covered by `aws lambda` and `node` by `node.js`, but `go` is not
+/// covered by `golang` nor `react` by `r`.
+fn covers(skill: &str, requirement: &str) -> bool {
+    let words = terms(skill);
+    let required = terms(requirement);
+    !required.is_empty() && required.iter().all(|word| words.contains(word))
+}
+
+/// Fraction of the project's requirements covered by at least one of the candidate's skills.
+fn skills_overlap(candidate: &Candidate, project: &ProjectSlot) -> f32 {
+    if project.requirements.is_empty() {
+        return 0.0;
+    }
+    let matched = project
+        .requirements
+        .iter()
+        .filter(|req| candidate.skills.iter().any(|skill| covers(skill, req)))
+        .count();
+    matched as f32 / project.requirements.len() as f32
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return None;
+    }
+    Some(dot / (norm_a * norm_b))
+}
+
+/// Scores a candidate against a project in `[0, 1]`. Embedding similarity is
+/// only blended in when both sides have a vector of the same dimension.
+pub fn score(candidate: &Candidate, project: &ProjectSlot) -> f32 {
+    let skills = skills_overlap(candidate, project);
+    let experience = (candidate.years_of_experience / EXPERIENCE_CAP_YEARS).min(1.0);
+    let base = SKILLS_WEIGHT * skills + EXPERIENCE_WEIGHT * experience;
+
+    let similarity = match (&candidate.embedding, &project.embedding) {
+        (Some(a), Some(b)) => cosine_similarity(a, b),
+        _ => None,
+    };
+
+    match similarity {
+        Some(sim) => {
+            let total = SKILLS_WEIGHT + EXPERIENCE_WEIGHT + EMBEDDING_WEIGHT;
+            (base + EMBEDDING_WEIGHT * sim.max(0.0)) / total
+        }
+        None => base / (SKILLS_WEIGHT + EXPERIENCE_WEIGHT),
+    }
+}
+
+/// Proposes assignments by letting projects pick in rounds, highest priority first.
+/// Each round every project with remaining capacity takes its best unassigned candidate,
+/// so a high-priority project gets first choice without taking everyone before others pick.
+/// Candidates below `MIN_ASSIGNMENT_SCORE` are left unassigned rather than filling a slot.
+pub fn assign(candidates: &[Candidate], projects: &[ProjectSlot]) -> Vec<Assignment> {
+    let mut ordered: Vec<&ProjectSlot> = projects.iter().filter(|p| p.intern_cap > 0).collect();
+    ordered.sort_by_key(|p| p.priority_rank());
+
+    let mut remaining: Vec<i16> = ordered.iter().map(|p| p.intern_cap).collect();
+    let mut taken: HashSet<Uuid> = HashSet::new();
+    let mut assignments = Vec::new();
+
+    loop {
+        let mut picked_any = false;
+        for (slot, project) in ordered.iter().enumerate() {
+            if remaining[slot] == 0 {
+                continue;
+            }
+            let best = candidates
+                .iter()
+                .filter(|c| !taken.contains(&c.id))
+                .map(|c| (c, score(c, project)))
+                .filter(|(_, s)| *s >= MIN_ASSIGNMENT_SCORE)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+
+            if let Some((candidate, s)) = best {
+                taken.insert(candidate.id);
+                remaining[slot] -= 1;
+                picked_any = true;
+                assignments.push(Assignment {
+                    resume_id: candidate.id,
+                    project_id: project.id,
+                    score: s,
+                });
+            }
+        }
+        if !picked_any {
+            break;
+        }
+    }
+
+    assignments
+}
+
+pub struct MatchingService {
+    state: AppState,
+}
+
+impl MatchingService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Periodically picks up jobs that reached `ready` and matches them.
+    pub async fn run_poller(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            self.run_ready_jobs().await;
+        }
+    }
+
+    pub async fn run_ready_jobs(&self) {
+        let jobs = match sqlx::query!(
+            "SELECT id FROM jobs WHERE status = $1 ORDER BY created_at",
+            JobStatus::Ready as JobStatus
+        )
+        .fetch_all(&self.state.pool)
+        .await
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::error!("Failed to fetch ready jobs: {}", e);
+                return;
+            }
+        };
+
+        for job in jobs {
+            self.run_job(job.id).await;
+        }
+    }
+
+    /// Moves a `ready` job to `matching`, writes proposed matches and finishes
+    /// as `completed` or `failed`. Does nothing if the job is not `ready`.
+    pub async fn run_job(&self, job_id: Uuid) {
+        // Claim the job so concurrent pollers don't match it twice
+        let claimed = match transition_job(
+            &self.state,
+            job_id,
+            Some(JobStatus::Ready),
+            JobStatus::Matching,
+        )
+        .await
+        {
+            Ok(moved) => moved,
+            Err(e) => {
+                tracing::error!("Failed to claim job {} for matching: {}", job_id, e);
+                return;
+            }
+        };
+
+        if !claimed {
+            return;
+        }
+
+        tracing::info!("Matching job {}", job_id);
+
+        match self.match_job(job_id).await {
+            Ok(count) => {
+                tracing::info!("Job {} matched with {} proposed assignments", job_id, count);
+                let _ = transition_job(&self.state, job_id, Some(JobStatus::Matching), JobStatus::Completed).await;
+            }
+            Err(e) => {
+                let err_msg = format!("Matching failed: {:#}", e);
+                tracing::error!("{}, job_id {}", err_msg, job_id);
+                let _ = sqlx::query!(
+                    "UPDATE jobs SET rust_error = jsonb_set(rust_error, '{matching}', to_jsonb($1::text)) WHERE id = $2",
+                    err_msg,
+                    job_id
+                )
+                .execute(&self.state.pool)
+                .await;
+                let _ = transition_job(&self.state, job_id, Some(JobStatus::Matching), JobStatus::Failed).await;
+            }
+        }
+    }
+
+    async fn match_job(&self, job_id: Uuid) -> anyhow::Result<usize> {
+        let job = sqlx::query!("SELECT term FROM jobs WHERE id = $1", job_id)
+            .fetch_one(&self.state.pool)
+            .await?;
+
+        // A job covers everything uploaded for its term plus anything explicitly linked to it.
+        // Interns with several resumes are only ranked on their newest one.
+        let resumes = sqlx::query!(
+            r#"
+            SELECT DISTINCT ON (coalesce(intern_id, id)) id, structured, embedding FROM resumes
+            WHERE structured IS NOT NULL
+              AND (term = $1 OR upload_id IN (SELECT id FROM resume_uploads WHERE job_id = $2))
+            ORDER BY coalesce(intern_id, id), created_at DESC
+            "#,
+            job.term,
+            job_id
+        )
+        .fetch_all(&self.state.pool)
+        .await?;
+
+        let projects = sqlx::query!(
+            r#"
+            SELECT id, requirements, priority, intern_cap, embedding FROM projects
+            WHERE term = $1 OR upload_id IN (SELECT id FROM project_uploads WHERE job_id = $2)
+            "#,
+            job.term,
+            job_id
+        )
+        .fetch_all(&self.state.pool)
+        .await?;
+
+        if resumes.is_empty() {
+            anyhow::bail!("No parsed resumes found for job");
+        }
+        if projects.is_empty() {
+            anyhow::bail!("No projects found for job");
+        }
+
+        let candidates: Vec<Candidate> = resumes
+            .into_iter()
+            .filter_map(|r| {
+                r.structured
+                    .map(|s| Candidate::from_structured(r.id, &s, r.embedding))
+            })
+            .collect();
+
+        let slots: Vec<ProjectSlot> = projects
+            .into_iter()
+            .map(|p| {
+                ProjectSlot::new(
+                    p.id,
+                    p.requirements.as_deref().unwrap_or(""),
+                    p.priority.unwrap_or(0),
+                    p.intern_cap.unwrap_or(1),
+                    p.embedding,
+                )
+            })
+            .collect();
+
+        let assignments = assign(&candidates, &slots);
+
+        // Replace any earlier proposals so re-running a job is idempotent
+        let mut tx = self.state.pool.begin().await?;
+        sqlx::query!(
+            "DELETE FROM matches WHERE job_id = $1 AND decision = $2",
+            job_id,
+            MatchDecision::Pending as MatchDecision
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for a in &assignments {
+            sqlx::query!(
+                "INSERT INTO matches (job_id, resume_id, project_id, score, decision) VALUES ($1, $2, $3, $4, $5)",
+                job_id,
+                a.resume_id,
+                a.project_id,
+                a.score,
+                MatchDecision::Pending as MatchDecision
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(assignments.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn candidate(skills: &[&str], years: &str) -> Candidate {
+        Candidate::from_structured(
+            Uuid::new_v4(),
+            &json!({
+                "skills": skills,
+                "experience": [{ "role": "Dev", "years_of_experience": years }]
+            }),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_candidate_from_structured() {
+        let c = Candidate::from_structured(
+            Uuid::new_v4(),
+            &json!({
+                "skills": [" Rust ", "SQL"],
+                "experience": [
+                    { "role": "A", "years_of_experience": "2 years" },
+                    { "role": "B", "years_of_experience": 1 }
+                ]
+            }),
+            None,
+        );
+        assert_eq!(c.skills, vec!["rust", "sql"]);
+        assert_eq!(c.years_of_experience, 3.0);
+        assert_eq!(c.graduation_date, None);
+    }
+
+    #[test]
+    fn test_candidate_includes_projects_and_certifications() {
+        let c = Candidate::from_structured(
+            Uuid::new_v4(),
+            &json!({
+                "graduation_date": "2026-05",
+                "work_authorization": "US citizen",
+                "skills": ["Rust"],
+                "experience": [],
+                "projects": [{ "name": "Site", "technologies": ["React", "rust"] }],
+                "certifications": [{ "name": "AWS Solutions Architect" }]
+            }),
+            None,
+        );
+        assert_eq!(c.skills, vec!["rust", "react", "aws solutions architect"]);
+        assert_eq!(c.graduation_date.as_deref(), Some("2026-05"));
+        assert_eq!(c.work_authorization.as_deref(), Some("US citizen"));
+    }
+
+    #[test]
+    fn test_split_requirements() {
+        assert_eq!(
+            split_requirements("Rust, SQL; AWS/Terraform\nDocker,"),
+            vec!["rust", "sql", "aws", "terraform", "docker"]
+        );
+    }
+
+    #[test]
+    fn test_score_prefers_skill_overlap() {
+        let project = ProjectSlot::new(Uuid::new_v4(), "Rust, SQL", 1, 1, None);
+        let strong = candidate(&["Rust", "SQL"], "1");
+        let weak = candidate(&["Python"], "1");
+        assert!(score(&strong, &project) > score(&weak, &project));
+    }
+
+    #[test]
+    fn test_skills_match_whole_terms() {
+        let project = ProjectSlot::new(Uuid::new_v4(), "React, Docker, Golang, AWS, Node", 1, 1, None);
+        assert_eq!(skills_overlap(&candidate(&["C", "R", "Go"], "0"), &project), 0.0);
+        assert_eq!(skills_overlap(&candidate(&["AWS Lambda", "Node.js"], "0"), &project), 0.4);
+        assert!(!covers("c++", "c"));
+        assert!(!covers("c", "react"));
+        assert!(covers("react", "react"));
+    }
+
+    #[test]
+    fn test_score_uses_embeddings_when_present() {
+        let mut project = ProjectSlot::new(Uuid::new_v4(), "Rust", 1, 1, Some(vec![1.0, 0.0]));
+        let mut a = candidate(&["Rust"], "0");
+        let mut b = a.clone();
+        a.embedding = Some(vec![1.0, 0.0]);
+        b.embedding = Some(vec![0.0, 1.0]);
+        assert!(score(&a, &project) > score(&b, &project));
+
+        // Mismatched dimensions fall back to the non-embedding score
+        project.embedding = Some(vec![1.0, 0.0, 0.0]);
+        assert_eq!(score(&a, &project), score(&b, &project));
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]), Some(1.0));
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), Some(0.0));
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), None);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), None);
+    }
+
+    #[test]
+    fn test_assign_respects_intern_cap() {
+        let candidates: Vec<Candidate> = (0..5).map(|_| candidate(&["Rust"], "1")).collect();
+        let projects = vec![
+            ProjectSlot::new(Uuid::new_v4(), "Rust", 1, 2, None),
+            ProjectSlot::new(Uuid::new_v4(), "Rust", 2, 1, None),
+        ];
+        let assignments = assign(&candidates, &projects);
+
+        assert_eq!(assignments.len(), 3);
+        let first = assignments.iter().filter(|a| a.project_id == projects[0].id).count();
+        let second = assignments.iter().filter(|a| a.project_id == projects[1].id).count();
+        assert_eq!((first, second), (2, 1));
+
+        let unique: HashSet<Uuid> = assignments.iter().map(|a| a.resume_id).collect();
+        assert_eq!(unique.len(), 3);
+    }
+
+    #[test]
+    fn test_assign_gives_priority_projects_first_pick() {
+        let rustacean = candidate(&["Rust"], "3");
+        let projects = vec![
+            ProjectSlot::new(Uuid::new_v4(), "Rust", 0, 1, None),
+            ProjectSlot::new(Uuid::new_v4(), "Rust", 1, 1, None),
+        ];
+        let assignments = assign(std::slice::from_ref(&rustacean), &projects);
+
+        assert_eq!(assignments.len(), 1);
+        assert_eq!(assignments[0].project_id, projects[1].id);
+    }
+
+    #[test]
+    fn test_assign_needs_a_minimum_score() {
+        let unrelated = candidate(&["Photoshop"], "0");
+        let projects = vec![ProjectSlot::new(Uuid::new_v4(), "Rust", 1, 1, None)];
+        assert!(assign(std::slice::from_ref(&unrelated), &projects).is_empty());
+    }
+
+    #[test]
+    fn test_assign_skips_zero_capacity_projects() {
+        let candidates = vec![candidate(&["Rust"], "1")];
+        let projects = vec![ProjectSlot::new(Uuid::new_v4(), "Rust", 1, 0, None)];
+        assert!(assign(&candidates, &projects).is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::AppState;
+use crate::service::{ProjectService, ResumeService};
+use crate::shutdown;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+pub const DEFAULT_QUEUE: &str = "default";
+/// How long a claimed task stays invisible to other workers without a heartbeat.
+pub const DEFAULT_VISIBILITY_TIMEOUT: Duration = Duration::from_secs(300);
+/// Tasks abandoned this many times (e.g. repeated crashes) are marked failed instead of re-claimed.
+pub const MAX_TASK_ATTEMPTS: i32 = 5;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, sqlx::Type, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "task_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TaskKind {
+    Resume,
+    Zip,
+    Project,
+}
+
+#[derive(Debug, sqlx::Type, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "task_status", rename_all = "lowercase")]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueuedTask {
+    pub id: Uuid,
+    pub kind: TaskKind,
+    pub upload_id: Uuid,
+    pub filename: String,
+    pub attempts: i32,
+}
+
+/// Postgres-backed work queue. Webhook handlers enqueue into the `tasks` table and
+/// workers claim rows with `FOR UPDATE SKIP LOCKED`, so nothing is lost on restart.
+#[derive(Clone)]
+pub struct TaskQueue {
+    pool: PgPool,
+    name: String,
+    visibility_timeout: Duration,
+    notify: Arc<Notify>,
+}
+
+impl TaskQueue {
+    pub fn new(pool: PgPool) -> Self {
+        Self::with_name(pool, DEFAULT_QUEUE)
+    }
+
+    pub fn with_name(pool: PgPool, name: &str) -> Self {
+        Self {
+            pool,
+            name: name.to_string(),
+            visibility_timeout: DEFAULT_VISIBILITY_TIMEOUT,
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn visibility_timeout(mut self, timeout: Duration) -> Self {
+        self.visibility_timeout = timeout;
+        self
+    }
+
+    /// Enqueues work for an upload. A task that is already queued or running for
+    /// the same upload is not duplicated, so repeated webhooks are harmless.
+    pub async fn enqueue(
+        &self,
+        kind: TaskKind,
+        upload_id: Uuid,
+        filename: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO tasks (queue, kind, upload_id, filename)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (kind, upload_id) WHERE status IN ('queued', 'running') DO NOTHING
+            "#,
+            self.name,
+            kind as TaskKind,
+            upload_id,
+            filename
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Claims the oldest available task, including running tasks whose visibility
+    /// timeout expired because their worker died.
+    pub async fn claim(&self) -> Result<Option<QueuedTask>, sqlx::Error> {
+        let abandoned = sqlx::query!(
+            r#"
+            UPDATE tasks SET status = $1, last_error = 'Abandoned after too many attempts', updated_at = now()
+            WHERE queue = $2 AND status = $3 AND locked_until < now() AND attempts >= $4
+            "#,
+            TaskStatus::Failed as TaskStatus,
+            self.name,
+            TaskStatus::Running as TaskStatus,
+            MAX_TASK_ATTEMPTS
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if abandoned.rows_affected() > 0 {
+            tracing::warn!(
+                "Marked {} abandoned tasks as failed in queue {}",
+                abandoned.rows_affected(),
+                self.name
+            );
+        }
+
+        sqlx::query_as!(
+            QueuedTask,
+            r#"
+            UPDATE tasks
+            SET status = $1, attempts = attempts + 1, locked_until = now() + make_interval(secs => $2), updated_at = now()
+            WHERE id = (
+                SELECT id FROM tasks
+                WHERE queue = $3 AND (status = $4 OR (status = $1 AND locked_until < now()))
+                ORDER BY created_at
+                FOR UPDATE SKIP LOCKED
+                LIMIT 1
+            )
+            RETURNING id, kind as "kind: TaskKind", upload_id, filename, attempts
+            "#,
+            TaskStatus::Running as TaskStatus,
+            self.visibility_timeout.as_secs_f64(),
+            self.name,
+            TaskStatus::Queued as TaskStatus
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Pushes the visibility timeout forward for a task that is still being worked on.
+    pub async fn heartbeat(&self, task_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE tasks SET locked_until = now() + make_interval(secs => $1), updated_at = now() WHERE id = $2 AND status = $3",
+            self.visibility_timeout.as_secs_f64(),
+            task_id,
+            TaskStatus::Running as TaskStatus
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn complete(&self, task_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE tasks SET status = $1, locked_until = NULL, updated_at = now() WHERE id = $2",
+            TaskStatus::Completed as TaskStatus,
+            task_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Puts a running task back in the queue without counting the attempt, e.g. when a
+    /// shutdown interrupts it.
+    pub async fn release(&self, task_id: Uuid, reason: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE tasks SET status = $1, attempts = greatest(attempts - 1, 0), locked_until = NULL, last_error = $2, updated_at = now() WHERE id = $3 AND status = $4",
+            TaskStatus::Queued as TaskStatus,
+            reason,
+            task_id,
+            TaskStatus::Running as TaskStatus
+        )
+        .execute(&self.pool)
+        .await?;
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Waits until something is enqueued in this process or the poll interval elapses.
+    async fn wait_for_work(&self) {
+        let _ = tokio::time::timeout(POLL_INTERVAL, self.notify.notified()).await;
+    }
+}
+
+/// Starts `workers` background tasks that drain `state.queue` until a shutdown is requested.
+pub fn spawn_workers(state: AppState, workers: usize) {
+    for worker in 0..workers {
+        state
+            .shutdown
+            .spawn_worker(run_worker(state.clone(), worker));
+    }
+}
+
+async fn run_worker(state: AppState, worker: usize) {
+    let queue = state.queue.clone();
+    loop {
+        if state.shutdown.is_requested() {
+            tracing::info!("Worker {} stopped", worker);
+            return;
+        }
+        let task = match queue.claim().await {
+            Ok(Some(task)) => task,
+            Ok(None) => {
+                tokio::select! {
+                    _ = queue.wait_for_work() => {}
+                    _ = state.shutdown.requested() => {}
+                }
+                continue;
+            }
+            Err(e) => {
+                tracing::error!("Worker {} failed to claim task: {}", worker, e);
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+
+        tracing::info!(
+            "Worker {} claimed {:?} task {} for upload {} (attempt {})",
+            worker,
+            task.kind,
+            task.id,
+            task.upload_id,
+            task.attempts
+        );
+
+        let work = execute(state.clone(), task.clone());
+        tokio::pin!(work);
+
+        // Keep the lease alive while the work runs so other workers don't re-claim it
+        let mut heartbeat = tokio::time::interval(queue.visibility_timeout / 3);
+        heartbeat.tick().await;
+        loop {
+            tokio::select! {
+                _ = &mut work => break,
+                // The shutdown deadline passed; the task resumes on another worker
+                _ = state.shutdown.interrupted() => {
+                    shutdown::interrupt(&state, &task).await;
+                    return;
+                }
+                _ = heartbeat.tick() => {
+                    if let Err(e) = queue.heartbeat(task.id).await {
+                        tracing::warn!("Failed to extend lease for task {}: {}", task.id, e);
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = queue.complete(task.id).await {
+            tracing::error!("Failed to mark task {} as completed: {}", task.id, e);
+        }
+    }
+}
+
+async fn execute(state: AppState, task: QueuedTask) {
+    match task.kind {
+        TaskKind::Resume => {
+            ResumeService::new(state)
+                .process_resume_upload(task.upload_id, task.filename)
+                .await
+        }
+        TaskKind::Zip => {
+            ResumeService::new(state)
+                .handle_batch_extraction(task.upload_id, task.filename)
+                .await
+        }
+        TaskKind::Project => {
+            ProjectService::new(state)
+                .process_project_spreadsheet(task.upload_id, task.filename)
+                .await
+        }
+    }
+}
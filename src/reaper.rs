@@ -0,0 +1,243 @@
+use crate::AppState;
+use crate::events::JobEvent;
+use crate::job_state;
+use crate::queue::{MAX_TASK_ATTEMPTS, TaskKind};
+use crate::service::{DocumentStatus, ProjectService, ResumeService};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long an upload may stay `processing` without progress before it is reaped.
+pub const DEFAULT_STALE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// How often the reaper looks for stale uploads.
+pub const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What happens to an upload found stuck in `processing`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaleAction {
+    /// Reset it to `pending` and queue it again, unless it already used up
+    /// `MAX_TASK_ATTEMPTS`, in which case it fails.
+    #[default]
+    Requeue,
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReaperConfig {
+    pub timeout: Duration,
+    pub interval: Duration,
+    pub action: StaleAction,
+}
+
+impl Default for ReaperConfig {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_STALE_TIMEOUT,
+            interval: DEFAULT_SWEEP_INTERVAL,
+            action: StaleAction::default(),
+        }
+    }
+}
+
+/// An upload the reaper took out of `processing`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReapedUpload {
+    pub kind: TaskKind,
+    pub id: Uuid,
+    pub job_id: Option<Uuid>,
+    /// Queued again rather than failed.
+    pub requeued: bool,
+}
+
+struct StaleRow {
+    id: Uuid,
+    filename: String,
+    job_id: Option<Uuid>,
+    requeued: bool,
+}
+
+/// Looks for stale uploads every `config.interval`, forever.
+pub async fn run_reaper(state: AppState, config: ReaperConfig) {
+    let mut interval = tokio::time::interval(config.interval);
+    loop {
+        interval.tick().await;
+        match reap_stale(&state, &config).await {
+            Ok(reaped) if !reaped.is_empty() => {
+                tracing::warn!("Reaped {} uploads stuck in processing", reaped.len())
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to reap stale uploads: {}", e),
+        }
+    }
+}
+
+/// Takes every upload that has been `processing` for longer than `config.timeout` without
+/// progress, and whose task isn't queued or held by a live worker, out of `processing`:
+/// it is queued again or failed with an explanation, and its job is settled.
+pub async fn reap_stale(
+    state: &AppState,
+    config: &ReaperConfig,
+) -> Result<Vec<ReapedUpload>, sqlx::Error> {
+    let timeout = config.timeout.as_secs_f64();
+    let requeue = config.action == StaleAction::Requeue;
+    let error = format!(
+        "Upload was still processing after {} seconds without progress; the worker handling it most likely stopped",
+        config.timeout.as_secs()
+    );
+
+    let mut reaped = Vec::new();
+    for kind in [TaskKind::Resume, TaskKind::Zip, TaskKind::Project] {
+        for row in claim_stale(state, kind, timeout, requeue, &error).await? {
+            let status = if row.requeued {
+                DocumentStatus::Pending
+            } else {
+                DocumentStatus::Failed
+            };
+            let message = (!row.requeued).then(|| error.clone());
+            tracing::warn!(
+                "Upload {} ({:?}) was stuck in processing, {}",
+                row.id,
+                kind,
+                if row.requeued { "queueing it again" } else { "failing it" }
+            );
+
+            if let Some(job_id) = row.job_id {
+                state
+                    .events
+                    .publish(JobEvent::upload(kind, job_id, row.id, status, message))
+                    .await;
+                if !row.requeued {
+                    match kind {
+                        TaskKind::Project => {
+                            ProjectService::new(state.clone())
+                                .record_job_error(job_id, row.id, error.clone())
+                                .await
+                        }
+                        TaskKind::Resume | TaskKind::Zip => {
+                            ResumeService::new(state.clone())
+                                .record_job_error(job_id, row.id, error.clone())
+                                .await
+                        }
+                    }
+                }
+            }
+            if row.requeued {
+                state.queue.enqueue(kind, row.id, &row.filename).await?;
+            }
+            reaped.push(ReapedUpload {
+                kind,
+                id: row.id,
+                job_id: row.job_id,
+                requeued: row.requeued,
+            });
+        }
+    }
+
+    let jobs: HashSet<Uuid> = reaped.iter().filter_map(|upload| upload.job_id).collect();
+    for job_id in jobs {
+        job_state::settle(state, job_id).await;
+    }
+    Ok(reaped)
+}
+
+/// Moves one kind's stale uploads to `pending` (to be requeued) or `failed` in a single
+/// statement, so replicas sweeping at the same time never reap the same upload twice.
+async fn claim_stale(
+    state: &AppState,
+    kind: TaskKind,
+    timeout: f64,
+    requeue: bool,
+    error: &str,
+) -> Result<Vec<StaleRow>, sqlx::Error> {
+    match kind {
+        TaskKind::Resume => {
+            sqlx::query_as!(
+                StaleRow,
+                r#"
+                WITH stale AS (
+                    SELECT u.id, $2 AND coalesce((SELECT sum(t.attempts) FROM tasks t WHERE t.kind = 'resume' AND t.upload_id = u.id), 0) < $3 as requeue
+                    FROM resume_uploads u
+                    WHERE u.status = 'processing' AND u.updated_at < now() - make_interval(secs => $1)
+                      AND NOT EXISTS (
+                          SELECT 1 FROM tasks t WHERE t.kind = 'resume' AND t.upload_id = u.id
+                            AND (t.status = 'queued' OR (t.status = 'running' AND t.locked_until > now()))
+                      )
+                    FOR UPDATE SKIP LOCKED
+                )
+                UPDATE resume_uploads u SET
+                    status = CASE WHEN stale.requeue THEN 'pending'::document_status ELSE 'failed'::document_status END,
+                    error_message = CASE WHEN stale.requeue THEN NULL ELSE $4 END,
+                    updated_at = now()
+                FROM stale WHERE u.id = stale.id
+                RETURNING u.id, u.filename, u.job_id, stale.requeue as "requeued!"
+                "#,
+                timeout,
+                requeue,
+                MAX_TASK_ATTEMPTS as i64,
+                error
+            )
+            .fetch_all(&state.pool)
+            .await
+        }
+        TaskKind::Zip => {
+            sqlx::query_as!(
+                StaleRow,
+                r#"
+                WITH stale AS (
+                    SELECT u.id, $2 AND coalesce((SELECT sum(t.attempts) FROM tasks t WHERE t.kind = 'zip' AND t.upload_id = u.id), 0) < $3 as requeue
+                    FROM zip_archives u
+                    WHERE u.status = 'processing' AND u.updated_at < now() - make_interval(secs => $1)
+                      AND NOT EXISTS (
+                          SELECT 1 FROM tasks t WHERE t.kind = 'zip' AND t.upload_id = u.id
+                            AND (t.status = 'queued' OR (t.status = 'running' AND t.locked_until > now()))
+                      )
+                    FOR UPDATE SKIP LOCKED
+                )
+                UPDATE zip_archives u SET
+                    status = CASE WHEN stale.requeue THEN 'pending'::document_status ELSE 'failed'::document_status END,
+                    error_message = CASE WHEN stale.requeue THEN NULL ELSE $4 END,
+                    updated_at = now()
+                FROM stale WHERE u.id = stale.id
+                RETURNING u.id, u.filename, u.job_id, stale.requeue as "requeued!"
+                "#,
+                timeout,
+                requeue,
+                MAX_TASK_ATTEMPTS as i64,
+                error
+            )
+            .fetch_all(&state.pool)
+            .await
+        }
+        TaskKind::Project => {
+            sqlx::query_as!(
+                StaleRow,
+                r#"
+                WITH stale AS (
+                    SELECT u.id, $2 AND coalesce((SELECT sum(t.attempts) FROM tasks t WHERE t.kind = 'project' AND t.upload_id = u.id), 0) < $3 as requeue
+                    FROM project_uploads u
+                    WHERE u.status = 'processing' AND u.updated_at < now() - make_interval(secs => $1)
+                      AND NOT EXISTS (
+                          SELECT 1 FROM tasks t WHERE t.kind = 'project' AND t.upload_id = u.id
+                            AND (t.status = 'queued' OR (t.status = 'running' AND t.locked_until > now()))
+                      )
+                    FOR UPDATE SKIP LOCKED
+                )
+                UPDATE project_uploads u SET
+                    status = CASE WHEN stale.requeue THEN 'pending'::document_status ELSE 'failed'::document_status END,
+                    error_message = CASE WHEN stale.requeue THEN NULL ELSE $4 END,
+                    updated_at = now()
+                FROM stale WHERE u.id = stale.id
+                RETURNING u.id, u.filename, u.job_id, stale.requeue as "requeued!"
+                "#,
+                timeout,
+                requeue,
+                MAX_TASK_ATTEMPTS as i64,
+                error
+            )
+            .fetch_all(&state.pool)
+            .await
+        }
+    }
+}
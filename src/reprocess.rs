@@ -0,0 +1,361 @@
+use crate::AppState;
+use crate::events::JobEvent;
+use crate::queue::TaskKind;
+use crate::job_state::{JobStatus, transition_job};
+use crate::service::DocumentStatus;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Why an upload or job can't be retried.
+#[derive(Debug)]
+pub enum RetryError {
+    NotFound,
+    /// The upload or job is not in a state that can be retried; the message says why.
+    Conflict(String),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for RetryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Not found"),
+            Self::Conflict(message) => write!(f, "{}", message),
+            Self::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RetryError {}
+
+impl From<sqlx::Error> for RetryError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+/// An upload that was reset and queued again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RetriedUpload {
+    pub kind: TaskKind,
+    pub id: Uuid,
+}
+
+/// What `retry_failed` did for a job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JobRetry {
+    pub uploads: Vec<RetriedUpload>,
+    /// No upload had failed but matching had, so the job goes back to `ready` to match again.
+    pub rematch: bool,
+}
+
+struct UploadRow {
+    filename: String,
+    status: Option<DocumentStatus>,
+    job_id: Option<Uuid>,
+}
+
+/// Resets one failed upload (or partial archive) to `pending`, moves its errors in the job's
+/// `rust_error` to `rust_error.archived`, puts the job back into `processing`, publishes
+/// the upload's new status and queues it again.
+pub async fn retry_upload(
+    state: &AppState,
+    kind: TaskKind,
+    id: Uuid,
+) -> Result<RetriedUpload, RetryError> {
+    let upload = load_upload(state, kind, id).await?.ok_or(RetryError::NotFound)?;
+    let Some(status) = upload.status.filter(|&status| is_retryable(kind, status)) else {
+        return Err(RetryError::Conflict(format!(
+            "Only failed uploads can be retried, this one is {}",
+            status_name(upload.status)
+        )));
+    };
+    if let Some(job_id) = upload.job_id {
+        check_job(state, job_id).await?;
+    }
+
+    // Only the status checked above is reset, so an upload that a worker or another retry
+    // picked up in the meantime isn't run twice
+    if reset_upload(state, kind, id, status, None).await?.is_none() {
+        return Err(RetryError::Conflict(format!(
+            "Upload is no longer {}, it was retried or picked up in the meantime",
+            status_name(Some(status))
+        )));
+    }
+    if let Some(job_id) = upload.job_id {
+        archive_errors(state, job_id, kind, id).await?;
+        transition_job(state, job_id, None, JobStatus::Processing).await?;
+        state
+            .events
+            .publish(JobEvent::upload(kind, job_id, id, DocumentStatus::Pending, None))
+            .await;
+    }
+    state.queue.enqueue(kind, id, &upload.filename).await?;
+    tracing::info!("Queued {:?} upload {} for retry", kind, id);
+    Ok(RetriedUpload { kind, id })
+}
+
+/// Retries every failed upload and partial archive of a job. A job whose uploads all
+/// succeeded but whose matching failed is sent back to `ready` to be matched again.
+pub async fn retry_failed(state: &AppState, job_id: Uuid) -> Result<JobRetry, RetryError> {
+    let status = check_job(state, job_id).await?;
+
+    let failed = sqlx::query!(
+        r#"
+        SELECT 'resume' as "kind!", id, filename, status as "status: DocumentStatus" FROM resume_uploads WHERE job_id = $1 AND status = 'failed'
+        UNION ALL
+        SELECT 'zip', id, filename, status FROM zip_archives WHERE job_id = $1 AND status IN ('failed', 'partial')
+        UNION ALL
+        SELECT 'project', id, filename, status FROM project_uploads WHERE job_id = $1 AND status = 'failed'
+        "#,
+        job_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    if failed.is_empty() {
+        if status != Some(JobStatus::Failed) {
+            return Err(RetryError::Conflict("Job has nothing to retry".to_string()));
+        }
+        archive_job_errors(state, job_id).await?;
+        transition_job(state, job_id, Some(JobStatus::Failed), JobStatus::Ready).await?;
+        tracing::info!("Job {} sent back to matching", job_id);
+        return Ok(JobRetry {
+            uploads: Vec::new(),
+            rematch: true,
+        });
+    }
+
+    let mut uploads = Vec::with_capacity(failed.len());
+    for row in &failed {
+        let (Some(id), Some(filename), Some(status)) = (row.id, row.filename.as_deref(), row.status)
+        else {
+            continue;
+        };
+        let kind = match row.kind.as_str() {
+            "resume" => TaskKind::Resume,
+            "zip" => TaskKind::Zip,
+            _ => TaskKind::Project,
+        };
+        // Uploads retried or picked up since they were listed are left alone
+        if reset_upload(state, kind, id, status, None).await?.is_none() {
+            continue;
+        }
+        archive_errors(state, job_id, kind, id).await?;
+        uploads.push((RetriedUpload { kind, id }, filename));
+    }
+    if uploads.is_empty() {
+        return Err(RetryError::Conflict(
+            "The job's failed uploads were retried in the meantime".to_string(),
+        ));
+    }
+    archive_job_errors(state, job_id).await?;
+    transition_job(state, job_id, None, JobStatus::Processing).await?;
+
+    for (upload, filename) in &uploads {
+        state
+            .events
+            .publish(JobEvent::upload(upload.kind, job_id, upload.id, DocumentStatus::Pending, None))
+            .await;
+        state.queue.enqueue(upload.kind, upload.id, filename).await?;
+    }
+    tracing::info!("Queued {} failed uploads of job {} for retry", uploads.len(), job_id);
+    Ok(JobRetry {
+        uploads: uploads.into_iter().map(|(upload, _)| upload).collect(),
+        rematch: false,
+    })
+}
+
+fn is_retryable(kind: TaskKind, status: DocumentStatus) -> bool {
+    match status {
+        DocumentStatus::Failed => true,
+        DocumentStatus::Partial => kind == TaskKind::Zip,
+        _ => false,
+    }
+}
+
+fn status_name(status: Option<DocumentStatus>) -> &'static str {
+    match status {
+        None | Some(DocumentStatus::Pending) => "pending",
+        Some(DocumentStatus::Processing) => "processing",
+        Some(DocumentStatus::Completed) => "completed",
+        Some(DocumentStatus::Partial) => "partial",
+        Some(DocumentStatus::Failed) => "failed",
+        Some(DocumentStatus::Cancelled) => "cancelled",
+    }
+}
+
+/// Fails if the job doesn't exist, was cancelled, or is being matched, which a retry would
+/// race with.
+async fn check_job(state: &AppState, job_id: Uuid) -> Result<Option<JobStatus>, RetryError> {
+    let status = sqlx::query_scalar!(
+        r#"SELECT status as "status: JobStatus" FROM jobs WHERE id = $1"#,
+        job_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(RetryError::NotFound)?;
+    if status == Some(JobStatus::Matching) {
+        return Err(RetryError::Conflict(
+            "Job is being matched, retry once it finishes".to_string(),
+        ));
+    }
+    if status == Some(JobStatus::Cancelled) {
+        return Err(RetryError::Conflict(
+            "Job was cancelled".to_string(),
+        ));
+    }
+    Ok(status)
+}
+
+async fn load_upload(
+    state: &AppState,
+    kind: TaskKind,
+    id: Uuid,
+) -> Result<Option<UploadRow>, sqlx::Error> {
+    match kind {
+        TaskKind::Resume => {
+            sqlx::query_as!(
+                UploadRow,
+                r#"SELECT filename, status as "status: DocumentStatus", job_id FROM resume_uploads WHERE id = $1"#,
+                id
+            )
+            .fetch_optional(&state.pool)
+            .await
+        }
+        TaskKind::Zip => {
+            sqlx::query_as!(
+                UploadRow,
+                r#"SELECT filename, status as "status: DocumentStatus", job_id FROM zip_archives WHERE id = $1"#,
+                id
+            )
+            .fetch_optional(&state.pool)
+            .await
+        }
+        TaskKind::Project => {
+            sqlx::query_as!(
+                UploadRow,
+                r#"SELECT filename, status as "status: DocumentStatus", job_id FROM project_uploads WHERE id = $1"#,
+                id
+            )
+            .fetch_optional(&state.pool)
+            .await
+        }
+    }
+}
+
+/// Moves an upload that is still in the `expected` status back to `pending` with `message`
+/// as its error. Returns `None` if it wasn't in that status, otherwise its job.
+pub(crate) async fn reset_upload(
+    state: &AppState,
+    kind: TaskKind,
+    id: Uuid,
+    expected: DocumentStatus,
+    message: Option<&str>,
+) -> Result<Option<Option<Uuid>>, sqlx::Error> {
+    let pending = DocumentStatus::Pending;
+    let job_id = match kind {
+        TaskKind::Resume => sqlx::query_scalar!(
+            "UPDATE resume_uploads SET status = $1, error_message = $2, updated_at = now() WHERE id = $3 AND status = $4 RETURNING job_id",
+            pending as DocumentStatus,
+            message,
+            id,
+            expected as DocumentStatus
+        )
+        .fetch_optional(&state.pool)
+        .await?,
+        TaskKind::Zip => sqlx::query_scalar!(
+            "UPDATE zip_archives SET status = $1, error_message = $2, updated_at = now() WHERE id = $3 AND status = $4 RETURNING job_id",
+            pending as DocumentStatus,
+            message,
+            id,
+            expected as DocumentStatus
+        )
+        .fetch_optional(&state.pool)
+        .await?,
+        TaskKind::Project => sqlx::query_scalar!(
+            "UPDATE project_uploads SET status = $1, error_message = $2, updated_at = now() WHERE id = $3 AND status = $4 RETURNING job_id",
+            pending as DocumentStatus,
+            message,
+            id,
+            expected as DocumentStatus
+        )
+        .fetch_optional(&state.pool)
+        .await?,
+    };
+    Ok(job_id)
+}
+
+/// Moves an upload's entries in `rust_error.resumes` or `rust_error.projects` to
+/// `rust_error.archived`, so the job's errors only describe the current attempt. Archive
+/// members are found by their `zip_id`.
+async fn archive_errors(
+    state: &AppState,
+    job_id: Uuid,
+    kind: TaskKind,
+    id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let key = match kind {
+        TaskKind::Project => "projects",
+        TaskKind::Resume | TaskKind::Zip => "resumes",
+    };
+    sqlx::query!(
+        r#"
+        WITH errors AS (
+            SELECT
+                coalesce(jsonb_agg(e) FILTER (WHERE e->>'id' = $3 OR e->>'zip_id' = $3), '[]'::jsonb) as retried,
+                coalesce(jsonb_agg(e) FILTER (WHERE NOT (e->>'id' = $3 OR coalesce(e->>'zip_id', '') = $3)), '[]'::jsonb) as kept
+            FROM jobs, jsonb_array_elements(coalesce(jobs.rust_error->$2, '[]'::jsonb)) e
+            WHERE jobs.id = $1
+        )
+        UPDATE jobs SET rust_error = jsonb_set(
+            jsonb_set(rust_error, ARRAY[$2], errors.kept),
+            '{archived}',
+            coalesce(rust_error->'archived', '[]'::jsonb) || errors.retried
+        )
+        FROM errors
+        WHERE jobs.id = $1
+        "#,
+        job_id,
+        key,
+        id.to_string()
+    )
+    .execute(&state.pool)
+    .await?;
+    Ok(())
+}
+
+/// Moves why the job itself failed, `rust_error.matching` for a failed matching run and
+/// `rust_error.failure` for too many failed uploads, to `rust_error.archived`.
+async fn archive_job_errors(state: &AppState, job_id: Uuid) -> Result<(), sqlx::Error> {
+    for key in ["matching", "failure"] {
+        sqlx::query!(
+            r#"
+            UPDATE jobs SET rust_error = jsonb_set(
+                rust_error - $2::text,
+                '{archived}',
+                coalesce(rust_error->'archived', '[]'::jsonb) || jsonb_build_array(jsonb_build_object($2::text, rust_error->$2))
+            )
+            WHERE id = $1 AND rust_error ? $2
+            "#,
+            job_id,
+            key
+        )
+        .execute(&state.pool)
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_failed_uploads_and_partial_archives_are_retryable() {
+        assert!(is_retryable(TaskKind::Resume, DocumentStatus::Failed));
+        assert!(is_retryable(TaskKind::Zip, DocumentStatus::Partial));
+        assert!(!is_retryable(TaskKind::Resume, DocumentStatus::Partial));
+        assert!(!is_retryable(TaskKind::Project, DocumentStatus::Completed));
+        assert!(!is_retryable(TaskKind::Zip, DocumentStatus::Pending));
+    }
+}
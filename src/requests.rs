@@ -1,14 +1,24 @@
-pub mod openai;
-
 use crate::AppState;
-use crate::service::{ProjectService, ResumeService};
+use crate::cancel::{self, CancelError};
+use crate::events::JobEvent;
+use crate::jobs::{self, DEFAULT_JOB_LIMIT, JobProgress};
+use crate::queue::TaskKind;
+use crate::reprocess::{self, RetryError};
+use crate::service::JobStatus;
 use axum::Json;
-use axum::extract::State;
+use axum::extract::ws::rejection::WebSocketUpgradeRejection;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
 use serde::Deserialize;
 use serde_json::json;
-use tokio::task;
+use std::convert::Infallible;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
 #[derive(Deserialize, Debug)]
@@ -26,58 +36,332 @@ pub async fn handle_single_upload(
     State(state): State<AppState>,
     Json(payload): Json<WebhookPayload>,
 ) -> impl IntoResponse {
-    let filename = payload.record.filename.clone();
-    let id = payload.record.id;
     tracing::info!("scrape handler accessed");
-
-    let service = ResumeService::new(state);
-
-    task::spawn(async move {
-        service.process_resume_upload(id, filename).await;
-    });
-
-    (
-        StatusCode::ACCEPTED,
-        Json(json!({"status": "processing", "message": "We're working on it!"})),
-    )
+    enqueue(&state, TaskKind::Resume, payload.record, "We're working on it!").await
 }
 
 pub async fn handle_batch_upload(
     State(state): State<AppState>,
     Json(payload): Json<WebhookPayload>,
 ) -> impl IntoResponse {
-    let filename = payload.record.filename.clone();
-    let id = payload.record.id;
     tracing::info!("batch upload handler accessed");
-
-    let service = ResumeService::new(state);
-
-    task::spawn(async move {
-        service.handle_batch_extraction(id, filename).await;
-    });
-
-    (
-        StatusCode::ACCEPTED,
-        Json(json!({"status": "processing", "message": "We're working on it!"})),
-    )
+    enqueue(&state, TaskKind::Zip, payload.record, "We're working on it!").await
 }
 
 pub async fn handle_project_upload(
     State(state): State<AppState>,
     Json(payload): Json<WebhookPayload>,
 ) -> impl IntoResponse {
-    let filename = payload.record.filename.clone();
-    let id = payload.record.id;
     tracing::info!("project upload handler accessed");
+    enqueue(&state, TaskKind::Project, payload.record, "Processing projects...").await
+}
 
-    let service = ProjectService::new(state);
+async fn enqueue(
+    state: &AppState,
+    kind: TaskKind,
+    record: FileTrackingTableRecord,
+    message: &str,
+) -> (StatusCode, Json<serde_json::Value>) {
+    match state.queue.enqueue(kind, record.id, &record.filename).await {
+        Ok(_) => (
+            StatusCode::ACCEPTED,
+            Json(json!({"status": "processing", "message": message})),
+        ),
+        Err(e) => {
+            tracing::error!(
+                "Failed to enqueue {:?} task for upload {}: {}",
+                kind,
+                record.id,
+                e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "error", "message": "Failed to queue upload for processing"})),
+            )
+        }
+    }
+}
 
-    task::spawn(async move {
-        service.process_project_spreadsheet(id, filename).await;
-    });
+/// Returns an intern's identity and every resume attached to them, newest first.
+pub async fn get_intern(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let intern = sqlx::query!(
+        "SELECT id, email, phone, name, canonical_resume_id FROM interns WHERE id = $1",
+        id
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to load intern {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
 
-    (
-        StatusCode::ACCEPTED,
-        Json(json!({"status": "processing", "message": "Processing projects..."})),
+    let resumes = sqlx::query!(
+        r#"
+        SELECT id, filename, term, upload_id, duplicate_of,
+            to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as created_at
+        FROM resumes
+        WHERE intern_id = $1
+        ORDER BY resumes.created_at DESC
+        "#,
+        id
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to load resumes of intern {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let history: Vec<_> = resumes
+        .into_iter()
+        .map(|r| {
+            json!({
+                "id": r.id,
+                "filename": r.filename,
+                "term": r.term,
+                "upload_id": r.upload_id,
+                "duplicate_of": r.duplicate_of,
+                "created_at": r.created_at,
+                "canonical": Some(r.id) == intern.canonical_resume_id,
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "id": intern.id,
+        "email": intern.email,
+        "phone": intern.phone,
+        "name": intern.name,
+        "canonical_resume_id": intern.canonical_resume_id,
+        "resumes": history,
+    })))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct JobListQuery {
+    status: Option<JobStatus>,
+    limit: Option<i64>,
+}
+
+/// Returns a job's status, per-kind upload counts, errors and timestamps.
+pub async fn get_job(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobProgress>, StatusCode> {
+    jobs::get_job(&state.pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load job {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Lists jobs newest first, filtered by `?status=` and limited by `?limit=`.
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    Query(query): Query<JobListQuery>,
+) -> Result<Json<Vec<JobProgress>>, StatusCode> {
+    jobs::list_jobs(
+        &state.pool,
+        query.status,
+        query.limit.unwrap_or(DEFAULT_JOB_LIMIT),
     )
+    .await
+    .map(Json)
+    .map_err(|e| {
+        tracing::error!("Failed to list jobs: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Resets a failed upload (`kind` is `resume`, `zip` or `project`) and queues it again.
+pub async fn retry_upload(
+    State(state): State<AppState>,
+    Path((kind, id)): Path<(TaskKind, Uuid)>,
+) -> Result<(StatusCode, Json<serde_json::Value>), (StatusCode, Json<serde_json::Value>)> {
+    let retried = reprocess::retry_upload(&state, kind, id)
+        .await
+        .map_err(retry_error)?;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({"status": "processing", "retried": [retried]})),
+    ))
+}
+
+/// Retries every failed upload of a job, or its matching if only that failed.
+pub async fn retry_failed(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<serde_json::Value>), (StatusCode, Json<serde_json::Value>)> {
+    let retry = reprocess::retry_failed(&state, id)
+        .await
+        .map_err(retry_error)?;
+    let status = if retry.rematch { "ready" } else { "processing" };
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({"status": status, "retried": retry.uploads, "rematch": retry.rematch})),
+    ))
+}
+
+/// Cancels a job, stopping its running work and marking its unfinished uploads cancelled.
+pub async fn cancel_job(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    match cancel::cancel_job(&state, id).await {
+        Ok(cancelled) => Ok(Json(json!({"status": "cancelled", "cancelled": cancelled}))),
+        Err(e) => {
+            let status = match &e {
+                CancelError::NotFound => StatusCode::NOT_FOUND,
+                CancelError::Conflict(_) => StatusCode::CONFLICT,
+                CancelError::Database(e) => {
+                    tracing::error!("Failed to cancel job {}: {}", id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            };
+            let message = match e {
+                CancelError::Database(_) => "Failed to cancel job".to_string(),
+                e => e.to_string(),
+            };
+            Err((status, Json(json!({"status": "error", "message": message}))))
+        }
+    }
+}
+
+fn retry_error(e: RetryError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &e {
+        RetryError::NotFound => StatusCode::NOT_FOUND,
+        RetryError::Conflict(_) => StatusCode::CONFLICT,
+        RetryError::Database(e) => {
+            tracing::error!("Failed to retry: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    let message = match e {
+        RetryError::Database(_) => "Failed to queue retry".to_string(),
+        e => e.to_string(),
+    };
+    (status, Json(json!({"status": "error", "message": message})))
+}
+
+/// What `/jobs/{id}/events` sends: the job's progress, then its transitions as they happen.
+enum JobStreamMessage {
+    Snapshot(Box<JobProgress>),
+    Event(JobEvent),
+}
+
+impl JobStreamMessage {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Snapshot(_) => "snapshot",
+            Self::Event(event) => event.name(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            Self::Snapshot(job) => json!({"kind": "snapshot", "job": job}).to_string(),
+            Self::Event(event) => serde_json::to_string(event).unwrap_or_default(),
+        }
+    }
+}
+
+/// Streams a job's status transitions, over WebSocket when the request asks for an upgrade
+/// and as Server-Sent Events otherwise. The first message is a `snapshot` of the job's
+/// progress; a new one is sent whenever the client fell behind and missed events.
+pub async fn job_events(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Response {
+    // Subscribe before taking the snapshot so no transition falls between the two
+    let receiver = state.events.subscribe();
+    let snapshot = match jobs::get_job(&state.pool, id).await {
+        Ok(Some(job)) => job,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            tracing::error!("Failed to load job {}: {}", id, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let (tx, rx) = mpsc::channel(JOB_STREAM_BUFFER);
+    let _ = tx.send(JobStreamMessage::Snapshot(Box::new(snapshot))).await;
+    tokio::spawn(forward_job_events(state, id, receiver, tx));
+
+    match ws {
+        Ok(ws) => ws
+            .on_upgrade(move |socket| stream_websocket(socket, rx))
+            .into_response(),
+        Err(_) => {
+            let stream = ReceiverStream::new(rx).map(|message| {
+                Ok::<_, Infallible>(
+                    SseEvent::default()
+                        .event(message.name())
+                        .data(message.to_json()),
+                )
+            });
+            Sse::new(stream)
+                .keep_alive(KeepAlive::default())
+                .into_response()
+        }
+    }
+}
+
+/// How many messages a job stream buffers for a slow client.
+const JOB_STREAM_BUFFER: usize = 16;
+
+/// Passes this job's events on to one client until it disconnects.
+async fn forward_job_events(
+    state: AppState,
+    id: Uuid,
+    mut receiver: broadcast::Receiver<JobEvent>,
+    tx: mpsc::Sender<JobStreamMessage>,
+) {
+    loop {
+        let message = tokio::select! {
+            _ = tx.closed() => return,
+            // End the stream so the server can finish shutting down
+            _ = state.shutdown.requested() => return,
+            received = receiver.recv() => match received {
+                Ok(event) if event.job_id() == id => JobStreamMessage::Event(event),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("Job {} event stream missed {} events, resending snapshot", id, missed);
+                    match jobs::get_job(&state.pool, id).await {
+                        Ok(Some(job)) => JobStreamMessage::Snapshot(Box::new(job)),
+                        _ => continue,
+                    }
+                }
+                Err(RecvError::Closed) => return,
+            },
+        };
+        if tx.send(message).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn stream_websocket(mut socket: WebSocket, mut rx: mpsc::Receiver<JobStreamMessage>) {
+    loop {
+        tokio::select! {
+            message = rx.recv() => {
+                let Some(message) = message else { break };
+                if socket.send(Message::Text(message.to_json().into())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Clients have nothing to say; pings are answered by axum
+                Some(Ok(_)) => {}
+            },
+        }
+    }
 }
@@ -1,84 +0,0 @@
-use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-
-#[derive(Serialize)]
-pub struct LLMRequest {
-    model: String,
-    messages: [Message; 2],
-    response_format: ResponseFormat,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct Message {
-    pub role: String,
-    pub content: String,
-}
-
-#[derive(Serialize, Debug)]
-#[serde(tag = "type")] // This tells serde to use "type": "json_schema"
-pub enum ResponseFormat {
-    #[serde(rename = "json_schema")]
-    JsonSchema { json_schema: JsonSchemaDefinition },
-}
-
-#[derive(Serialize, Debug)]
-pub struct JsonSchemaDefinition {
-    pub name: String,
-    pub strict: bool,
-    pub schema: Value,
-}
-#[derive(Deserialize, Debug)]
-pub struct ChatCompletionResponse {
-    pub choices: Vec<Choice>,
-}
-
-#[derive(Deserialize, Debug)]
-pub struct Choice {
-    pub message: Message,
-}
-
-const OPENAI_MODEL: &str = "gpt-5-nano";
-
-pub async fn generate_structure_from_pdf(
-    resume_text: &str,
-    client: &reqwest::Client,
-    api_key: &str,
-    endpoint: &str,
-    schema: &Value,
-) -> Result<ChatCompletionResponse> {
-    let system_prompt = "You are a resume conversion assistant. Extract information from the user's resume text and format it into the given structure.".to_string();
-    let user_prompt = resume_text.to_string();
-
-    let request = LLMRequest {
-        model: OPENAI_MODEL.to_string(),
-        messages: [
-            Message {
-                role: "system".to_string(),
-                content: system_prompt,
-            },
-            Message {
-                role: "user".to_string(),
-                content: user_prompt,
-            },
-        ],
-        response_format: ResponseFormat::JsonSchema {
-            json_schema: JsonSchemaDefinition {
-                name: "resume_data_structuring".to_string(),
-                strict: true,
-                schema: schema.clone(),
-            },
-        },
-    };
-
-    client
-        .post(endpoint)
-        .bearer_auth(api_key)
-        .json(&request)
-        .send()
-        .await
-        .context("Failed to send request to OpenAI")?
-        .json::<ChatCompletionResponse>()
-        .await
-        .context("Failed to parse OpenAI response")
-}
@@ -0,0 +1,213 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+/// Marks an error as transient (rate limit, 5xx, network hiccup) so `retry` will try again.
+/// Any error that does not carry this marker is treated as permanent.
+#[derive(Debug)]
+pub struct TransientError {
+    pub message: String,
+    /// Server-provided delay, e.g. from a `Retry-After` header.
+    pub retry_after: Option<Duration>,
+}
+
+impl TransientError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            retry_after: None,
+        }
+    }
+
+    pub fn with_retry_after(mut self, retry_after: Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+}
+
+impl fmt::Display for TransientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for TransientError {}
+
+/// Returns the transient marker if any error in the chain carries one.
+pub fn as_transient(err: &anyhow::Error) -> Option<&TransientError> {
+    err.chain().find_map(|e| e.downcast_ref::<TransientError>())
+}
+
+/// Parses a `Retry-After` header given in seconds. HTTP-date values are ignored
+/// and fall back to the computed backoff.
+pub fn parse_retry_after(value: Option<&reqwest::header::HeaderValue>) -> Option<Duration> {
+    value
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Upper bound of the backoff before attempt `attempt + 1`: `base * 2^(attempt - 1)`, capped.
+    pub fn backoff_ceiling(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Delay before the next attempt. A server-provided `Retry-After` wins (capped at
+    /// `max_delay`); otherwise "full jitter" picks uniformly between zero and the ceiling.
+    pub fn delay_for(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(after) = retry_after {
+            return after.min(self.max_delay);
+        }
+        let ceiling = self.backoff_ceiling(attempt);
+        ceiling.mul_f64(fastrand::f64())
+    }
+}
+
+/// Runs `op` until it succeeds, fails permanently, or `policy.max_attempts` is reached.
+/// `op` receives the 1-based attempt number. The last error is returned unchanged.
+pub async fn retry<T, F, Fut>(policy: &RetryPolicy, what: &str, mut op: F) -> anyhow::Result<T>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op(attempt).await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                let Some(transient) = as_transient(&e) else {
+                    return Err(e);
+                };
+                if attempt >= policy.max_attempts {
+                    tracing::error!("{} failed after {} attempts: {:#}", what, attempt, e);
+                    return Err(e);
+                }
+                let delay = policy.delay_for(attempt, transient.retry_after);
+                tracing::warn!(
+                    "{} failed (attempt {}/{}), retrying in {:?}: {:#}",
+                    what,
+                    attempt,
+                    policy.max_attempts,
+                    delay,
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        }
+    }
+
+    #[test]
+    fn test_backoff_ceiling_grows_and_caps() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+        assert_eq!(policy.backoff_ceiling(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_ceiling(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_ceiling(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff_ceiling(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff_ceiling(40), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_delay_honors_retry_after() {
+        let policy = RetryPolicy::default();
+        assert_eq!(
+            policy.delay_for(1, Some(Duration::from_secs(7))),
+            Duration::from_secs(7)
+        );
+        // Capped so a hostile header can't park a worker for an hour
+        assert_eq!(
+            policy.delay_for(1, Some(Duration::from_secs(3600))),
+            policy.max_delay
+        );
+        assert!(policy.delay_for(3, None) <= policy.backoff_ceiling(3));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let v = reqwest::header::HeaderValue::from_static("12");
+        assert_eq!(parse_retry_after(Some(&v)), Some(Duration::from_secs(12)));
+        let date = reqwest::header::HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT");
+        assert_eq!(parse_retry_after(Some(&date)), None);
+        assert_eq!(parse_retry_after(None), None);
+    }
+
+    #[tokio::test]
+    async fn test_retry_recovers_from_transient_errors() {
+        let calls = AtomicU32::new(0);
+        let result = retry(&fast_policy(5), "test op", |attempt| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt < 3 {
+                    Err(TransientError::new("rate limited").into())
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_permanent_error() {
+        let calls = AtomicU32::new(0);
+        let result: anyhow::Result<()> = retry(&fast_policy(5), "test op", |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err(anyhow::anyhow!("bad request")) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: anyhow::Result<()> = retry(&fast_policy(3), "test op", |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err(anyhow::Error::new(TransientError::new("503")).context("upstream")) }
+        })
+        .await;
+        assert!(as_transient(&result.unwrap_err()).is_some());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -1,32 +1,65 @@
 use crate::AppState;
-use crate::requests::openai::generate_structure_from_pdf;
+use crate::archive::{self, ArchiveItem, sanitize_entry_name};
+use crate::config::StorageBackend;
+use crate::dedup::{self, DuplicatePolicy, MatchedOn};
+use crate::embeddings::{content_hash, project_embedding_input, resume_embedding_input};
+use crate::events::JobEvent;
+use crate::extraction::{ExtractedText, ExtractionMethod, extract_text};
+use crate::identity::{Identity, IdentityMatch};
+use crate::job_state::{self, run_upload, transition_job};
+use crate::llm::{Message, repair_messages, resume_messages};
+use crate::queue::TaskKind;
+use crate::retry::{TransientError, retry};
+use crate::storage::StorageProvider;
+use crate::validation::{RESUME_SCHEMA_VERSION, parse_and_validate};
+use anyhow::Context;
 use calamine::{DataType, Reader, Xlsx, open_workbook_from_rs};
 use csv::ReaderBuilder;
-use serde_json::{Value, from_str};
-use std::io::{Cursor, Read, Write};
+use serde_json::Value;
+use std::io::Cursor;
+use std::sync::Arc;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Semaphore;
 use uuid::Uuid;
 
-#[derive(Debug, sqlx::Type, serde::Serialize, serde::Deserialize)]
+pub use crate::job_state::JobStatus;
+
+#[derive(Debug, sqlx::Type, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "document_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum DocumentStatus {
     Pending,
     Processing,
     Completed,
+    /// Only used by zip archives: some members could not be processed.
+    Partial,
     Failed,
+    /// The job was cancelled before this upload finished.
+    Cancelled,
 }
 
 #[derive(Debug, sqlx::Type, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
-#[sqlx(type_name = "job_status", rename_all = "lowercase")]
-pub enum JobStatus {
-
+#[sqlx(type_name = "zip_member_status", rename_all = "lowercase")]
+pub enum ZipMemberStatus {
     Pending,
-    Processing,
-    Ready,
-    Matching,
-    Completed,
+    /// Re-uploaded to the resumes bucket; its `resume_uploads` row takes over from here.
+    Uploaded,
+    /// Not a supported resume format.
+    Skipped,
     Failed,
 }
 
+/// How many resumes `reparse_outdated` and `resolve_unassigned` load per query.
+const REPARSE_BATCH_SIZE: i64 = 50;
+/// Advisory lock held by the replica that runs `reparse_outdated`.
+pub const REPARSE_LOCK: i64 = 0x0072_6570_6172_7365;
+/// Advisory lock held by the replica that runs `resolve_unassigned`.
+pub const BACKFILL_LOCK: i64 = 0x0069_6e74_6572_6e73;
+/// Advisory lock class for `resolve_intern`; the second key is a hash of an email or phone.
+const INTERN_LOCK: i32 = 0x696e_7472;
+/// How many members of one zip archive are re-uploaded at the same time.
+const ZIP_UPLOAD_CONCURRENCY: usize = 8;
+
 pub struct ResumeService {
     state: AppState,
 }
@@ -42,18 +75,40 @@ impl ResumeService {
         status: DocumentStatus,
         error_message: Option<String>,
     ) -> Result<(), sqlx::Error> {
-        sqlx::query!(
-            "UPDATE resume_uploads SET status = $1, error_message = $2 WHERE id = $3",
+        let job_id = sqlx::query_scalar!(
+            "UPDATE resume_uploads SET status = $1, error_message = $2, updated_at = now(), started_at = CASE WHEN $1 = 'processing'::document_status THEN now() ELSE started_at END WHERE id = $3 RETURNING job_id",
             status as DocumentStatus,
             error_message,
             id
         )
-        .execute(&self.state.pool)
-        .await?;
+        .fetch_optional(&self.state.pool)
+        .await?
+        .flatten();
+        if let Some(job_id) = job_id {
+            self.state
+                .events
+                .publish(JobEvent::ResumeUpload {
+                    job_id,
+                    id,
+                    status,
+                    error: error_message,
+                })
+                .await;
+        }
         Ok(())
     }
 
-    async fn record_job_error(&self, job_id: Uuid, resume_id: Uuid, error: String) {
+    /// Counts every download and LLM attempt made for an upload, including retries.
+    async fn record_upload_attempt(&self, upload_id: Uuid) {
+        let _ = sqlx::query!(
+            "UPDATE resume_uploads SET attempts = attempts + 1, updated_at = now() WHERE id = $1",
+            upload_id
+        )
+        .execute(&self.state.pool)
+        .await;
+    }
+
+    pub(crate) async fn record_job_error(&self, job_id: Uuid, resume_id: Uuid, error: String) {
         let error_json = serde_json::json!({
             "id": resume_id,
             "error": error
@@ -68,37 +123,319 @@ impl ResumeService {
         .await;
     }
 
-    async fn maybe_mark_job_as_ready(&self, job_id: Uuid) {
-        // Check if all related uploads are in a terminal state
-        let pending_count = match sqlx::query!(
+    async fn record_zip_member_error(
+        &self,
+        job_id: Uuid,
+        zip_id: Uuid,
+        member_id: Uuid,
+        name: &str,
+        error: String,
+    ) {
+        let error_json = serde_json::json!({
+            "id": member_id,
+            "zip_id": zip_id,
+            "filename": name,
+            "error": error
+        });
+
+        let _ = sqlx::query!(
+            "UPDATE jobs SET rust_error = jsonb_set(rust_error, '{resumes}', rust_error->'resumes' || $1) WHERE id = $2",
+            error_json,
+            job_id
+        )
+        .execute(&self.state.pool)
+        .await;
+    }
+
+    /// Lists an upload in the job's `rust_error.duplicates`, with what was done about it.
+    async fn record_duplicate(
+        &self,
+        job_id: Uuid,
+        upload_id: Uuid,
+        filename: &str,
+        original: Uuid,
+        matched_on: MatchedOn,
+    ) {
+        let duplicate_json = serde_json::json!({
+            "id": upload_id,
+            "filename": filename,
+            "duplicate_of": original,
+            "matched_on": matched_on.name(),
+            "action": self.state.config.duplicate_policy.action()
+        });
+
+        let _ = sqlx::query!(
+            "UPDATE jobs SET rust_error = jsonb_set(rust_error, '{duplicates}', coalesce(rust_error->'duplicates', '[]'::jsonb) || $1) WHERE id = $2",
+            duplicate_json,
+            job_id
+        )
+        .execute(&self.state.pool)
+        .await;
+    }
+
+    /// Finds a parsed resume from another upload with the same file or text hash. A match
+    /// that is itself a duplicate resolves to the resume it was copied from.
+    async fn find_original(&self, upload_id: Uuid, matched_on: MatchedOn, hash: &str) -> Option<Uuid> {
+        let result = match matched_on {
+            MatchedOn::File => {
+                sqlx::query_scalar!(
+                    r#"
+                    SELECT coalesce(duplicate_of, id) as "id!" FROM resumes
+                    WHERE file_hash = $1 AND structured IS NOT NULL AND upload_id IS DISTINCT FROM $2
+                    ORDER BY created_at
+                    LIMIT 1
+                    "#,
+                    hash,
+                    upload_id
+                )
+                .fetch_optional(&self.state.pool)
+                .await
+            }
+            MatchedOn::Text => {
+                sqlx::query_scalar!(
+                    r#"
+                    SELECT coalesce(duplicate_of, id) as "id!" FROM resumes
+                    WHERE text_hash = $1 AND structured IS NOT NULL AND upload_id IS DISTINCT FROM $2
+                    ORDER BY created_at
+                    LIMIT 1
+                    "#,
+                    hash,
+                    upload_id
+                )
+                .fetch_optional(&self.state.pool)
+                .await
+            }
+        };
+        result.unwrap_or_else(|e| {
+            tracing::error!("Failed to look up duplicates of upload {}: {}", upload_id, e);
+            None
+        })
+    }
+
+    /// Copies the parsed data and embedding of `original` into the new resume row.
+    async fn link_resume(&self, resume_id: Uuid, original: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
             r#"
-            SELECT 
-                (SELECT count(*) FROM resume_uploads WHERE job_id = $1 AND status NOT IN ('completed', 'failed')) +
-                (SELECT count(*) FROM zip_archives WHERE job_id = $1 AND status NOT IN ('completed', 'failed')) +
-                (SELECT count(*) FROM project_uploads WHERE job_id = $1 AND status NOT IN ('completed', 'failed'))
-            as count
+            UPDATE resumes r SET
+                text = o.text,
+                text_hash = o.text_hash,
+                extraction_method = o.extraction_method,
+                structured = o.structured,
+                schema_version = o.schema_version,
+                embedding = o.embedding,
+                embedding_model = o.embedding_model,
+                embedding_hash = o.embedding_hash,
+                embedded_at = o.embedded_at,
+                duplicate_of = o.id
+            FROM resumes o
+            WHERE r.id = $1 AND o.id = $2
             "#,
-            job_id
+            resume_id,
+            original
         )
-        .fetch_one(&self.state.pool)
-        .await {
-            Ok(r) => r.count.unwrap_or(0),
-            Err(_) => return,
+        .execute(&self.state.pool)
+        .await?;
+        self.resolve_stored_intern(resume_id).await?;
+        Ok(())
+    }
+
+    /// Deletes the resume row of a duplicate that is skipped. A row that can't be deleted,
+    /// e.g. because matches already reference it, is linked to the original instead.
+    async fn discard_resume(&self, resume_id: Uuid, original: Uuid) -> Result<(), sqlx::Error> {
+        if let Err(e) = sqlx::query!("DELETE FROM resumes WHERE id = $1", resume_id)
+            .execute(&self.state.pool)
+            .await
+        {
+            tracing::warn!(
+                "Failed to delete duplicate resume {}, linking it to {} instead: {}",
+                resume_id,
+                original,
+                e
+            );
+            self.link_resume(resume_id, original).await?;
+        }
+        Ok(())
+    }
+
+    /// Fails an upload whose duplicate resume row could not be discarded or linked, as
+    /// `action` says, rather than completing it with a row that is neither.
+    async fn fail_duplicate(
+        &self,
+        upload_id: Uuid,
+        job_id: Option<Uuid>,
+        resume_id: Uuid,
+        action: &str,
+        e: sqlx::Error,
+    ) {
+        let err_msg = format!("Failed to {} duplicate resume {}: {}", action, resume_id, e);
+        tracing::error!("{}", err_msg);
+        let _ = self
+            .update_resume_upload_status(upload_id, DocumentStatus::Failed, Some(err_msg.clone()))
+            .await;
+        if let Some(job_id) = job_id {
+            self.record_job_error(job_id, upload_id, err_msg).await;
+        }
+    }
+
+    /// Attaches a parsed resume to the intern it belongs to, creating the intern when none
+    /// matches its contact details, and makes the newest resume of every intern involved
+    /// its canonical one. Returns `None` for resumes without a usable email or phone, which
+    /// stay on their own.
+    pub async fn resolve_intern(
+        &self,
+        resume_id: Uuid,
+        identity: &Identity,
+    ) -> Result<Option<Uuid>, sqlx::Error> {
+        let mut tx = self.state.pool.begin().await?;
+        // Resumes sharing an email or phone are resolved one at a time, so two resumes of a
+        // new intern can't both create one. Keys are locked in order to avoid deadlocks.
+        let mut keys: Vec<String> = [("email", &identity.email), ("phone", &identity.phone)]
+            .into_iter()
+            .filter_map(|(field, value)| value.as_ref().map(|value| format!("{field}:{value}")))
+            .collect();
+        keys.sort();
+        for key in keys {
+            sqlx::query!("SELECT pg_advisory_xact_lock($1, hashtext($2))", INTERN_LOCK, key)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let previous = sqlx::query_scalar!("SELECT intern_id FROM resumes WHERE id = $1", resume_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let intern_id = if !identity.has_contact() {
+            None
+        } else {
+            let candidates = sqlx::query!(
+                "SELECT id, email, phone, name FROM interns WHERE email = $1 OR phone = $2 ORDER BY created_at",
+                identity.email,
+                identity.phone
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+
+            // The strongest match wins; ties go to the oldest intern
+            let mut best: Option<(IdentityMatch, Uuid)> = None;
+            for candidate in candidates {
+                let known = Identity {
+                    email: candidate.email,
+                    phone: candidate.phone,
+                    name: candidate.name,
+                };
+                if let Some(strength) = identity.matches(&known)
+                    && best.is_none_or(|(current, _)| strength > current)
+                {
+                    best = Some((strength, candidate.id));
+                }
+            }
+
+            let id = match best {
+                Some((_, id)) => {
+                    sqlx::query!(
+                        "UPDATE interns SET email = coalesce(email, $2), phone = coalesce(phone, $3), name = coalesce($4, name) WHERE id = $1",
+                        id,
+                        identity.email,
+                        identity.phone,
+                        identity.name
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                    id
+                }
+                None => {
+                    sqlx::query_scalar!(
+                        "INSERT INTO interns (email, phone, name) VALUES ($1, $2, $3) RETURNING id",
+                        identity.email,
+                        identity.phone,
+                        identity.name
+                    )
+                    .fetch_one(&mut *tx)
+                    .await?
+                }
+            };
+            Some(id)
         };
 
-        if pending_count == 0 {
-            let _ = sqlx::query!(
-                "UPDATE jobs SET status = $1 WHERE id = $2 AND status = $3",
-                JobStatus::Ready as JobStatus,
-                job_id,
-                JobStatus::Processing as JobStatus
+        sqlx::query!(
+            "UPDATE resumes SET intern_id = $1 WHERE id = $2",
+            intern_id,
+            resume_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // A re-parsed resume may have moved to another intern, so both are refreshed
+        let mut affected: Vec<Uuid> = previous.into_iter().chain(intern_id).collect();
+        affected.dedup();
+        for id in affected {
+            sqlx::query!(
+                r#"
+                UPDATE interns SET
+                    canonical_resume_id = (
+                        SELECT id FROM resumes WHERE intern_id = $1 ORDER BY created_at DESC LIMIT 1
+                    ),
+                    updated_at = now()
+                WHERE id = $1
+                "#,
+                id
             )
-            .execute(&self.state.pool)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(intern_id)
+    }
+
+    /// Resolves the intern of a resume from the structured data already stored for it.
+    async fn resolve_stored_intern(&self, resume_id: Uuid) -> Result<Option<Uuid>, sqlx::Error> {
+        let structured = sqlx::query_scalar!("SELECT structured FROM resumes WHERE id = $1", resume_id)
+            .fetch_one(&self.state.pool)
+            .await?;
+        let identity = structured
+            .map(|structured| Identity::from_structured(&structured))
+            .unwrap_or_default();
+        self.resolve_intern(resume_id, &identity).await
+    }
+
+    /// Completes an upload that was skipped or linked as a duplicate.
+    async fn finish_duplicate(
+        &self,
+        upload_id: Uuid,
+        job_id: Option<Uuid>,
+        filename: &str,
+        original: Uuid,
+        matched_on: MatchedOn,
+    ) {
+        tracing::info!(
+            "Upload {} (filename: {}) is a duplicate of resume {} by {}, {}",
+            upload_id,
+            filename,
+            original,
+            matched_on.name(),
+            self.state.config.duplicate_policy.action()
+        );
+        let _ = self
+            .update_resume_upload_status(upload_id, DocumentStatus::Completed, None)
             .await;
+        if let Some(job_id) = job_id {
+            self.record_duplicate(job_id, upload_id, filename, original, matched_on)
+                .await;
         }
     }
 
+    /// Parses one uploaded resume, then settles its job. Stops, and marks the upload
+    /// cancelled, if its job is cancelled.
     pub async fn process_resume_upload(&self, upload_id: Uuid, filename: String) {
+        let work = self.run_resume_upload(upload_id, filename);
+        if run_upload(&self.state, TaskKind::Resume, upload_id, work).await.is_none() {
+            let _ = self
+                .update_resume_upload_status(upload_id, DocumentStatus::Cancelled, None)
+                .await;
+        }
+    }
+
+    async fn run_resume_upload(&self, upload_id: Uuid, filename: String) {
         let _permit = self
             .state
             .semaphore
@@ -126,23 +463,37 @@ impl ResumeService {
             .update_resume_upload_status(upload_id, DocumentStatus::Processing, None)
             .await;
 
-        // Mark job as processing if it was pending
-        if let Some(job_id) = upload_record.job_id {
+        // Uploads re-uploaded from an archive point back at their zip member
+        if let Some(zip_id) = upload_record.zip_id {
             let _ = sqlx::query!(
-                "UPDATE jobs SET status = $1 WHERE id = $2 AND status = $3",
-                JobStatus::Processing as JobStatus,
-                job_id,
-                JobStatus::Pending as JobStatus
+                "UPDATE zip_members SET resume_upload_id = $1 WHERE zip_id = $2 AND object_key = $3",
+                upload_id,
+                zip_id,
+                filename
             )
             .execute(&self.state.pool)
             .await;
         }
 
+        // Mark job as processing if it was pending
+        if let Some(job_id) = upload_record.job_id {
+            let _ = transition_job(&self.state, job_id, Some(JobStatus::Pending), JobStatus::Processing).await;
+        }
+
         // Download
-        let pdf_data = match self.state.storage.get_object("resumes", &filename).await {
+        let key = filename.as_str();
+        let data = match retry(&self.state.config.retry_policy(), "Resume download", |_| async move {
+            self.record_upload_attempt(upload_id).await;
+            self.state
+                .storage
+                .get_object(&self.state.config.storage.buckets.resumes, key)
+                .await
+        })
+        .await
+        {
             Ok(data) => data,
             Err(e) => {
-                let err_msg = format!("Failed to download pdf: {}", e);
+                let err_msg = format!("Failed to download resume: {}", e);
                 tracing::error!(
                     "{}, filename {}, upload_id {}",
                     err_msg,
@@ -163,16 +514,60 @@ impl ResumeService {
             }
         };
 
+        // A retried or redelivered upload reuses its resume row instead of adding another
+        let existing = sqlx::query_scalar!(
+            "SELECT id FROM resumes WHERE upload_id = $1 ORDER BY created_at LIMIT 1",
+            upload_id
+        )
+        .fetch_optional(&self.state.pool)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to look up resume of upload {}: {}", upload_id, e);
+            None
+        });
+
+        // A file that was parsed before doesn't need another LLM call
+        let file_hash = dedup::file_hash(&data);
+        let mut duplicate = self
+            .find_original(upload_id, MatchedOn::File, &file_hash)
+            .await
+            .map(|original| (original, MatchedOn::File));
+        if let Some((original, matched_on)) = duplicate
+            && self.state.config.duplicate_policy == DuplicatePolicy::Skip
+        {
+            if let Some(resume_id) = existing
+                && let Err(e) = self.discard_resume(resume_id, original).await
+            {
+                self.fail_duplicate(upload_id, upload_record.job_id, resume_id, "discard", e).await;
+                return;
+            }
+            self.finish_duplicate(upload_id, upload_record.job_id, &filename, original, matched_on)
+                .await;
+            return;
+        }
+
         // Create resume record
-        let resume_id = Uuid::new_v4();
+        let resume_id = existing.unwrap_or_else(Uuid::new_v4);
         if let Err(e) = sqlx::query!(
-            "INSERT INTO resumes (id, user_id, filename, term, zip_id, upload_id) VALUES ($1, $2, $3, $4, $5, $6)",
+            r#"
+            INSERT INTO resumes (id, user_id, filename, term, zip_id, upload_id, file_hash, duplicate_of)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (id) DO UPDATE SET
+                user_id = EXCLUDED.user_id,
+                filename = EXCLUDED.filename,
+                term = EXCLUDED.term,
+                zip_id = EXCLUDED.zip_id,
+                file_hash = EXCLUDED.file_hash,
+                duplicate_of = EXCLUDED.duplicate_of
+            "#,
             resume_id,
             upload_record.user_id,
             filename,
             upload_record.term,
             upload_record.zip_id,
-            upload_id
+            upload_id,
+            file_hash,
+            duplicate.map(|(original, _)| original)
         )
         .execute(&self.state.pool)
         .await {
@@ -185,14 +580,92 @@ impl ResumeService {
             return;
         }
 
-        // Parse and process
-        match self
-            .process_single_pdf(&pdf_data, &filename, resume_id)
-            .await
+        if let Some((original, matched_on)) = duplicate
+            && self.state.config.duplicate_policy == DuplicatePolicy::Link
         {
-            Some((pdf_text, parsed_json)) => {
+            if let Err(e) = self.link_resume(resume_id, original).await {
+                self.fail_duplicate(upload_id, upload_record.job_id, resume_id, "link", e)
+                    .await;
+                return;
+            }
+            self.finish_duplicate(upload_id, upload_record.job_id, &filename, original, matched_on)
+                .await;
+            return;
+        }
+
+        // Parse and process. The text is checked against earlier resumes before the LLM call,
+        // which catches the same resume exported to a different file.
+        let processed = match self.extract_document(&data, &filename, resume_id).await {
+            Ok(extracted) => {
+                let text_hash = dedup::text_hash(&extracted.text);
+                let _ = sqlx::query!(
+                    "UPDATE resumes SET text_hash = $1 WHERE id = $2",
+                    text_hash,
+                    resume_id
+                )
+                .execute(&self.state.pool)
+                .await;
+                if duplicate.is_none()
+                    && let Some(original) =
+                        self.find_original(upload_id, MatchedOn::Text, &text_hash).await
+                {
+                    duplicate = Some((original, MatchedOn::Text));
+                    match self.state.config.duplicate_policy {
+                        DuplicatePolicy::Skip => {
+                            if let Err(e) = self.discard_resume(resume_id, original).await {
+                                self.fail_duplicate(upload_id, upload_record.job_id, resume_id, "discard", e)
+                                    .await;
+                                return;
+                            }
+                        }
+                        DuplicatePolicy::Link => {
+                            if let Err(e) = self.link_resume(resume_id, original).await {
+                                self.fail_duplicate(upload_id, upload_record.job_id, resume_id, "link", e)
+                                    .await;
+                                return;
+                            }
+                        }
+                        DuplicatePolicy::Reprocess => {
+                            let _ = sqlx::query!(
+                                "UPDATE resumes SET duplicate_of = $1 WHERE id = $2",
+                                original,
+                                resume_id
+                            )
+                            .execute(&self.state.pool)
+                            .await;
+                        }
+                    }
+                    if self.state.config.duplicate_policy != DuplicatePolicy::Reprocess {
+                        self.finish_duplicate(
+                            upload_id,
+                            upload_record.job_id,
+                            &filename,
+                            original,
+                            MatchedOn::Text,
+                        )
+                        .await;
+                        return;
+                    }
+                }
+                self.structure_text(&extracted.text, &filename, resume_id, Some(upload_id))
+                    .await
+                    .map(|parsed_json| (extracted, parsed_json))
+            }
+            Err(e) => Err(e),
+        };
+
+        // Reprocessed duplicates are parsed like new resumes but still show up in the summary
+        if let (Some((original, matched_on)), Some(job_id)) = (duplicate, upload_record.job_id) {
+            self.record_duplicate(job_id, upload_id, &filename, original, matched_on)
+                .await;
+        }
+
+        match processed {
+            Ok((extracted, parsed_json)) => {
+                let embedding_input = resume_embedding_input(&parsed_json);
+                let identity = Identity::from_structured(&parsed_json);
                 match self
-                    .update_resume_record(resume_id, pdf_text, parsed_json)
+                    .update_resume_record(resume_id, extracted, parsed_json)
                     .await
                 {
                     Ok(_) => {
@@ -201,6 +674,22 @@ impl ResumeService {
                             resume_id,
                             filename
                         );
+                        if let Err(e) = self.resolve_intern(resume_id, &identity).await {
+                            tracing::error!("Failed to resolve intern of resume {}: {}", resume_id, e);
+                        }
+                        // Embedding failures are logged but do not fail the upload;
+                        // the structured data is already persisted and can be re-embedded.
+                        if let Err(e) = EmbeddingService::new(self.state.clone())
+                            .embed_resume(resume_id, embedding_input)
+                            .await
+                        {
+                            tracing::error!(
+                                "Failed to embed resume {} (filename: {}): {:#}",
+                                resume_id,
+                                filename,
+                                e
+                            );
+                        }
                         let _ = self
                             .update_resume_upload_status(upload_id, DocumentStatus::Completed, None)
                             .await;
@@ -226,9 +715,9 @@ impl ResumeService {
                     }
                 }
             }
-            None => {
-                let err_msg = "PDF processing or LLM parsing failed".to_string();
-                tracing::warn!(
+            Err(e) => {
+                let err_msg = format!("Text extraction or LLM parsing failed: {:#}", e);
+                tracing::error!(
                     "{}, filename {}, resume_id {}",
                     err_msg,
                     filename,
@@ -246,118 +735,400 @@ impl ResumeService {
                 }
             }
         }
+    }
 
-        // Finalize job status if this was the last part
-        if let Some(job_id) = upload_record.job_id {
-            self.maybe_mark_job_as_ready(job_id).await;
+    /// Sends the conversation to the LLM, retrying transient failures according to `AppState::retry`.
+    async fn request_structure(
+        &self,
+        messages: &[Message],
+        upload_id: Option<Uuid>,
+    ) -> anyhow::Result<String> {
+        retry(&self.state.config.retry_policy(), "LLM request", |_| async move {
+            if let Some(upload_id) = upload_id {
+                self.record_upload_attempt(upload_id).await;
+            }
+            self.state
+                .llm
+                .complete_json(messages, &self.state.resume_schema)
+                .await
+        })
+        .await
+        .with_context(|| format!("{} request failed", self.state.llm.name()))
+    }
+
+    /// Extracts the text of a resume in any supported format, falling back to OCR for
+    /// scanned PDFs, and asks the LLM to structure it.
+    pub async fn process_document(
+        &self,
+        data: &[u8],
+        filename: &str,
+        id: Uuid,
+        upload_id: Uuid,
+    ) -> anyhow::Result<(ExtractedText, Value)> {
+        let extracted = self.extract_document(data, filename, id).await?;
+        let parsed_json = self
+            .structure_text(&extracted.text, filename, id, Some(upload_id))
+            .await?;
+        Ok((extracted, parsed_json))
+    }
+
+    /// Extracts the text of a resume, falling back to OCR for scanned PDFs.
+    async fn extract_document(
+        &self,
+        data: &[u8],
+        filename: &str,
+        id: Uuid,
+    ) -> anyhow::Result<ExtractedText> {
+        let extracted = extract_text(
+            data,
+            filename,
+            self.state.ocr.as_deref(),
+            self.state.config.archive.max_entry_bytes,
+        )
+        .await?;
+        if extracted.method == ExtractionMethod::Ocr {
+            tracing::info!("Used OCR for filename {}, id {}", filename, id);
         }
+        Ok(extracted)
     }
 
-    pub async fn process_single_pdf(
+    /// Asks the LLM to structure resume text. The reply is validated against
+    /// `AppState::resume_schema`; if it doesn't conform and `schema_repair` is
+    /// enabled, the violations are sent back to the model once for a corrected version.
+    async fn structure_text(
         &self,
-        pdf_data: &[u8],
+        text: &str,
         filename: &str,
         id: Uuid,
-    ) -> Option<(String, Value)> {
-        let pdf_text = match pdf_extract::extract_text_from_mem(pdf_data) {
-            Ok(text) => text,
-            Err(e) => {
-                tracing::error!(
-                    "Failed to extract text from PDF for filename {}, id {}: {}",
+        upload_id: Option<Uuid>,
+    ) -> anyhow::Result<Value> {
+        let mut messages = resume_messages(text);
+        let content = self.request_structure(&messages, upload_id).await?;
+
+        let parsed_json = match parse_and_validate(&self.state.resume_schema, &content) {
+            Ok(json) => json,
+            Err(e) if self.state.config.llm.schema_repair => {
+                tracing::warn!(
+                    "LLM output for filename {}, id {} failed validation, requesting repair: {}",
                     filename,
                     id,
                     e
                 );
-                return None;
+                messages.extend(repair_messages(&content, &e.errors));
+                let repaired = self.request_structure(&messages, upload_id).await?;
+                parse_and_validate(&self.state.resume_schema, &repaired)
+                    .context("Repaired LLM output is still invalid")?
             }
+            Err(e) => return Err(e.into()),
         };
 
-        let response = generate_structure_from_pdf(
-            &pdf_text,
-            &self.state.http_client,
-            &self.state.openai_api_key,
-            &self.state.openai_endpoint,
-            &self.state.resume_schema,
-        )
-        .await;
-        match response {
-            Ok(r) => {
-                if let Some(choice) = r.choices.first() {
-                    let raw_content = &choice.message.content;
-                    match from_str::<Value>(raw_content) {
-                        Ok(parsed_json) => {
-                            tracing::info!(
-                                "LLM-generated JSON received for filename {}, id {}",
-                                filename,
-                                id
-                            );
-                            Some((pdf_text, parsed_json))
-                        }
-                        Err(e) => {
-                            tracing::error!(
-                                "LLM returned invalid JSON for filename {}, id {}: {}",
-                                filename,
-                                id,
-                                e
-                            );
-                            None
-                        }
-                    }
-                } else {
-                    tracing::error!(
-                        "No choices returned from LLM for filename {}, id {}",
-                        filename,
-                        id
-                    );
-                    None
-                }
-            }
-            Err(e) => {
-                tracing::error!(
-                    "LLM request failed for filename {}, id {}: {:#?}",
-                    filename,
-                    id,
-                    e
-                );
-                None
-            }
-        }
+        tracing::info!(
+            "LLM-generated JSON received for filename {}, id {}",
+            filename,
+            id
+        );
+        Ok(parsed_json)
     }
 
     pub async fn update_resume_record(
         &self,
         id: Uuid,
-        text: String,
+        extracted: ExtractedText,
         structured_json: Value,
     ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
         sqlx::query!(
-            "UPDATE resumes SET text = $1, structured = $2 WHERE id = $3",
-            text,
+            "UPDATE resumes SET text = $1, extraction_method = $2, structured = $3, schema_version = $4 WHERE id = $5",
+            extracted.text,
+            extracted.method as ExtractionMethod,
             structured_json,
+            RESUME_SCHEMA_VERSION,
             id
         )
         .execute(&self.state.pool)
         .await
     }
 
+    async fn update_structured(&self, id: Uuid, structured_json: Value) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE resumes SET structured = $1, schema_version = $2 WHERE id = $3",
+            structured_json,
+            RESUME_SCHEMA_VERSION,
+            id
+        )
+        .execute(&self.state.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Re-runs LLM parsing on stored resume text for every resume parsed with an older
+    /// `RESUME_SCHEMA_VERSION`, then refreshes its embedding. Resumes that fail are
+    /// logged and left on their old version. Only one replica runs this at a time; the
+    /// others return right away. Returns the number of resumes upgraded.
+    pub async fn reparse_outdated(&self) -> anyhow::Result<usize> {
+        let Some(_lock) = try_advisory_lock(&self.state.pool, REPARSE_LOCK).await? else {
+            tracing::info!("Another replica is re-parsing outdated resumes");
+            return Ok(0);
+        };
+
+        let mut attempted: Vec<Uuid> = Vec::new();
+        let mut upgraded = 0;
+
+        loop {
+            let batch = sqlx::query!(
+                r#"
+                SELECT id, filename, text as "text!", upload_id FROM resumes
+                WHERE text IS NOT NULL
+                  AND (schema_version IS NULL OR schema_version < $1)
+                  AND NOT (id = ANY($2))
+                ORDER BY created_at
+                LIMIT $3
+                "#,
+                RESUME_SCHEMA_VERSION,
+                &attempted,
+                REPARSE_BATCH_SIZE
+            )
+            .fetch_all(&self.state.pool)
+            .await?;
+
+            if batch.is_empty() {
+                break;
+            }
+
+            for resume in batch {
+                attempted.push(resume.id);
+                let _permit = self
+                    .state
+                    .semaphore
+                    .acquire()
+                    .await
+                    .expect("Semaphore closed");
+
+                let parsed_json = match self
+                    .structure_text(&resume.text, &resume.filename, resume.id, resume.upload_id)
+                    .await
+                {
+                    Ok(json) => json,
+                    Err(e) => {
+                        tracing::error!(
+                            "Failed to re-parse resume {} (filename: {}): {:#}",
+                            resume.id,
+                            resume.filename,
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+                let embedding_input = resume_embedding_input(&parsed_json);
+                let identity = Identity::from_structured(&parsed_json);
+                self.update_structured(resume.id, parsed_json).await?;
+                upgraded += 1;
+
+                if let Err(e) = self.resolve_intern(resume.id, &identity).await {
+                    tracing::error!("Failed to resolve intern of resume {}: {}", resume.id, e);
+                }
+
+                if let Err(e) = EmbeddingService::new(self.state.clone())
+                    .embed_resume(resume.id, embedding_input)
+                    .await
+                {
+                    tracing::error!("Failed to embed re-parsed resume {}: {:#}", resume.id, e);
+                }
+            }
+        }
+
+        if upgraded > 0 {
+            tracing::info!(
+                "Re-parsed {} resumes to schema version {}",
+                upgraded,
+                RESUME_SCHEMA_VERSION
+            );
+        }
+        Ok(upgraded)
+    }
+
+    /// Attaches every parsed resume that has no intern yet, e.g. ones stored before interns
+    /// were tracked. Only one replica runs it at a time. Returns the number of resumes
+    /// attached to an intern.
+    pub async fn resolve_unassigned(&self) -> anyhow::Result<usize> {
+        let Some(_lock) = try_advisory_lock(&self.state.pool, BACKFILL_LOCK).await? else {
+            tracing::info!("Another replica is attaching existing resumes to interns");
+            return Ok(0);
+        };
+        let mut attempted: Vec<Uuid> = Vec::new();
+        let mut resolved = 0;
+
+        loop {
+            let batch = sqlx::query_scalar!(
+                r#"
+                SELECT id FROM resumes
+                WHERE structured IS NOT NULL AND intern_id IS NULL AND NOT (id = ANY($1))
+                ORDER BY created_at
+                LIMIT $2
+                "#,
+                &attempted,
+                REPARSE_BATCH_SIZE
+            )
+            .fetch_all(&self.state.pool)
+            .await?;
+
+            if batch.is_empty() {
+                break;
+            }
+
+            for resume_id in batch {
+                attempted.push(resume_id);
+                if self.resolve_stored_intern(resume_id).await?.is_some() {
+                    resolved += 1;
+                }
+            }
+        }
+
+        if resolved > 0 {
+            tracing::info!("Attached {} resumes to interns", resolved);
+        }
+        Ok(resolved)
+    }
+
     async fn update_zip_status(
         &self,
         id: Uuid,
         status: DocumentStatus,
         error_message: Option<String>,
     ) -> Result<(), sqlx::Error> {
-        sqlx::query!(
-            "UPDATE zip_archives SET status = $1, error_message = $2 WHERE id = $3",
+        let job_id = sqlx::query_scalar!(
+            "UPDATE zip_archives SET status = $1, error_message = $2, updated_at = now(), started_at = CASE WHEN $1 = 'processing'::document_status THEN now() ELSE started_at END WHERE id = $3 RETURNING job_id",
             status as DocumentStatus,
             error_message,
             id
         )
+        .fetch_optional(&self.state.pool)
+        .await?
+        .flatten();
+        if let Some(job_id) = job_id {
+            self.state
+                .events
+                .publish(JobEvent::ZipArchive {
+                    job_id,
+                    id,
+                    status,
+                    error: error_message,
+                })
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Creates or, when an archive is processed again, resets the row for one member.
+    async fn record_zip_member(
+        &self,
+        zip_id: Uuid,
+        name: &str,
+        size: i64,
+        object_key: Option<&str>,
+        status: ZipMemberStatus,
+        error_message: Option<&str>,
+    ) -> Result<Uuid, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO zip_members (zip_id, name, size, object_key, status, error_message)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (zip_id, name) DO UPDATE SET
+                size = EXCLUDED.size,
+                object_key = EXCLUDED.object_key,
+                status = EXCLUDED.status,
+                error_message = EXCLUDED.error_message
+            RETURNING id
+            "#,
+            zip_id,
+            name,
+            size,
+            object_key,
+            status as ZipMemberStatus,
+            error_message
+        )
+        .fetch_one(&self.state.pool)
+        .await?;
+        Ok(row.id)
+    }
+
+    async fn update_zip_member_status(
+        &self,
+        id: Uuid,
+        status: ZipMemberStatus,
+        error_message: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE zip_members SET status = $1, error_message = $2 WHERE id = $3",
+            status as ZipMemberStatus,
+            error_message,
+            id
+        )
         .execute(&self.state.pool)
         .await?;
         Ok(())
     }
 
+    /// Creates the `resume_uploads` row of a re-uploaded zip member and queues it. Supabase
+    /// does this through its storage webhook; local storage has no webhook, so the archive
+    /// hands its members to the queue itself.
+    async fn queue_extracted_resume(&self, zip_id: Uuid, object_key: &str) -> Result<(), sqlx::Error> {
+        let upload_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO resume_uploads (id, filename, status, user_id, job_id, zip_id, term)
+            SELECT gen_random_uuid(), $2, 'pending', user_id, job_id, id, term FROM zip_archives WHERE id = $1
+            RETURNING id
+            "#,
+            zip_id,
+            object_key
+        )
+        .fetch_one(&self.state.pool)
+        .await?;
+        self.state
+            .queue
+            .enqueue(TaskKind::Resume, upload_id, object_key)
+            .await
+    }
+
+    /// Records a member that will not produce a resume, on its row and in the job's errors.
+    async fn drop_zip_member(
+        &self,
+        zip_id: Uuid,
+        job_id: Option<Uuid>,
+        name: &str,
+        size: i64,
+        status: ZipMemberStatus,
+        error: String,
+    ) {
+        tracing::warn!("Dropping {} from zip {}: {}", name, zip_id, error);
+        let member_id = match self
+            .record_zip_member(zip_id, name, size, None, status, Some(&error))
+            .await
+        {
+            Ok(id) => id,
+            Err(e) => {
+                tracing::error!("Failed to record zip member {}: {}", name, e);
+                return;
+            }
+        };
+        if let Some(job_id) = job_id {
+            self.record_zip_member_error(job_id, zip_id, member_id, name, error)
+                .await;
+        }
+    }
+
+    /// Extracts an uploaded archive and re-uploads its resumes, then settles its job. Stops,
+    /// and marks the archive cancelled, if its job is cancelled.
     pub async fn handle_batch_extraction(&self, id: Uuid, filename: String) {
+        let work = self.run_batch_extraction(id, filename);
+        if run_upload(&self.state, TaskKind::Zip, id, work).await.is_none() {
+            let _ = self
+                .update_zip_status(id, DocumentStatus::Cancelled, None)
+                .await;
+        }
+    }
+
+    async fn run_batch_extraction(&self, id: Uuid, filename: String) {
         let _permit = self
             .state
             .semaphore
@@ -384,100 +1155,178 @@ impl ResumeService {
 
         // Mark job as processing
         if let Some(job_id) = zip_record.job_id {
-            let _ = sqlx::query!(
-                "UPDATE jobs SET status = $1 WHERE id = $2 AND status = $3",
-                JobStatus::Processing as JobStatus,
-                job_id,
-                JobStatus::Pending as JobStatus
-            )
-            .execute(&self.state.pool)
-            .await;
+            let _ = transition_job(&self.state, job_id, Some(JobStatus::Pending), JobStatus::Processing).await;
         }
 
-        // Download
-        let zip_data = match self
-            .state
-            .storage
-            .get_object("zip-archives", &filename)
-            .await
-        {
-            Ok(data) => data,
-            Err(e) => {
-                let err_msg = format!("Failed to download zip: {}", e);
-                tracing::error!("{}, filename {}, id {}", err_msg, filename, id);
-                let _ = self
-                    .update_zip_status(id, DocumentStatus::Failed, Some(err_msg))
-                    .await;
-                return;
-            }
-        };
-
-        let mut tmp_file = match tempfile::tempfile() {
+        let tmp_file = match tempfile::tempfile() {
             Ok(f) => f,
             Err(e) => {
                 let err_msg = format!("Failed to create tempfile: {}", e);
                 tracing::error!("{}", err_msg);
                 let _ = self
-                    .update_zip_status(id, DocumentStatus::Failed, Some(err_msg))
+                    .update_zip_status(id, DocumentStatus::Failed, Some(err_msg.clone()))
                     .await;
+                if let Some(job_id) = zip_record.job_id {
+                    self.record_job_error(job_id, id, err_msg).await;
+                }
                 return;
             }
         };
 
-        if let Err(e) = tmp_file.write_all(&zip_data) {
-            let err_msg = format!("Failed to write to tempfile: {}", e);
-            tracing::error!("{}", err_msg);
+        // Download straight to disk; the archive is never held in memory
+        let key = filename.as_str();
+        if let Err(e) = retry(&self.state.config.retry_policy(), "Zip download", |_| {
+            download_to_file(
+                self.state.storage.as_ref(),
+                &self.state.config.storage.buckets.zip_archives,
+                key,
+                &tmp_file,
+            )
+        })
+        .await
+        {
+            let err_msg = format!("Failed to download zip: {}", e);
+            tracing::error!("{}, filename {}, id {}", err_msg, filename, id);
             let _ = self
-                .update_zip_status(id, DocumentStatus::Failed, Some(err_msg))
+                .update_zip_status(id, DocumentStatus::Failed, Some(err_msg.clone()))
                 .await;
+            if let Some(job_id) = zip_record.job_id {
+                self.record_job_error(job_id, id, err_msg).await;
+            }
             return;
         }
 
-        let mut archive = match zip::ZipArchive::new(tmp_file) {
-            Ok(a) => a,
-            Err(e) => {
-                let err_msg = format!("Failed to create zip archive: {}", e);
-                tracing::error!("{}", err_msg);
-                let _ = self
-                    .update_zip_status(id, DocumentStatus::Failed, Some(err_msg))
-                    .await;
-                return;
-            }
+        // Extraction blocks, so it runs on its own thread and feeds files through a channel.
+        // Members wait on disk until the whole archive is through: limits can still reject it
+        // part way, and nothing may be uploaded from an archive that ends up rejected
+        let (items_tx, mut items) = tokio::sync::mpsc::channel(ZIP_UPLOAD_CONCURRENCY);
+        let limits = self.state.config.archive;
+        let extraction = tokio::task::spawn_blocking(move || {
+            archive::extract(tmp_file, &limits, |item| items_tx.blocking_send(item).is_ok())
+        });
+        let mut extracted_items = Vec::new();
+        while let Some(item) = items.recv().await {
+            extracted_items.push(item);
+        }
+
+        let extracted = match extraction.await {
+            Ok(result) => result.map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Archive extraction failed: {}", e)),
         };
+        if let Err(err_msg) = extracted {
+            tracing::error!("{}, filename {}, id {}", err_msg, filename, id);
+            let _ = self
+                .update_zip_status(id, DocumentStatus::Failed, Some(err_msg.clone()))
+                .await;
+            if let Some(job_id) = zip_record.job_id {
+                self.record_job_error(job_id, id, err_msg).await;
+            }
+            return;
+        }
 
-        tracing::info!(
-            "Successfully extracted zip archive with {} files",
-            archive.len()
-        );
-        for i in 0..archive.len() {
-            let mut file = match archive.by_index(i) {
-                Ok(f) => f,
-                Err(e) => {
-                    tracing::error!("Failed to read file at index {} in zip: {}", i, e);
-                    continue;
+        // Members uploaded by an earlier run already have their own uploads; sending them
+        // again would process them twice
+        let already_uploaded: std::collections::HashSet<String> = sqlx::query_scalar!(
+            "SELECT name FROM zip_members WHERE zip_id = $1 AND status = $2",
+            id,
+            ZipMemberStatus::Uploaded as ZipMemberStatus
+        )
+        .fetch_all(&self.state.pool)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+        let mut members = 0usize;
+        let mut dropped = 0usize;
+        let mut names = std::collections::HashSet::new();
+        let mut uploads = tokio::task::JoinSet::new();
+        let upload_limit = Arc::new(Semaphore::new(ZIP_UPLOAD_CONCURRENCY));
+
+        for item in extracted_items {
+            let (raw_name, size, outcome) = match item {
+                ArchiveItem::File {
+                    name,
+                    size,
+                    document_type: Some(_),
+                    file,
+                } => (name, size, Ok(file)),
+                ArchiveItem::File { name, size, .. } => {
+                    let reason = "Unsupported file type".to_string();
+                    (name, size, Err((ZipMemberStatus::Skipped, reason)))
+                }
+                ArchiveItem::Skipped { name, size, reason } => {
+                    (name, size, Err((ZipMemberStatus::Skipped, reason)))
+                }
+                ArchiveItem::Failed { name, size, error } => {
+                    (name, size, Err((ZipMemberStatus::Failed, error)))
+                }
+            };
+            let size = size as i64;
+            let (name, outcome) = match sanitize_entry_name(&raw_name) {
+                Some(name) => (name, outcome),
+                None => {
+                    let reason = format!("Invalid file name '{}'", raw_name);
+                    (raw_name, Err((ZipMemberStatus::Skipped, reason)))
                 }
             };
 
-            if file.is_dir() || !file.name().ends_with(".pdf") || file.name().starts_with('.') {
+            // Skip hidden files and the resource forks macOS adds to archives
+            let basename = name.rsplit('/').next().unwrap_or(&name);
+            if basename.starts_with('.') || name.starts_with("__MACOSX/") {
                 continue;
             }
-
-            let mut pdf_buffer = Vec::new();
-            if let Err(e) = file.read_to_end(&mut pdf_buffer) {
-                tracing::error!("Failed to read file {} to buffer: {}", file.name(), e);
+            // Different raw names can sanitize to the same key
+            let name = unique_member_name(&mut names, name);
+            members += 1;
+            if already_uploaded.contains(&name) {
                 continue;
             }
 
-            let pdf_name = file.name().to_string();
-            let upload_path = format!("{}_{}", filename, pdf_name);
+            let file = match outcome {
+                Ok(file) => file,
+                Err((status, error)) => {
+                    dropped += 1;
+                    self.drop_zip_member(id, zip_record.job_id, &name, size, status, error)
+                        .await;
+                    continue;
+                }
+            };
 
+            let upload_path = format!("{}_{}", filename, name);
+            let member_id = match self
+                .record_zip_member(
+                    id,
+                    &name,
+                    size,
+                    Some(&upload_path),
+                    ZipMemberStatus::Pending,
+                    None,
+                )
+                .await
+            {
+                Ok(member_id) => member_id,
+                Err(e) => {
+                    tracing::error!("Failed to record zip member {}: {}", name, e);
+                    dropped += 1;
+                    continue;
+                }
+            };
+
+            // Members upload a few at a time
+            let permit = upload_limit
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("Semaphore closed");
             let storage = self.state.storage.clone();
-            let semaphore = self.state.semaphore.clone();
+            let config = self.state.config.clone();
+            let policy = self.state.config.retry_policy();
             let zip_id_str = id.to_string();
             let job_id = zip_record.job_id;
 
-            tokio::spawn(async move {
-                let _permit = semaphore.acquire().await.expect("Semaphore closed");
+            uploads.spawn(async move {
+                let _permit = permit;
 
                 let mut metadata = std::collections::HashMap::new();
                 metadata.insert("zip_id".to_string(), zip_id_str);
@@ -485,27 +1334,144 @@ impl ResumeService {
                     metadata.insert("job_id".to_string(), j_id.to_string());
                 }
 
-                match storage
-                    .put_object("resumes", &upload_path, pdf_buffer, Some(metadata))
+                let result = retry(&policy, "Zip member upload", |_| {
+                    upload_file(
+                        storage.as_ref(),
+                        &config.storage.buckets.resumes,
+                        &upload_path,
+                        &file,
+                        metadata.clone(),
+                    )
+                })
+                .await;
+                (member_id, name, upload_path, result)
+            });
+        }
+
+        tracing::info!("Extracted {} files from archive {}", members, filename);
+
+        while let Some(joined) = uploads.join_next().await {
+            let (member_id, name, upload_path, result) = match joined {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    tracing::error!("Zip member upload task failed: {}", e);
+                    dropped += 1;
+                    continue;
+                }
+            };
+
+            let result = match result {
+                Ok(()) if self.state.config.storage.backend == StorageBackend::Local => self
+                    .queue_extracted_resume(id, &upload_path)
                     .await
-                {
-                    Ok(_) => {
-                        tracing::info!("Successfully re-uploaded extracted PDF: {}", upload_path)
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to upload extracted PDF {}: {}", upload_path, e)
+                    .context("failed to queue it"),
+                other => other,
+            };
+            match result {
+                Ok(_) => {
+                    tracing::info!("Successfully re-uploaded extracted resume: {}", upload_path);
+                    let _ = self
+                        .update_zip_member_status(member_id, ZipMemberStatus::Uploaded, None)
+                        .await;
+                }
+                Err(e) => {
+                    let err_msg = format!("Failed to upload extracted resume: {}", e);
+                    tracing::error!("{}, filename {}", err_msg, upload_path);
+                    dropped += 1;
+                    let _ = self
+                        .update_zip_member_status(member_id, ZipMemberStatus::Failed, Some(&err_msg))
+                        .await;
+                    if let Some(job_id) = zip_record.job_id {
+                        self.record_zip_member_error(job_id, id, member_id, &name, err_msg)
+                            .await;
                     }
                 }
-            });
+            }
         }
 
-        let _ = self
-            .update_zip_status(id, DocumentStatus::Completed, None)
-            .await;
+        let (status, error_message) = if members == 0 {
+            (DocumentStatus::Failed, Some("Archive contains no files".to_string()))
+        } else if dropped == 0 {
+            (DocumentStatus::Completed, None)
+        } else if dropped == members {
+            (
+                DocumentStatus::Failed,
+                Some(format!("None of the {} files could be processed", members)),
+            )
+        } else {
+            (
+                DocumentStatus::Partial,
+                Some(format!("{} of {} files could not be processed", dropped, members)),
+            )
+        };
+        let _ = self.update_zip_status(id, status, error_message).await;
+    }
+}
 
-        if let Some(job_id) = zip_record.job_id {
-            self.maybe_mark_job_as_ready(job_id).await;
+/// Takes the session advisory lock `key` on a connection of its own, or returns `None` if
+/// another replica holds it. The lock lasts until the connection is dropped, which closes it
+/// rather than returning it to the pool, however the caller's run ends.
+async fn try_advisory_lock(
+    pool: &sqlx::PgPool,
+    key: i64,
+) -> Result<Option<sqlx::pool::PoolConnection<sqlx::Postgres>>, sqlx::Error> {
+    let mut lock = pool.acquire().await?;
+    lock.close_on_drop();
+    let locked = sqlx::query_scalar!("SELECT pg_try_advisory_lock($1)", key)
+        .fetch_one(&mut *lock)
+        .await?;
+    Ok((locked == Some(true)).then_some(lock))
+}
+
+/// Streams an object into `file`, replacing whatever an earlier attempt wrote.
+async fn download_to_file(
+    storage: &dyn StorageProvider,
+    bucket: &str,
+    key: &str,
+    file: &std::fs::File,
+) -> anyhow::Result<()> {
+    let mut file = tokio::fs::File::from_std(file.try_clone()?);
+    file.set_len(0).await?;
+    file.rewind().await?;
+    let mut body = storage.get_object_stream(bucket, key).await?;
+    tokio::io::copy(&mut body, &mut file)
+        .await
+        .map_err(|e| TransientError::new(format!("Failed to stream {}: {}", key, e)))?;
+    file.flush().await?;
+    Ok(())
+}
+
+async fn upload_file(
+    storage: &dyn StorageProvider,
+    bucket: &str,
+    key: &str,
+    file: &std::fs::File,
+    metadata: std::collections::HashMap<String, String>,
+) -> anyhow::Result<()> {
+    let file = tokio::fs::File::from_std(file.try_clone()?);
+    storage.put_object_stream(bucket, key, file, Some(metadata)).await
+}
+
+/// Returns `name`, or `name (2)`, `name (3)`, ... before the extension if it is taken.
+fn unique_member_name(taken: &mut std::collections::HashSet<String>, name: String) -> String {
+    if taken.insert(name.clone()) {
+        return name;
+    }
+    let (dir, file) = match name.rfind('/') {
+        Some(i) => name.split_at(i + 1),
+        None => ("", name.as_str()),
+    };
+    let (stem, ext) = match file.rfind('.') {
+        Some(i) if i > 0 => file.split_at(i),
+        _ => (file, ""),
+    };
+    let mut n = 2;
+    loop {
+        let candidate = format!("{}{} ({}){}", dir, stem, n, ext);
+        if taken.insert(candidate.clone()) {
+            return candidate;
         }
+        n += 1;
     }
 }
 
@@ -524,14 +1490,26 @@ impl ProjectService {
         status: DocumentStatus,
         error_message: Option<String>,
     ) -> Result<(), sqlx::Error> {
-        sqlx::query!(
-            "UPDATE project_uploads SET status = $1, error_message = $2 WHERE id = $3",
+        let job_id = sqlx::query_scalar!(
+            "UPDATE project_uploads SET status = $1, error_message = $2, updated_at = now(), started_at = CASE WHEN $1 = 'processing'::document_status THEN now() ELSE started_at END WHERE id = $3 RETURNING job_id",
             status as DocumentStatus,
             error_message,
             id
         )
-        .execute(&self.state.pool)
-        .await?;
+        .fetch_optional(&self.state.pool)
+        .await?
+        .flatten();
+        if let Some(job_id) = job_id {
+            self.state
+                .events
+                .publish(JobEvent::ProjectUpload {
+                    job_id,
+                    id,
+                    status,
+                    error: error_message,
+                })
+                .await;
+        }
         Ok(())
     }
 
@@ -550,37 +1528,23 @@ impl ProjectService {
         .await;
     }
 
+    /// Moves the job to `ready` or `failed` once all of its uploads finished.
     pub async fn maybe_mark_job_as_ready(&self, job_id: Uuid) {
-        // Reuse logic or consolidate. For now, duplication is simpler than refactoring everything to a shared trait.
-        let pending_count = match sqlx::query!(
-            r#"
-            SELECT 
-                (SELECT count(*) FROM resume_uploads WHERE job_id = $1 AND status NOT IN ('completed', 'failed')) +
-                (SELECT count(*) FROM zip_archives WHERE job_id = $1 AND status NOT IN ('completed', 'failed')) +
-                (SELECT count(*) FROM project_uploads WHERE job_id = $1 AND status NOT IN ('completed', 'failed'))
-            as count
-            "#,
-            job_id
-        )
-        .fetch_one(&self.state.pool)
-        .await {
-            Ok(r) => r.count.unwrap_or(0),
-            Err(_) => return,
-        };
+        job_state::settle(&self.state, job_id).await
+    }
 
-        if pending_count == 0 {
-            let _ = sqlx::query!(
-                "UPDATE jobs SET status = $1 WHERE id = $2 AND status = $3",
-                JobStatus::Ready as JobStatus,
-                job_id,
-                JobStatus::Processing as JobStatus
-            )
-            .execute(&self.state.pool)
-            .await;
+    /// Imports an uploaded project spreadsheet, then settles its job. Stops, and marks the
+    /// upload cancelled, if its job is cancelled.
+    pub async fn process_project_spreadsheet(&self, id: Uuid, filename: String) {
+        let work = self.run_project_spreadsheet(id, filename);
+        if run_upload(&self.state, TaskKind::Project, id, work).await.is_none() {
+            let _ = self
+                .update_upload_status(id, DocumentStatus::Cancelled, None)
+                .await;
         }
     }
 
-    pub async fn process_project_spreadsheet(&self, id: Uuid, filename: String) {
+    async fn run_project_spreadsheet(&self, id: Uuid, filename: String) {
         let _permit = self
             .state
             .semaphore
@@ -607,21 +1571,16 @@ impl ProjectService {
 
         // Mark job as processing
         if let Some(job_id) = upload_record.job_id {
-            let _ = sqlx::query!(
-                "UPDATE jobs SET status = $1 WHERE id = $2 AND status = $3",
-                JobStatus::Processing as JobStatus,
-                job_id,
-                JobStatus::Pending as JobStatus
-            )
-            .execute(&self.state.pool)
-            .await;
+            let _ = transition_job(&self.state, job_id, Some(JobStatus::Pending), JobStatus::Processing).await;
         }
 
-        let data = match self
-            .state
-            .storage
-            .get_object("project-spreadsheets", &filename)
-            .await
+        let key = filename.as_str();
+        let data = match retry(&self.state.config.retry_policy(), "Spreadsheet download", |_| {
+            self.state
+                .storage
+                .get_object(&self.state.config.storage.buckets.project_spreadsheets, key)
+        })
+        .await
         {
             Ok(data) => data,
             Err(e) => {
@@ -664,6 +1623,12 @@ impl ProjectService {
                         self.record_job_error(job_id, id, err_msg).await;
                     }
                 } else {
+                    if let Err(e) = EmbeddingService::new(self.state.clone())
+                        .embed_projects(id)
+                        .await
+                    {
+                        tracing::error!("Failed to embed projects for upload {}: {:#}", id, e);
+                    }
                     let _ = self
                         .update_upload_status(id, DocumentStatus::Completed, None)
                         .await;
@@ -680,10 +1645,6 @@ impl ProjectService {
                 }
             }
         }
-
-        if let Some(job_id) = upload_record.job_id {
-            self.maybe_mark_job_as_ready(job_id).await;
-        }
     }
 
     pub fn parse_csv(data: &[u8]) -> anyhow::Result<Vec<ProjectData>> {
@@ -772,8 +1733,13 @@ impl ProjectService {
         projects: Vec<ProjectData>,
         term: Option<String>,
     ) -> Result<(), sqlx::Error> {
+        // Processing a spreadsheet again replaces its projects rather than adding to them
+        let mut tx = self.state.pool.begin().await?;
+        sqlx::query!("DELETE FROM projects WHERE upload_id = $1", upload_id)
+            .execute(&mut *tx)
+            .await?;
         if projects.is_empty() {
-            return Ok(());
+            return tx.commit().await;
         }
 
         let mut query_builder: sqlx::QueryBuilder<sqlx::Postgres> = sqlx::QueryBuilder::new(
@@ -793,9 +1759,119 @@ impl ProjectService {
         });
 
         let query = query_builder.build();
-        query.execute(&self.state.pool).await?;
+        query.execute(&mut *tx).await?;
 
-        Ok(())
+        tx.commit().await
+    }
+}
+
+pub struct EmbeddingService {
+    state: AppState,
+}
+
+impl EmbeddingService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Embeds a resume's structured JSON unless the stored embedding already
+    /// matches the current content and model, or embeddings are off. Returns whether a
+    /// new vector was written.
+    pub async fn embed_resume(&self, resume_id: Uuid, input: String) -> anyhow::Result<bool> {
+        let Some(embeddings) = &self.state.embeddings else {
+            return Ok(false);
+        };
+        let model = embeddings.model().to_string();
+        let hash = content_hash(&input);
+
+        let existing = sqlx::query!(
+            "SELECT embedding_hash, embedding_model FROM resumes WHERE id = $1",
+            resume_id
+        )
+        .fetch_one(&self.state.pool)
+        .await?;
+
+        if existing.embedding_hash.as_deref() == Some(hash.as_str())
+            && existing.embedding_model.as_deref() == Some(model.as_str())
+        {
+            tracing::debug!("Embedding for resume {} is up to date", resume_id);
+            return Ok(false);
+        }
+
+        let vector = embeddings
+            .embed(&[input])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Embeddings endpoint returned no vectors"))?;
+
+        sqlx::query!(
+            "UPDATE resumes SET embedding = $1, embedding_model = $2, embedding_hash = $3, embedded_at = now() WHERE id = $4",
+            &vector,
+            model,
+            hash,
+            resume_id
+        )
+        .execute(&self.state.pool)
+        .await?;
+
+        tracing::info!("Stored embedding for resume {}", resume_id);
+        Ok(true)
+    }
+
+    /// Embeds every project created from `upload_id` whose content or model changed.
+    /// Returns the number of projects that received a new vector.
+    pub async fn embed_projects(&self, upload_id: Uuid) -> anyhow::Result<usize> {
+        let Some(embeddings) = &self.state.embeddings else {
+            return Ok(0);
+        };
+        let model = embeddings.model().to_string();
+
+        let rows = sqlx::query!(
+            "SELECT id, title, description, requirements, embedding_hash, embedding_model FROM projects WHERE upload_id = $1",
+            upload_id
+        )
+        .fetch_all(&self.state.pool)
+        .await?;
+
+        let mut ids = Vec::new();
+        let mut hashes = Vec::new();
+        let mut inputs = Vec::new();
+        for row in rows {
+            let input = project_embedding_input(
+                &row.title,
+                row.description.as_deref().unwrap_or(""),
+                row.requirements.as_deref().unwrap_or(""),
+            );
+            let hash = content_hash(&input);
+            if row.embedding_hash.as_deref() == Some(hash.as_str())
+                && row.embedding_model.as_deref() == Some(model.as_str())
+            {
+                continue;
+            }
+            ids.push(row.id);
+            hashes.push(hash);
+            inputs.push(input);
+        }
+
+        if inputs.is_empty() {
+            return Ok(0);
+        }
+
+        let vectors = embeddings.embed(&inputs).await?;
+        for ((id, hash), vector) in ids.iter().zip(hashes).zip(vectors) {
+            sqlx::query!(
+                "UPDATE projects SET embedding = $1, embedding_model = $2, embedding_hash = $3, embedded_at = now() WHERE id = $4",
+                &vector,
+                model,
+                hash,
+                id
+            )
+            .execute(&self.state.pool)
+            .await?;
+        }
+
+        tracing::info!("Stored embeddings for {} projects from upload {}", ids.len(), upload_id);
+        Ok(ids.len())
     }
 }
 
@@ -851,6 +1927,16 @@ fn default_cap() -> i16 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_unique_member_name() {
+        let mut taken = std::collections::HashSet::new();
+        assert_eq!(unique_member_name(&mut taken, "cv.pdf".into()), "cv.pdf");
+        assert_eq!(unique_member_name(&mut taken, "cv.pdf".into()), "cv (2).pdf");
+        assert_eq!(unique_member_name(&mut taken, "cv.pdf".into()), "cv (3).pdf");
+        assert_eq!(unique_member_name(&mut taken, "a.b/README".into()), "a.b/README");
+        assert_eq!(unique_member_name(&mut taken, "a.b/README".into()), "a.b/README (2)");
+    }
+
     #[test]
     fn test_parse_csv_valid() {
         let csv_data = b"title,description,requirements,manager,deadline,priority,intern_cap\nProject A,Desc A,Req A,Manager A,2026-01-01,1,2";
@@ -0,0 +1,172 @@
+use crate::AppState;
+use crate::events::JobEvent;
+use crate::queue::QueuedTask;
+use crate::reprocess::reset_upload;
+use crate::service::DocumentStatus;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+/// How long running uploads get to finish after SIGTERM/SIGINT before they are interrupted.
+pub const DEFAULT_SHUTDOWN_DEADLINE: Duration = Duration::from_secs(30);
+/// How long interrupted workers get to hand their tasks back to the queue.
+const INTERRUPT_GRACE: Duration = Duration::from_secs(5);
+/// Recorded on uploads and tasks that a shutdown interrupted.
+pub const INTERRUPTED_ERROR: &str = "Interrupted by a shutdown, queued to resume";
+
+/// Coordinates a graceful shutdown between `main`, the queue workers and the job event
+/// streams: once requested, workers stop claiming tasks and streams end, and `drain`
+/// waits for running work before interrupting what is left.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    requested: CancellationToken,
+    interrupted: CancellationToken,
+    workers: TaskTracker,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request(&self) {
+        self.requested.cancel();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.is_cancelled()
+    }
+
+    /// Resolves once a shutdown has been requested.
+    pub async fn requested(&self) {
+        self.requested.cancelled().await
+    }
+
+    /// Resolves once `drain` gives up on running work.
+    pub(crate) async fn interrupted(&self) {
+        self.interrupted.cancelled().await
+    }
+
+    /// Runs a queue worker that `drain` waits for.
+    pub(crate) fn spawn_worker<F>(&self, worker: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.workers.spawn(worker);
+    }
+
+    /// Requests a shutdown, then waits up to `deadline` for the workers to finish their
+    /// current tasks and for all `permits` of `semaphore` to be returned. Work still running
+    /// after that is interrupted: its tasks go back on the queue and its uploads back to
+    /// `pending`, so they resume on the next worker. Returns whether everything finished.
+    pub async fn drain(&self, semaphore: &Semaphore, permits: usize, deadline: Duration) -> bool {
+        self.request();
+        self.workers.close();
+        let finished = tokio::time::timeout(deadline, async {
+            self.workers.wait().await;
+            // Keep the permits so nothing else starts before the process exits
+            if let Ok(permits) = semaphore.acquire_many(permits as u32).await {
+                permits.forget();
+            }
+        })
+        .await
+        .is_ok();
+
+        if !finished {
+            self.interrupted.cancel();
+            if tokio::time::timeout(INTERRUPT_GRACE, self.workers.wait())
+                .await
+                .is_err()
+            {
+                tracing::error!("Workers did not hand back their tasks in time");
+            }
+        }
+        finished
+    }
+}
+
+/// Waits for SIGTERM or SIGINT and requests a shutdown.
+pub async fn signal(shutdown: Shutdown) {
+    let interrupt = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for SIGINT");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => tracing::info!("Received SIGINT, shutting down"),
+        _ = terminate => tracing::info!("Received SIGTERM, shutting down"),
+    }
+    shutdown.request();
+}
+
+/// Hands an interrupted task back: its upload returns to `pending` and the task to the
+/// queue, without counting the interrupted attempt.
+pub(crate) async fn interrupt(state: &AppState, task: &QueuedTask) {
+    let processing = DocumentStatus::Processing;
+    match reset_upload(state, task.kind, task.upload_id, processing, Some(INTERRUPTED_ERROR)).await {
+        Ok(Some(Some(job_id))) => {
+            state
+                .events
+                .publish(JobEvent::upload(
+                    task.kind,
+                    job_id,
+                    task.upload_id,
+                    DocumentStatus::Pending,
+                    Some(INTERRUPTED_ERROR.to_string()),
+                ))
+                .await
+        }
+        Ok(_) => {}
+        Err(e) => tracing::error!("Failed to reset interrupted upload {}: {}", task.upload_id, e),
+    }
+    if let Err(e) = state.queue.release(task.id, INTERRUPTED_ERROR).await {
+        tracing::error!("Failed to release interrupted task {}: {}", task.id, e);
+    }
+    tracing::warn!(
+        "Interrupted {:?} task {} for upload {}",
+        task.kind,
+        task.id,
+        task.upload_id
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_permits_then_gives_up() {
+        let shutdown = Shutdown::new();
+        let semaphore = Arc::new(Semaphore::new(2));
+
+        // A permit returned before the deadline lets the drain finish
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(permit);
+        });
+        assert!(shutdown.drain(&semaphore, 2, Duration::from_secs(5)).await);
+        assert!(shutdown.is_requested());
+
+        // Work that never returns its permit is interrupted at the deadline
+        let shutdown = Shutdown::new();
+        let semaphore = Semaphore::new(2);
+        let _held = semaphore.acquire().await.unwrap();
+        let worker = shutdown.clone();
+        shutdown.spawn_worker(async move { worker.interrupted().await });
+        assert!(!shutdown.drain(&semaphore, 2, Duration::from_millis(50)).await);
+    }
+}
@@ -1,7 +1,17 @@
+use crate::config::{S3Connection, S3Credentials};
+use crate::retry::TransientError;
 use anyhow::Result;
 use async_trait::async_trait;
 use aws_sdk_s3::Client as S3Client;
-use tokio::io::AsyncReadExt;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{DisplayErrorContext, SdkError};
+use aws_sdk_s3::primitives::ByteStream;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use uuid::Uuid;
+
+pub type ObjectStream = Box<dyn AsyncRead + Send + Unpin>;
 
 #[async_trait]
 pub trait StorageProvider: Send + Sync {
@@ -14,6 +24,26 @@ pub trait StorageProvider: Send + Sync {
         metadata: Option<std::collections::HashMap<String, String>>,
     ) -> Result<()>;
     async fn delete_object(&self, bucket: &str, key: &str) -> Result<()>;
+
+    /// Returns an object's body as a stream. The default buffers it with `get_object`.
+    async fn get_object_stream(&self, bucket: &str, key: &str) -> Result<ObjectStream> {
+        let data = self.get_object(bucket, key).await?;
+        Ok(Box::new(std::io::Cursor::new(data)))
+    }
+
+    /// Uploads `file` from its start. The default reads it into memory for `put_object`.
+    async fn put_object_stream(
+        &self,
+        bucket: &str,
+        key: &str,
+        mut file: tokio::fs::File,
+        metadata: Option<std::collections::HashMap<String, String>>,
+    ) -> Result<()> {
+        let mut body = Vec::new();
+        file.rewind().await?;
+        file.read_to_end(&mut body).await?;
+        self.put_object(bucket, key, body, metadata).await
+    }
 }
 
 pub struct S3StorageProvider {
@@ -24,6 +54,69 @@ impl S3StorageProvider {
     pub fn new(client: S3Client) -> Self {
         Self { client }
     }
+
+    /// Builds the client for Supabase storage, MinIO or AWS S3 as `connection` describes.
+    pub async fn connect(connection: &S3Connection) -> Self {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(aws_config::Region::new(connection.region.clone()));
+        if let Some(endpoint) = &connection.endpoint {
+            loader = loader.endpoint_url(endpoint);
+        }
+        loader = match &connection.credentials {
+            S3Credentials::Static {
+                access_key,
+                secret_key,
+            } => loader.credentials_provider(aws_sdk_s3::config::Credentials::new(
+                access_key,
+                secret_key,
+                None,
+                None,
+                "static",
+            )),
+            S3Credentials::Env => loader.credentials_provider(
+                aws_config::environment::EnvironmentVariableCredentialsProvider::new(),
+            ),
+            S3Credentials::Profile(profile) => {
+                let mut provider = aws_config::profile::ProfileFileCredentialsProvider::builder();
+                if let Some(profile) = profile {
+                    provider = provider.profile_name(profile);
+                }
+                loader.credentials_provider(provider.build())
+            }
+            S3Credentials::Imds => loader.credentials_provider(
+                aws_config::imds::credentials::ImdsCredentialsProvider::builder().build(),
+            ),
+        };
+
+        let s3_config = aws_sdk_s3::config::Builder::from(&loader.load().await)
+            .force_path_style(connection.force_path_style)
+            .build();
+        Self::new(S3Client::from_conf(s3_config))
+    }
+}
+
+/// Network failures, throttling and 5xx responses are transient; anything else
+/// (missing key, access denied) is permanent.
+fn classify_sdk_error<E>(err: SdkError<E, HttpResponse>) -> anyhow::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let transient = match &err {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => {
+            true
+        }
+        SdkError::ServiceError(e) => {
+            let status = e.raw().status().as_u16();
+            status == 429 || status >= 500
+        }
+        _ => false,
+    };
+
+    if transient {
+        TransientError::new(format!("S3 request failed: {}", DisplayErrorContext(&err))).into()
+    } else {
+        err.into()
+    }
 }
 
 #[async_trait]
@@ -35,10 +128,16 @@ impl StorageProvider for S3StorageProvider {
             .bucket(bucket)
             .key(key)
             .send()
-            .await?;
+            .await
+            .map_err(classify_sdk_error)?;
 
         let mut data = Vec::new();
-        output.body.into_async_read().read_to_end(&mut data).await?;
+        output
+            .body
+            .into_async_read()
+            .read_to_end(&mut data)
+            .await
+            .map_err(|e| TransientError::new(format!("Failed to read S3 object body: {}", e)))?;
         Ok(data)
     }
 
@@ -62,7 +161,45 @@ impl StorageProvider for S3StorageProvider {
             }
         }
 
-        request.send().await?;
+        request.send().await.map_err(classify_sdk_error)?;
+        Ok(())
+    }
+
+    async fn get_object_stream(&self, bucket: &str, key: &str) -> Result<ObjectStream> {
+        let output = self
+            .client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(classify_sdk_error)?;
+        Ok(Box::new(output.body.into_async_read()))
+    }
+
+    async fn put_object_stream(
+        &self,
+        bucket: &str,
+        key: &str,
+        mut file: tokio::fs::File,
+        metadata: Option<std::collections::HashMap<String, String>>,
+    ) -> Result<()> {
+        file.rewind().await?;
+        let body = ByteStream::read_from().file(file).build().await?;
+        let mut request = self
+            .client
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .body(body);
+
+        if let Some(meta) = metadata {
+            for (k, v) in meta {
+                request = request.metadata(k, v);
+            }
+        }
+
+        request.send().await.map_err(classify_sdk_error)?;
         Ok(())
     }
 
@@ -72,11 +209,166 @@ impl StorageProvider for S3StorageProvider {
             .bucket(bucket)
             .key(key)
             .send()
-            .await?;
+            .await
+            .map_err(classify_sdk_error)?;
         Ok(())
     }
 }
 
+/// Suffix of the sidecar file that holds an object's metadata in `LocalStorageProvider`.
+const METADATA_SUFFIX: &str = ".meta.json";
+
+/// Keeps objects as files under `root/<bucket>/<key>`, for offline development and
+/// installs without object storage. Metadata is stored next to each object in a
+/// `<key>.meta.json` sidecar. Writes go to a temporary file that is renamed into place,
+/// so readers never see a half-written object.
+pub struct LocalStorageProvider {
+    root: PathBuf,
+}
+
+impl LocalStorageProvider {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The metadata stored with an object, if it was written with any.
+    pub async fn get_metadata(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<Option<HashMap<String, String>>> {
+        let path = metadata_path(&self.object_path(bucket, key)?);
+        match tokio::fs::read(&path).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Maps a bucket and key to a path under `root`, refusing anything that could
+    /// step outside it or be mistaken for a metadata sidecar.
+    fn object_path(&self, bucket: &str, key: &str) -> Result<PathBuf> {
+        if key.ends_with(METADATA_SUFFIX) {
+            anyhow::bail!("Object keys can't end with {}: {}", METADATA_SUFFIX, key);
+        }
+        let mut path = self.root.clone();
+        for part in std::iter::once(bucket).chain(key.split('/')) {
+            if part.is_empty() || part == "." || part == ".." || part.contains(['\\', '\0']) {
+                anyhow::bail!("Invalid object path: {}/{}", bucket, key);
+            }
+            path.push(part);
+        }
+        Ok(path)
+    }
+
+    async fn write_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: &mut (impl AsyncRead + Unpin),
+        metadata: Option<HashMap<String, String>>,
+    ) -> Result<()> {
+        let path = self.object_path(bucket, key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Like S3, a write replaces the object's metadata along with its body
+        let sidecar = metadata_path(&path);
+        match metadata {
+            Some(metadata) => {
+                write_atomically(&sidecar, &mut serde_json::to_vec(&metadata)?.as_slice()).await?
+            }
+            None => remove_if_exists(&sidecar).await?,
+        }
+        write_atomically(&path, body).await
+    }
+}
+
+fn metadata_path(object: &Path) -> PathBuf {
+    let mut name = object.file_name().unwrap_or_default().to_os_string();
+    name.push(METADATA_SUFFIX);
+    object.with_file_name(name)
+}
+
+/// Writes `body` to a temporary file next to `path`, flushes it to disk and renames it over
+/// `path`.
+async fn write_atomically(path: &Path, body: &mut (impl AsyncRead + Unpin)) -> Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, Uuid::new_v4()));
+    let written = async {
+        let mut file = tokio::fs::File::create(&temp).await?;
+        tokio::io::copy(body, &mut file).await?;
+        file.flush().await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&temp, path).await
+    }
+    .await;
+    if written.is_err() {
+        let _ = tokio::fs::remove_file(&temp).await;
+    }
+    Ok(written?)
+}
+
+async fn remove_if_exists(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[async_trait]
+impl StorageProvider for LocalStorageProvider {
+    async fn get_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>> {
+        let path = self.object_path(bucket, key)?;
+        match tokio::fs::read(&path).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(anyhow::anyhow!("Object not found: {}/{}", bucket, key))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Result<()> {
+        self.write_object(bucket, key, &mut body.as_slice(), metadata)
+            .await
+    }
+
+    async fn get_object_stream(&self, bucket: &str, key: &str) -> Result<ObjectStream> {
+        let path = self.object_path(bucket, key)?;
+        match tokio::fs::File::open(&path).await {
+            Ok(file) => Ok(Box::new(file)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(anyhow::anyhow!("Object not found: {}/{}", bucket, key))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put_object_stream(
+        &self,
+        bucket: &str,
+        key: &str,
+        mut file: tokio::fs::File,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Result<()> {
+        file.rewind().await?;
+        self.write_object(bucket, key, &mut file, metadata).await
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<()> {
+        let path = self.object_path(bucket, key)?;
+        remove_if_exists(&path).await?;
+        remove_if_exists(&metadata_path(&path)).await
+    }
+}
+
 type MockStorageMap = std::collections::HashMap<
     String,
     (Vec<u8>, Option<std::collections::HashMap<String, String>>),
@@ -134,3 +426,72 @@ impl StorageProvider for MockStorageProvider {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_storage_round_trip() {
+        let root = tempfile::tempdir().unwrap();
+        let storage = LocalStorageProvider::new(root.path());
+        let metadata = HashMap::from([("job_id".to_string(), "42".to_string())]);
+
+        storage
+            .put_object("resumes", "batch/ana.pdf", b"first".to_vec(), Some(metadata.clone()))
+            .await
+            .unwrap();
+        assert_eq!(storage.get_object("resumes", "batch/ana.pdf").await.unwrap(), b"first");
+        assert_eq!(storage.get_metadata("resumes", "batch/ana.pdf").await.unwrap(), Some(metadata));
+        assert!(root.path().join("resumes/batch/ana.pdf.meta.json").exists());
+
+        // Overwriting replaces body and metadata, and leaves no temporary files behind
+        let mut upload = tempfile::tempfile().map(tokio::fs::File::from_std).unwrap();
+        upload.write_all(b"second").await.unwrap();
+        storage
+            .put_object_stream("resumes", "batch/ana.pdf", upload, None)
+            .await
+            .unwrap();
+        let mut streamed = Vec::new();
+        storage
+            .get_object_stream("resumes", "batch/ana.pdf")
+            .await
+            .unwrap()
+            .read_to_end(&mut streamed)
+            .await
+            .unwrap();
+        assert_eq!(streamed, b"second");
+        assert_eq!(storage.get_metadata("resumes", "batch/ana.pdf").await.unwrap(), None);
+        let files: Vec<_> = std::fs::read_dir(root.path().join("resumes/batch"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, ["ana.pdf"]);
+
+        storage.delete_object("resumes", "batch/ana.pdf").await.unwrap();
+        storage.delete_object("resumes", "batch/ana.pdf").await.unwrap();
+        let err = storage.get_object("resumes", "batch/ana.pdf").await.unwrap_err();
+        assert!(err.to_string().contains("Object not found"));
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_stays_inside_root() {
+        let root = tempfile::tempdir().unwrap();
+        let storage = LocalStorageProvider::new(root.path().join("objects"));
+        for (bucket, key) in [
+            ("resumes", "../escape.pdf"),
+            ("..", "escape.pdf"),
+            ("resumes", "/etc/passwd"),
+            ("resumes", "a//b.pdf"),
+            ("resumes", "ana.pdf.meta.json"),
+        ] {
+            assert!(
+                storage.put_object(bucket, key, b"x".to_vec(), None).await.is_err(),
+                "{}/{} should be rejected",
+                bucket,
+                key
+            );
+        }
+        assert!(!root.path().join("escape.pdf").exists());
+    }
+}
@@ -0,0 +1,145 @@
+use serde_json::Value;
+use std::fmt;
+
+/// Version of `resume_schema.json`, stored in `resumes.schema_version`. Bump it whenever
+/// the schema changes so resumes parsed with an older version get re-parsed.
+pub const RESUME_SCHEMA_VERSION: i32 = 2;
+
+/// The model's reply was not valid JSON or did not conform to the resume schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaValidationError {
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for SchemaValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LLM output failed schema validation: {}",
+            self.errors.join("; ")
+        )
+    }
+}
+
+impl std::error::Error for SchemaValidationError {}
+
+/// Validates `document` against `schema`, collecting every violation with its JSON pointer.
+pub fn validate(schema: &Value, document: &Value) -> Result<(), SchemaValidationError> {
+    let validator = jsonschema::validator_for(schema).map_err(|e| SchemaValidationError {
+        errors: vec![format!("invalid resume schema: {}", e)],
+    })?;
+
+    let errors: Vec<String> = validator
+        .iter_errors(document)
+        .map(|e| {
+            let path = e.instance_path.to_string();
+            let path = if path.is_empty() { "/".to_string() } else { path };
+            format!("{}: {}", path, e)
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(SchemaValidationError { errors })
+    }
+}
+
+/// Parses the model's raw reply and validates it in one step.
+pub fn parse_and_validate(schema: &Value, content: &str) -> Result<Value, SchemaValidationError> {
+    let document = serde_json::from_str::<Value>(content).map_err(|e| SchemaValidationError {
+        errors: vec![format!("/: not valid JSON: {}", e)],
+    })?;
+    validate(schema, &document)?;
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn resume_schema() -> Value {
+        serde_json::from_str(include_str!("resume_schema.json")).unwrap()
+    }
+
+    fn valid_resume() -> Value {
+        json!({
+            "contact": {
+                "name": "Alex Rivera",
+                "email": "alex@example.com",
+                "phone": null,
+                "location": "Fayetteville, AR"
+            },
+            "links": { "github": "https://github.com/alex", "linkedin": null, "portfolio": null },
+            "work_authorization": null,
+            "graduation_date": "2026-05",
+            "education": [{
+                "school": "University of Arkansas",
+                "degree_type": "BS",
+                "degree_title": "BS in Computer Science",
+                "grad_date": "May 2026",
+                "grad_date_normalized": "2026-05",
+                "GPA": "3.8"
+            }],
+            "skills": ["Rust"],
+            "experience": [{
+                "role": "Intern",
+                "years_of_experience": "1",
+                "start_date": "2024-06",
+                "end_date": null
+            }],
+            "projects": [{
+                "name": "Matchmaker",
+                "description": "Matches interns to projects",
+                "technologies": ["Rust", "Postgres"],
+                "url": null
+            }],
+            "certifications": []
+        })
+    }
+
+    #[test]
+    fn test_valid_resume_passes() {
+        assert!(validate(&resume_schema(), &valid_resume()).is_ok());
+    }
+
+    #[test]
+    fn test_missing_section_is_reported() {
+        let mut doc = valid_resume();
+        doc.as_object_mut().unwrap().remove("education");
+        let err = validate(&resume_schema(), &doc).unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert!(err.errors[0].starts_with("/: "));
+        assert!(err.errors[0].contains("education"));
+    }
+
+    #[test]
+    fn test_wrong_types_and_extra_keys_are_reported_with_paths() {
+        let mut doc = valid_resume();
+        doc["skills"] = json!("Rust, SQL");
+        doc["education"][0]["minor"] = json!("Math");
+        let err = validate(&resume_schema(), &doc).unwrap_err();
+        assert_eq!(err.errors.len(), 2);
+        assert!(err.errors.iter().any(|e| e.starts_with("/skills: ")));
+        assert!(err.errors.iter().any(|e| e.starts_with("/education/0: ")));
+    }
+
+    #[test]
+    fn test_dates_must_be_normalized() {
+        let mut doc = valid_resume();
+        doc["graduation_date"] = json!("May 2026");
+        doc["experience"][0]["end_date"] = json!("2025-13");
+        let err = validate(&resume_schema(), &doc).unwrap_err();
+        assert_eq!(err.errors.len(), 2);
+        assert!(err.errors.iter().any(|e| e.starts_with("/graduation_date: ")));
+        assert!(err.errors.iter().any(|e| e.starts_with("/experience/0/end_date: ")));
+    }
+
+    #[test]
+    fn test_parse_and_validate_rejects_invalid_json() {
+        let err = parse_and_validate(&resume_schema(), "{not json").unwrap_err();
+        assert!(err.errors[0].contains("not valid JSON"));
+        assert!(err.to_string().starts_with("LLM output failed schema validation"));
+    }
+}
@@ -2,14 +2,23 @@ use axum::{
     Router,
     body::Body,
     http::{Request, StatusCode},
-    routing::post,
+    routing::{get, post},
 };
 use jsonwebtoken::{EncodingKey, Header, encode};
 use matchmaker_orchestrator::auth::Claims;
+use matchmaker_orchestrator::embeddings::OpenAIEmbeddingClient;
+use matchmaker_orchestrator::cancel::Cancellations;
+use matchmaker_orchestrator::config::{Config, RetryConfig};
+use matchmaker_orchestrator::shutdown::Shutdown;
+use matchmaker_orchestrator::events::EventBus;
+use matchmaker_orchestrator::extraction::ExtractionMethod;
+use matchmaker_orchestrator::llm::{MockLlmProvider, OpenAIProvider};
+use matchmaker_orchestrator::queue::{self, TaskQueue};
 use matchmaker_orchestrator::requests::{
-    handle_batch_upload, handle_project_upload, handle_single_upload,
+    cancel_job, get_job, handle_batch_upload, handle_project_upload, handle_single_upload,
+    job_events, list_jobs, retry_failed, retry_upload,
 };
-use matchmaker_orchestrator::service::DocumentStatus;
+use matchmaker_orchestrator::service::{DocumentStatus, ZipMemberStatus};
 use matchmaker_orchestrator::storage::{MockStorageProvider, StorageProvider};
 use matchmaker_orchestrator::{AppState, auth};
 use serde_json::json;
@@ -25,6 +34,7 @@ struct TestEnv {
     app: Router,
     pool: sqlx::PgPool,
     storage: Arc<MockStorageProvider>,
+    events: EventBus,
     jwt_secret: String,
 }
 
@@ -40,22 +50,41 @@ async fn setup_test_env() -> TestEnv {
             .unwrap();
     let storage = Arc::new(MockStorageProvider::new());
     let jwt_secret = "test-secret".to_string();
+    let events = EventBus::new();
 
     let app_state = AppState {
+        config: Arc::new(Config::default()),
         pool: pool.clone(),
         storage: storage.clone(),
+        embeddings: Some(Arc::new(OpenAIEmbeddingClient::new(
+            reqwest::Client::new(),
+            "test-key".to_string(),
+            "http://localhost:1234/embeddings".to_string(),
+            "test-embedding-model".to_string(),
+        ))),
+        queue: test_queue(&pool),
+        events: events.clone(),
+        cancellations: Cancellations::new(),
+        shutdown: Shutdown::new(),
+        llm: Arc::new(MockLlmProvider::with_json(json!({}))), // Default, tests can override
         http_client: reqwest::Client::new(),
-        openai_api_key: "test-key".to_string(),
-        openai_endpoint: "http://localhost:1234".to_string(), // Default, tests can override
         resume_schema: json!({}),
+        ocr: None,
         semaphore: Arc::new(Semaphore::new(10)),
         jwt_secret: jwt_secret.clone(),
     };
+    queue::spawn_workers(app_state.clone(), 2);
 
     let app = Router::new()
         .route("/ingest/projects", post(handle_project_upload))
         .route("/ingest/interns/individual", post(handle_single_upload))
         .route("/ingest/interns/batch", post(handle_batch_upload))
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/{id}", get(get_job))
+        .route("/jobs/{id}/events", get(job_events))
+        .route("/jobs/{id}/cancel", post(cancel_job))
+        .route("/jobs/{id}/retry-failed", post(retry_failed))
+        .route("/uploads/{kind}/{id}/retry", post(retry_upload))
         .route_layer(axum::middleware::from_fn_with_state(
             app_state.clone(),
             auth::auth,
@@ -66,10 +95,16 @@ async fn setup_test_env() -> TestEnv {
         app,
         pool,
         storage,
+        events,
         jwt_secret,
     }
 }
 
+/// Each test drains its own queue so workers never pick up tasks backed by another test's mock storage.
+fn test_queue(pool: &sqlx::PgPool) -> TaskQueue {
+    TaskQueue::with_name(pool.clone(), &format!("test-{}", Uuid::new_v4()))
+}
+
 fn create_jwt(secret: &str) -> String {
     let claims = Claims {
         sub: "test-user".to_string(),
@@ -175,37 +210,44 @@ async fn test_project_upload_flow() {
 async fn test_resume_upload_flow() {
     let mut env = setup_test_env().await;
 
-    // 1. Setup Mock OpenAI
-    let mock_server = MockServer::start().await;
-    let mock_response = json!({
-        "choices": [
-            {
-                "message": {
-                    "role": "assistant",
-                    "content": "{\"name\": \"Alex Rivera\", \"skills\": [\"Rust\", \"Testing\"]}"
-                }
-            }
-        ]
-    });
+    // 1. Setup in-memory LLM and mock embeddings endpoint
+    let llm = Arc::new(MockLlmProvider::with_json(
+        json!({ "name": "Alex Rivera", "skills": ["Rust", "Testing"] }),
+    ));
 
+    let mock_server = MockServer::start().await;
     Mock::given(method("POST"))
-        .and(path("/"))
-        .respond_with(ResponseTemplate::new(200).set_body_json(mock_response))
+        .and(path("/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{ "index": 0, "embedding": [0.1, 0.2, 0.3] }]
+        })))
         .mount(&mock_server)
         .await;
 
-    // Update app state with mock server URI
-    // We need to recreate the app with the new endpoint
+    // Update app state with the mock LLM and embeddings endpoint
+    // We need to recreate the app with the new providers
     let app_state = AppState {
+        config: Arc::new(Config::default()),
         pool: env.pool.clone(),
         storage: env.storage.clone(),
+        embeddings: Some(Arc::new(OpenAIEmbeddingClient::new(
+            reqwest::Client::new(),
+            "test-key".to_string(),
+            format!("{}/embeddings", mock_server.uri()),
+            "test-embedding-model".to_string(),
+        ))),
+        queue: test_queue(&env.pool),
+        events: EventBus::new(),
+        cancellations: Cancellations::new(),
+        shutdown: Shutdown::new(),
+        llm: llm.clone(),
         http_client: reqwest::Client::new(),
-        openai_api_key: "test-key".to_string(),
-        openai_endpoint: mock_server.uri(),
         resume_schema: json!({}),
+        ocr: None,
         semaphore: Arc::new(Semaphore::new(10)),
         jwt_secret: env.jwt_secret.clone(),
     };
+    queue::spawn_workers(app_state.clone(), 2);
 
     env.app = Router::new()
         .route("/ingest/interns/individual", post(handle_single_upload))
@@ -291,7 +333,7 @@ async fn test_resume_upload_flow() {
 
     // 6. Verify DB record
     let resume = sqlx::query!(
-        "SELECT filename, structured FROM resumes WHERE upload_id = $1",
+        r#"SELECT filename, structured, extraction_method as "extraction_method: ExtractionMethod", embedding, embedding_model FROM resumes WHERE upload_id = $1"#,
         upload_id
     )
     .fetch_one(&env.pool)
@@ -299,7 +341,152 @@ async fn test_resume_upload_flow() {
     .unwrap();
 
     assert_eq!(resume.filename, "Alex_Rivera_CV.pdf");
+    assert_eq!(resume.extraction_method, Some(ExtractionMethod::Text));
     assert_eq!(resume.structured.unwrap()["name"], "Alex Rivera");
+    assert_eq!(resume.embedding.unwrap(), vec![0.1f32, 0.2, 0.3]);
+    assert_eq!(resume.embedding_model.as_deref(), Some("test-embedding-model"));
+
+    // The LLM saw the extracted PDF text
+    {
+        let requests = llm.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0][1].content.contains("Alex"));
+    }
+
+    // Cleanup
+    sqlx::query!("DELETE FROM resumes WHERE upload_id = $1", upload_id)
+        .execute(&env.pool)
+        .await
+        .unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE id = $1", upload_id)
+        .execute(&env.pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_resume_upload_retries_rate_limited_llm() {
+    let env = setup_test_env().await;
+
+    // 1. The first LLM call is rate limited, the second succeeds
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "{\"skills\": [\"Rust\"]}" } }]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config {
+        retry: RetryConfig { max_attempts: 3, base_delay_ms: 10, max_delay_ms: 50 },
+        ..Config::default()
+    };
+    let app_state = AppState {
+        config: Arc::new(config),
+        pool: env.pool.clone(),
+        storage: env.storage.clone(),
+        embeddings: Some(Arc::new(OpenAIEmbeddingClient::new(
+            reqwest::Client::new(),
+            "test-key".to_string(),
+            format!("{}/embeddings", mock_server.uri()),
+            "test-embedding-model".to_string(),
+        ))),
+        queue: test_queue(&env.pool),
+        events: EventBus::new(),
+        cancellations: Cancellations::new(),
+        shutdown: Shutdown::new(),
+        llm: Arc::new(OpenAIProvider::new(
+            reqwest::Client::new(),
+            "test-key".to_string(),
+            mock_server.uri(),
+            "gpt-5-nano".to_string(),
+        )),
+        http_client: reqwest::Client::new(),
+        resume_schema: json!({}),
+        ocr: None,
+        semaphore: Arc::new(Semaphore::new(10)),
+        jwt_secret: env.jwt_secret.clone(),
+    };
+    queue::spawn_workers(app_state.clone(), 1);
+
+    let app = Router::new()
+        .route("/ingest/interns/individual", post(handle_single_upload))
+        .route_layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            auth::auth,
+        ))
+        .with_state(app_state);
+
+    // 2. Upload and trigger
+    let pdf_bytes = std::fs::read("archive.zip-resumes/Alex_Rivera_CV.pdf").expect("Failed to read test PDF");
+    env.storage
+        .put_object("resumes", "retry_test.pdf", pdf_bytes, None)
+        .await
+        .unwrap();
+
+    let upload_id = Uuid::new_v4();
+    sqlx::query!(
+        "INSERT INTO resume_uploads (id, filename, status) VALUES ($1, $2, 'pending')",
+        upload_id,
+        "retry_test.pdf"
+    )
+    .execute(&env.pool)
+    .await
+    .unwrap();
+
+    let token = create_jwt(&env.jwt_secret);
+    let payload = json!({ "record": { "id": upload_id, "filename": "retry_test.pdf" } });
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/ingest/interns/individual")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+    // 3. Poll for completion
+    let mut final_status = None;
+    for _ in 0..20 {
+        let record = sqlx::query_as::<_, (DocumentStatus,)>(
+            "SELECT status FROM resume_uploads WHERE id = $1",
+        )
+        .bind(upload_id)
+        .fetch_one(&env.pool)
+        .await
+        .unwrap();
+
+        if matches!(record.0, DocumentStatus::Completed | DocumentStatus::Failed) {
+            final_status = Some(record.0);
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    }
+
+    assert!(
+        matches!(final_status, Some(DocumentStatus::Completed)),
+        "Rate-limited upload did not recover: {:?}",
+        final_status
+    );
+
+    // One download plus two LLM attempts
+    let upload = sqlx::query!("SELECT attempts FROM resume_uploads WHERE id = $1", upload_id)
+        .fetch_one(&env.pool)
+        .await
+        .unwrap();
+    assert_eq!(upload.attempts, 3);
 
     // Cleanup
     sqlx::query!("DELETE FROM resumes WHERE upload_id = $1", upload_id)
@@ -341,7 +528,7 @@ async fn test_auth_security_gauntlet() {
 async fn test_zip_filtering_chaos() {
     let env = setup_test_env().await;
     
-    // Create ZIP with mixed content: one valid PDF, one TXT, and one in a folder
+    // Create ZIP with mixed content: a PDF, a TXT, an image, a macOS resource fork and one in a folder
     let mut buf = Vec::new();
     {
         let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut buf));
@@ -350,8 +537,14 @@ async fn test_zip_filtering_chaos() {
         zip.start_file("valid.pdf", options).unwrap();
         zip.write_all(b"%PDF-1.4").unwrap();
         
-        zip.start_file("ignore_me.txt", options).unwrap();
-        zip.write_all(b"I am not a resume").unwrap();
+        zip.start_file("notes.txt", options).unwrap();
+        zip.write_all(b"Jordan Lee - Rust, SQL").unwrap();
+
+        zip.start_file("photo.pdf", options).unwrap();
+        zip.write_all(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+        zip.start_file("__MACOSX/._valid.pdf", options).unwrap();
+        zip.write_all(b"\0\x05\x16\x07\0\x02\0\0").unwrap();
         
         zip.start_file("nested/folder.pdf", options).unwrap();
         zip.write_all(b"%PDF-1.4 nested").unwrap();
@@ -378,17 +571,184 @@ async fn test_zip_filtering_chaos() {
     // Poll for completion
     for _ in 0..10 {
         let rec = sqlx::query_as::<_, (DocumentStatus,)>("SELECT status FROM zip_archives WHERE id = $1").bind(zip_id).fetch_one(&env.pool).await.unwrap();
-        if matches!(rec.0, DocumentStatus::Completed) { break; }
+        if matches!(rec.0, DocumentStatus::Completed | DocumentStatus::Partial | DocumentStatus::Failed) { break; }
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
     }
 
     {
         let objects = env.storage.objects.lock().unwrap();
-        // Should have valid.pdf AND the nested one (it currently doesn't filter nested, just non-pdfs)
+        // Supported documents are re-uploaded wherever they sit in the archive
         assert!(objects.contains_key("resumes/chaos.zip_valid.pdf"));
         assert!(objects.contains_key("resumes/chaos.zip_nested/folder.pdf"));
-        // Should NOT have the text file
-        assert!(!objects.contains_key("resumes/chaos.zip_ignore_me.txt"));
+        assert!(objects.contains_key("resumes/chaos.zip_notes.txt"));
+        // Type is decided by content, so an image named .pdf is rejected
+        assert!(!objects.contains_key("resumes/chaos.zip_photo.pdf"));
+        assert!(!objects.keys().any(|k| k.contains("__MACOSX")));
+    }
+    let zip = sqlx::query!(r#"SELECT status as "status: DocumentStatus", error_message FROM zip_archives WHERE id = $1"#, zip_id)
+        .fetch_one(&env.pool).await.unwrap();
+    assert!(matches!(zip.status, Some(DocumentStatus::Partial)));
+    assert_eq!(zip.error_message.as_deref(), Some("1 of 4 files could not be processed"));
+
+    // Every member is tracked, including the one that was dropped
+    let members = sqlx::query!(
+        r#"SELECT name, size, object_key, status as "status: ZipMemberStatus", error_message FROM zip_members WHERE zip_id = $1 ORDER BY name"#,
+        zip_id
+    )
+    .fetch_all(&env.pool).await.unwrap();
+    let summary: Vec<(&str, ZipMemberStatus)> = members.iter().map(|m| (m.name.as_str(), m.status)).collect();
+    assert_eq!(summary, vec![
+        ("nested/folder.pdf", ZipMemberStatus::Uploaded),
+        ("notes.txt", ZipMemberStatus::Uploaded),
+        ("photo.pdf", ZipMemberStatus::Skipped),
+        ("valid.pdf", ZipMemberStatus::Uploaded),
+    ]);
+    assert_eq!(members[2].error_message.as_deref(), Some("Unsupported file type"));
+    assert_eq!(members[3].object_key.as_deref(), Some("chaos.zip_valid.pdf"));
+    assert_eq!(members[3].size, Some(8));
+    sqlx::query!("DELETE FROM zip_archives WHERE id = $1", zip_id).execute(&env.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_job_progress_api() {
+    let env = setup_test_env().await;
+    let token = create_jwt(&env.jwt_secret);
+    let get = |uri: String, token: Option<&str>| {
+        let mut request = Request::builder().method("GET").uri(uri);
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        env.app.clone().oneshot(request.body(Body::empty()).unwrap())
+    };
+
+    let job_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status, term) VALUES ($1, 'processing', 'Progress Term')", job_id)
+        .execute(&env.pool).await.unwrap();
+    let zip_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO zip_archives (id, filename, status, job_id) VALUES ($1, 'b.zip', 'partial', $2)", zip_id, job_id)
+        .execute(&env.pool).await.unwrap();
+    let uploads = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+    for (upload_id, status) in uploads.iter().zip([DocumentStatus::Completed, DocumentStatus::Failed, DocumentStatus::Processing]) {
+        sqlx::query!(
+            "INSERT INTO resume_uploads (id, filename, status, job_id) VALUES ($1, 'r.pdf', $2, $3)",
+            upload_id,
+            status as DocumentStatus,
+            job_id
+        )
+        .execute(&env.pool).await.unwrap();
     }
+
+    // Without a token the job stays private
+    let response = get(format!("/jobs/{}", job_id), None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = get(format!("/jobs/{}", job_id), Some(&token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let job: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(job["status"], "processing");
+    assert_eq!(job["term"], "Progress Term");
+    assert_eq!(job["items"]["resumes"], json!({"pending": 0, "processing": 1, "completed": 1, "partial": 0, "failed": 1, "cancelled": 0, "total": 3}));
+    assert_eq!(job["items"]["zip_archives"]["partial"], 1);
+    assert_eq!(job["items"]["projects"]["total"], 0);
+    assert_eq!(job["progress"], 0.75);
+    assert_eq!(job["rust_error"], json!({"resumes": [], "projects": []}));
+    assert!(job["created_at"].as_str().unwrap().ends_with('Z'));
+
+    // The list can be filtered by status
+    let response = get("/jobs?status=processing&limit=200".to_string(), Some(&token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let jobs: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+    assert!(jobs.iter().any(|j| j["id"] == job_id.to_string()));
+    assert!(jobs.iter().all(|j| j["status"] == "processing"));
+
+    let response = get("/jobs?status=ready".to_string(), Some(&token)).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let jobs: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+    assert!(jobs.iter().all(|j| j["id"] != job_id.to_string()));
+
+    let response = get(format!("/jobs/{}", Uuid::new_v4()), Some(&token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Cancelling stops the unfinished upload; a second cancel conflicts
+    let cancel = || {
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/jobs/{}/cancel", job_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        env.app.clone().oneshot(request)
+    };
+    let response = cancel().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let cancelled: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cancelled["cancelled"], json!({"resumes": 1, "zip_archives": 0, "projects": 0}));
+    let response = get(format!("/jobs/{}", job_id), Some(&token)).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let job: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(job["status"], "cancelled");
+    assert_eq!(job["items"]["resumes"]["cancelled"], 1);
+    assert_eq!(job["progress"], 1.0);
+    assert_eq!(cancel().await.unwrap().status(), StatusCode::CONFLICT);
+
+    // Cleanup
+    sqlx::query!("DELETE FROM resume_uploads WHERE job_id = $1", job_id).execute(&env.pool).await.unwrap();
     sqlx::query!("DELETE FROM zip_archives WHERE id = $1", zip_id).execute(&env.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&env.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_job_events_stream() {
+    use matchmaker_orchestrator::events::JobEvent;
+    use matchmaker_orchestrator::service::JobStatus;
+    use tokio_stream::StreamExt;
+
+    let env = setup_test_env().await;
+    let token = create_jwt(&env.jwt_secret);
+    let job_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&env.pool).await.unwrap();
+
+    let response = env
+        .app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/jobs/{}/events", job_id))
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut body = response.into_body().into_data_stream();
+    let mut next_frame = async || {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), body.next())
+            .await
+            .expect("no event within 5s")
+            .unwrap()
+            .unwrap();
+        String::from_utf8(frame.to_vec()).unwrap()
+    };
+
+    // The stream opens with the job's current progress
+    let snapshot = next_frame().await;
+    assert!(snapshot.starts_with("event: snapshot\n"), "{}", snapshot);
+    assert!(snapshot.contains(&format!(r#""id":"{}""#, job_id)));
+
+    // Other jobs' events are filtered out, this job's are passed on
+    env.events.publish(JobEvent::Job { job_id: Uuid::new_v4(), status: JobStatus::Ready }).await;
+    env.events.publish(JobEvent::Job { job_id, status: JobStatus::Ready }).await;
+    let event = next_frame().await;
+    assert!(event.starts_with("event: job\n"), "{}", event);
+    assert!(event.contains(&format!(r#""job_id":"{}""#, job_id)));
+    assert!(event.contains(r#""status":"ready""#));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&env.pool).await.unwrap();
 }
@@ -1,21 +1,59 @@
 use matchmaker_orchestrator::{AppState};
-use matchmaker_orchestrator::service::{ProjectService};
+use matchmaker_orchestrator::archive::ArchiveLimits;
+use matchmaker_orchestrator::cancel::Cancellations;
+use matchmaker_orchestrator::config::Config;
+use matchmaker_orchestrator::shutdown::Shutdown;
+use matchmaker_orchestrator::dedup::DuplicatePolicy;
+use matchmaker_orchestrator::embeddings::EmbeddingClient;
+use matchmaker_orchestrator::events::EventBus;
+use matchmaker_orchestrator::llm::MockLlmProvider;
+use matchmaker_orchestrator::queue::{TaskKind, TaskQueue, TaskStatus};
+use matchmaker_orchestrator::service::{EmbeddingService, ProjectService};
 use matchmaker_orchestrator::storage::MockStorageProvider;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::Semaphore;
 use serde_json::json;
 use uuid::Uuid;
 
+/// Counts how many texts it was asked to embed and returns a fixed vector for each.
+#[derive(Default)]
+struct CountingEmbeddingClient {
+    calls: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl EmbeddingClient for CountingEmbeddingClient {
+    fn model(&self) -> &str {
+        "counting-model"
+    }
+
+    async fn embed(&self, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        self.calls.fetch_add(inputs.len(), Ordering::SeqCst);
+        Ok(inputs.iter().map(|_| vec![1.0, 0.0]).collect())
+    }
+}
+
 async fn setup_app_state() -> AppState {
+    setup_app_state_with_embeddings(Arc::new(CountingEmbeddingClient::default())).await
+}
+
+async fn setup_app_state_with_embeddings(embeddings: Arc<dyn EmbeddingClient>) -> AppState {
     dotenvy::dotenv().ok();
     let pool = sqlx::PgPool::connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set")).await.unwrap();
     AppState {
-        pool,
+        config: Arc::new(Config::default()),
+        pool: pool.clone(),
         storage: Arc::new(MockStorageProvider::new()),
+        embeddings: Some(embeddings),
+        queue: TaskQueue::new(pool.clone()),
+        events: EventBus::new(),
+        cancellations: Cancellations::new(),
+        shutdown: Shutdown::new(),
         http_client: reqwest::Client::new(),
-        openai_api_key: "test".to_string(),
-        openai_endpoint: "test".to_string(),
+        llm: Arc::new(MockLlmProvider::with_json(json!({}))),
         resume_schema: json!({}),
+        ocr: None,
         semaphore: Arc::new(Semaphore::new(1)),
         jwt_secret: "test".to_string(),
     }
@@ -104,3 +142,1342 @@ async fn test_job_readiness_logic() {
     sqlx::query!("DELETE FROM project_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
     sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
 }
+
+#[tokio::test]
+async fn test_project_embeddings_only_recomputed_on_change() {
+    let client = Arc::new(CountingEmbeddingClient::default());
+    let state = setup_app_state_with_embeddings(client.clone()).await;
+    let service = EmbeddingService::new(state.clone());
+
+    let upload_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO project_uploads (id, filename, status) VALUES ($1, 'p.csv', 'completed')", upload_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!(
+        "INSERT INTO projects (upload_id, title, description, requirements) VALUES ($1, 'A', 'Desc A', 'Rust'), ($1, 'B', 'Desc B', 'SQL')",
+        upload_id
+    )
+    .execute(&state.pool).await.unwrap();
+
+    // 1. First pass embeds both projects
+    assert_eq!(service.embed_projects(upload_id).await.unwrap(), 2);
+    assert_eq!(client.calls.load(Ordering::SeqCst), 2);
+
+    // 2. Second pass with unchanged content is a no-op
+    assert_eq!(service.embed_projects(upload_id).await.unwrap(), 0);
+    assert_eq!(client.calls.load(Ordering::SeqCst), 2);
+
+    // 3. Changing one project only re-embeds that one
+    sqlx::query!("UPDATE projects SET requirements = 'Rust, Go' WHERE upload_id = $1 AND title = 'A'", upload_id)
+        .execute(&state.pool).await.unwrap();
+    assert_eq!(service.embed_projects(upload_id).await.unwrap(), 1);
+    assert_eq!(client.calls.load(Ordering::SeqCst), 3);
+
+    // 4. Without an embeddings client nothing is embedded
+    sqlx::query!("UPDATE projects SET requirements = 'SQL, Go' WHERE upload_id = $1 AND title = 'B'", upload_id)
+        .execute(&state.pool).await.unwrap();
+    let disabled = EmbeddingService::new(AppState { embeddings: None, ..state.clone() });
+    assert_eq!(disabled.embed_projects(upload_id).await.unwrap(), 0);
+    assert_eq!(client.calls.load(Ordering::SeqCst), 3);
+
+    let rows = sqlx::query!("SELECT embedding, embedding_model FROM projects WHERE upload_id = $1", upload_id)
+        .fetch_all(&state.pool).await.unwrap();
+    for row in rows {
+        assert_eq!(row.embedding.unwrap(), vec![1.0f32, 0.0]);
+        assert_eq!(row.embedding_model.as_deref(), Some("counting-model"));
+    }
+
+    // Cleanup
+    sqlx::query!("DELETE FROM projects WHERE upload_id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM project_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::matching::MatchingService;
+
+#[tokio::test]
+async fn test_matching_drives_job_to_completed() {
+    let state = setup_app_state().await;
+    let service = MatchingService::new(state.clone());
+
+    let job_id = Uuid::new_v4();
+    let term = format!("TestTerm-{}", Uuid::new_v4());
+    sqlx::query!("INSERT INTO jobs (id, status, term) VALUES ($1, 'ready', $2)", job_id, term)
+        .execute(&state.pool).await.unwrap();
+
+    // 1. Two projects with a combined capacity of 2 and three candidates
+    let upload_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO project_uploads (id, filename, status, job_id, term) VALUES ($1, 'p.csv', 'completed', $2, $3)", upload_id, job_id, term)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!(
+        "INSERT INTO projects (upload_id, title, requirements, priority, intern_cap, term) VALUES ($1, 'Rust API', 'Rust, SQL', 1, 1, $2), ($1, 'Dashboard', 'React', 2, 1, $2)",
+        upload_id,
+        term
+    )
+    .execute(&state.pool).await.unwrap();
+
+    for skills in [json!(["Rust", "SQL"]), json!(["React", "CSS"]), json!(["Cobol"])] {
+        sqlx::query!(
+            "INSERT INTO resumes (filename, structured, term) VALUES ('r.pdf', $1, $2)",
+            json!({ "skills": skills, "experience": [] }),
+            term
+        )
+        .execute(&state.pool).await.unwrap();
+    }
+
+    // 2. Run matching
+    service.run_job(job_id).await;
+
+    let job = sqlx::query_as::<_, (JobStatus,)>("SELECT status FROM jobs WHERE id = $1")
+        .bind(job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(job.0, JobStatus::Completed);
+
+    // 3. Each project got its best-fitting candidate and nobody was assigned twice
+    let matches = sqlx::query!(
+        r#"
+        SELECT p.title, r.structured FROM matches m
+        JOIN projects p ON p.id = m.project_id
+        JOIN resumes r ON r.id = m.resume_id
+        WHERE m.job_id = $1 AND m.decision = 'pending'
+        ORDER BY p.priority
+        "#,
+        job_id
+    )
+    .fetch_all(&state.pool).await.unwrap();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].title, "Rust API");
+    assert_eq!(matches[0].structured.as_ref().unwrap()["skills"][0], "Rust");
+    assert_eq!(matches[1].title, "Dashboard");
+    assert_eq!(matches[1].structured.as_ref().unwrap()["skills"][0], "React");
+
+    // 4. A job that is not `ready` is left alone
+    service.run_job(job_id).await;
+    let count = sqlx::query!("SELECT count(*) FROM matches WHERE job_id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(count.count, Some(2));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM matches WHERE job_id = $1", job_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resumes WHERE term = $1", term).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM projects WHERE upload_id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM project_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_matching_fails_job_without_projects() {
+    let state = setup_app_state().await;
+    let service = MatchingService::new(state.clone());
+
+    let job_id = Uuid::new_v4();
+    let term = format!("TestTerm-{}", Uuid::new_v4());
+    sqlx::query!("INSERT INTO jobs (id, status, term) VALUES ($1, 'ready', $2)", job_id, term)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!(
+        "INSERT INTO resumes (filename, structured, term) VALUES ('r.pdf', $1, $2)",
+        json!({ "skills": ["Rust"], "experience": [] }),
+        term
+    )
+    .execute(&state.pool).await.unwrap();
+
+    service.run_job(job_id).await;
+
+    let job = sqlx::query!("SELECT status as \"status: JobStatus\", rust_error FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(job.status, Some(JobStatus::Failed));
+    assert!(job.rust_error.unwrap()["matching"].as_str().unwrap().contains("No projects"));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM resumes WHERE term = $1", term).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_task_queue_claim_and_reclaim() {
+    let state = setup_app_state().await;
+    let queue_name = format!("test-{}", Uuid::new_v4());
+    let queue = TaskQueue::with_name(state.pool.clone(), &queue_name)
+        .visibility_timeout(std::time::Duration::ZERO);
+
+    // 1. Duplicate webhooks for the same upload only enqueue once
+    let upload_id = Uuid::new_v4();
+    queue.enqueue(TaskKind::Resume, upload_id, "a.pdf").await.unwrap();
+    queue.enqueue(TaskKind::Resume, upload_id, "a.pdf").await.unwrap();
+    let count = sqlx::query!("SELECT count(*) FROM tasks WHERE queue = $1", queue_name)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(count.count, Some(1));
+
+    // 2. Claim marks the task running
+    let task = queue.claim().await.unwrap().expect("task should be claimable");
+    assert_eq!(task.upload_id, upload_id);
+    assert_eq!(task.kind, TaskKind::Resume);
+    assert_eq!(task.attempts, 1);
+
+    // 3. With a zero visibility timeout the lease has already expired, simulating a crashed worker
+    let reclaimed = queue.claim().await.unwrap().expect("abandoned task should be re-claimed");
+    assert_eq!(reclaimed.id, task.id);
+    assert_eq!(reclaimed.attempts, 2);
+
+    // 4. Completed tasks are never handed out again
+    queue.complete(task.id).await.unwrap();
+    assert!(queue.claim().await.unwrap().is_none());
+    let row = sqlx::query!("SELECT status as \"status: TaskStatus\" FROM tasks WHERE id = $1", task.id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(row.status, TaskStatus::Completed);
+
+    // Cleanup
+    sqlx::query!("DELETE FROM tasks WHERE queue = $1", queue_name).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::service::{BACKFILL_LOCK, DocumentStatus, REPARSE_LOCK, ResumeService};
+use matchmaker_orchestrator::storage::StorageProvider;
+use matchmaker_orchestrator::validation::RESUME_SCHEMA_VERSION;
+
+fn resume_schema() -> serde_json::Value {
+    serde_json::from_str(include_str!("../src/resume_schema.json")).unwrap()
+}
+
+fn valid_structured_resume() -> serde_json::Value {
+    json!({
+        "contact": { "name": "Alex Rivera", "email": null, "phone": null, "location": null },
+        "links": { "github": null, "linkedin": null, "portfolio": null },
+        "work_authorization": null,
+        "graduation_date": "2026-05",
+        "education": [],
+        "skills": ["Rust"],
+        "experience": [],
+        "projects": [],
+        "certifications": []
+    })
+}
+
+#[tokio::test]
+async fn test_invalid_llm_output_is_repaired_once() {
+    let mut state = setup_app_state().await;
+    let llm = Arc::new(MockLlmProvider::new(vec![
+        json!({ "skills": "Rust" }).to_string(),
+        valid_structured_resume().to_string(),
+    ]));
+    state.llm = llm.clone();
+    state.resume_schema = resume_schema();
+
+    let pdf_bytes = std::fs::read("archive.zip-resumes/Alex_Rivera_CV.pdf").unwrap();
+    let (_, structured) = ResumeService::new(state)
+        .process_document(&pdf_bytes, "Alex_Rivera_CV.pdf", Uuid::new_v4(), Uuid::new_v4())
+        .await
+        .expect("repaired output should validate");
+    assert_eq!(structured["skills"][0], "Rust");
+
+    // The repair turn echoes the bad reply and lists the violations
+    let requests = llm.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    let repair = &requests[1];
+    assert_eq!(repair.len(), 4);
+    assert_eq!(repair[2].role, "assistant");
+    assert!(repair[3].content.contains("/skills"));
+    assert!(repair[3].content.contains("education"));
+}
+
+#[tokio::test]
+async fn test_schema_violation_is_recorded_on_upload_and_job() {
+    let mut state = setup_app_state().await;
+    let storage = Arc::new(MockStorageProvider::new());
+    state.storage = storage.clone();
+    state.llm = Arc::new(MockLlmProvider::with_json(json!({ "skills": ["Rust"] })));
+    state.resume_schema = resume_schema();
+    Arc::make_mut(&mut state.config).llm.schema_repair = false;
+
+    let job_id = Uuid::new_v4();
+    let upload_id = Uuid::new_v4();
+    let pdf_bytes = std::fs::read("archive.zip-resumes/Alex_Rivera_CV.pdf").unwrap();
+    storage.put_object("resumes", "invalid.pdf", pdf_bytes, None).await.unwrap();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO resume_uploads (id, filename, status, job_id) VALUES ($1, 'invalid.pdf', 'pending', $2)", upload_id, job_id)
+        .execute(&state.pool).await.unwrap();
+
+    ResumeService::new(state.clone()).process_resume_upload(upload_id, "invalid.pdf".to_string()).await;
+
+    let upload = sqlx::query!("SELECT status as \"status: DocumentStatus\", error_message FROM resume_uploads WHERE id = $1", upload_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert!(matches!(upload.status, Some(DocumentStatus::Failed)));
+    let error_message = upload.error_message.unwrap();
+    assert!(error_message.contains("schema validation"), "{}", error_message);
+    assert!(error_message.contains("education"), "{}", error_message);
+
+    let job = sqlx::query!("SELECT rust_error FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    let resumes = job.rust_error.unwrap()["resumes"].as_array().unwrap().clone();
+    assert_eq!(resumes.len(), 1);
+    assert!(resumes[0]["error"].as_str().unwrap().contains("schema validation"));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM resumes WHERE upload_id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_outdated_resumes_are_reparsed() {
+    let mut state = setup_app_state().await;
+    let llm = Arc::new(MockLlmProvider::with_json(valid_structured_resume()));
+    state.llm = llm.clone();
+    state.resume_schema = resume_schema();
+
+    let outdated_id = Uuid::new_v4();
+    let current_id = Uuid::new_v4();
+    let old_structured = json!({ "education": [], "skills": ["rust"], "experience": [] });
+    sqlx::query!(
+        "INSERT INTO resumes (id, filename, text, structured) VALUES ($1, 'old.pdf', 'Alex Rivera, Rust developer', $2)",
+        outdated_id,
+        old_structured
+    )
+    .execute(&state.pool).await.unwrap();
+    sqlx::query!(
+        "INSERT INTO resumes (id, filename, text, structured, schema_version) VALUES ($1, 'new.pdf', 'Already current', $2, $3)",
+        current_id,
+        valid_structured_resume(),
+        RESUME_SCHEMA_VERSION
+    )
+    .execute(&state.pool).await.unwrap();
+
+    // Nothing happens while another replica holds the lock
+    let mut other = state.pool.acquire().await.unwrap();
+    sqlx::query!("SELECT pg_advisory_lock($1)", REPARSE_LOCK).execute(&mut *other).await.unwrap();
+    assert_eq!(ResumeService::new(state.clone()).reparse_outdated().await.unwrap(), 0);
+    assert!(llm.requests.lock().unwrap().is_empty());
+    sqlx::query_scalar!("SELECT pg_advisory_unlock($1)", REPARSE_LOCK).fetch_one(&mut *other).await.unwrap();
+
+    let upgraded = ResumeService::new(state.clone()).reparse_outdated().await.unwrap();
+    assert!(upgraded >= 1);
+
+    let row = sqlx::query!("SELECT structured, schema_version, embedding_hash FROM resumes WHERE id = $1", outdated_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(row.schema_version, Some(RESUME_SCHEMA_VERSION));
+    assert_eq!(row.structured.unwrap()["graduation_date"], "2026-05");
+    assert!(row.embedding_hash.is_some(), "re-parsed resume should be re-embedded");
+
+    // Parsing ran from the stored text and never touched the up-to-date resume
+    let prompts: Vec<String> = llm.requests.lock().unwrap().iter().map(|r| r[1].content.clone()).collect();
+    assert!(prompts.iter().any(|p| p == "Alex Rivera, Rust developer"));
+    assert!(!prompts.iter().any(|p| p == "Already current"));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM resumes WHERE id = ANY($1)", &[outdated_id, current_id][..])
+        .execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unsupported_single_upload_is_reported() {
+    let mut state = setup_app_state().await;
+    let storage = Arc::new(MockStorageProvider::new());
+    state.storage = storage.clone();
+    let llm = Arc::new(MockLlmProvider::with_json(valid_structured_resume()));
+    state.llm = llm.clone();
+
+    let job_id = Uuid::new_v4();
+    let upload_id = Uuid::new_v4();
+    storage.put_object("resumes", "headshot.pdf", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec(), None).await.unwrap();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO resume_uploads (id, filename, status, job_id) VALUES ($1, 'headshot.pdf', 'pending', $2)", upload_id, job_id)
+        .execute(&state.pool).await.unwrap();
+
+    ResumeService::new(state.clone()).process_resume_upload(upload_id, "headshot.pdf".to_string()).await;
+
+    let upload = sqlx::query!("SELECT status as \"status: DocumentStatus\", error_message FROM resume_uploads WHERE id = $1", upload_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert!(matches!(upload.status, Some(DocumentStatus::Failed)));
+    assert!(upload.error_message.unwrap().contains("Unsupported file type"));
+    assert!(llm.requests.lock().unwrap().is_empty(), "unsupported files never reach the LLM");
+
+    // Cleanup
+    sqlx::query!("DELETE FROM resumes WHERE upload_id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::service::ZipMemberStatus;
+use std::io::Write;
+
+/// Wraps `MockStorageProvider` and rejects uploads to one key.
+struct RejectingStorage {
+    inner: MockStorageProvider,
+    reject_key: String,
+}
+
+#[async_trait::async_trait]
+impl StorageProvider for RejectingStorage {
+    async fn get_object(&self, bucket: &str, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.inner.get_object(bucket, key).await
+    }
+
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+        metadata: Option<std::collections::HashMap<String, String>>,
+    ) -> anyhow::Result<()> {
+        if key == self.reject_key {
+            anyhow::bail!("Access denied");
+        }
+        self.inner.put_object(bucket, key, body, metadata).await
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> anyhow::Result<()> {
+        self.inner.delete_object(bucket, key).await
+    }
+}
+
+#[tokio::test]
+async fn test_zip_members_are_tracked_per_file() {
+    let mut state = setup_app_state().await;
+    let storage = Arc::new(RejectingStorage {
+        inner: MockStorageProvider::new(),
+        reject_key: "batch.zip_b.txt".to_string(),
+    });
+    state.storage = storage.clone();
+    state.llm = Arc::new(MockLlmProvider::with_json(valid_structured_resume()));
+
+    let mut buf = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut buf));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("a.txt", options).unwrap();
+        zip.write_all(b"Alex Rivera - Rust").unwrap();
+        zip.start_file("b.txt", options).unwrap();
+        zip.write_all(b"Jordan Lee - SQL").unwrap();
+        zip.finish().unwrap();
+    }
+    storage.put_object("zip-archives", "batch.zip", buf, None).await.unwrap();
+
+    let job_id = Uuid::new_v4();
+    let zip_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO zip_archives (id, filename, status, job_id) VALUES ($1, 'batch.zip', 'pending', $2)", zip_id, job_id)
+        .execute(&state.pool).await.unwrap();
+
+    let service = ResumeService::new(state.clone());
+    service.handle_batch_extraction(zip_id, "batch.zip".to_string()).await;
+
+    // The failed re-upload makes the archive partial and is recorded on its member and the job
+    let zip = sqlx::query!("SELECT status as \"status: DocumentStatus\", error_message FROM zip_archives WHERE id = $1", zip_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert!(matches!(zip.status, Some(DocumentStatus::Partial)));
+    assert_eq!(zip.error_message.as_deref(), Some("1 of 2 files could not be processed"));
+
+    let failed = sqlx::query!(
+        "SELECT id, status as \"status: ZipMemberStatus\", error_message FROM zip_members WHERE zip_id = $1 AND name = 'b.txt'",
+        zip_id
+    )
+    .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(failed.status, ZipMemberStatus::Failed);
+    assert!(failed.error_message.unwrap().contains("Access denied"));
+
+    let job = sqlx::query!("SELECT rust_error FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    let resumes = job.rust_error.unwrap()["resumes"].as_array().unwrap().clone();
+    assert_eq!(resumes.len(), 1);
+    assert_eq!(resumes[0]["id"], failed.id.to_string());
+    assert_eq!(resumes[0]["zip_id"], zip_id.to_string());
+    assert_eq!(resumes[0]["filename"], "b.txt");
+
+    // Processing the re-uploaded member links its resume_uploads row back to the member
+    let upload_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO resume_uploads (id, filename, status, zip_id) VALUES ($1, 'batch.zip_a.txt', 'pending', $2)", upload_id, zip_id)
+        .execute(&state.pool).await.unwrap();
+    service.process_resume_upload(upload_id, "batch.zip_a.txt".to_string()).await;
+
+    let uploaded = sqlx::query!(
+        "SELECT status as \"status: ZipMemberStatus\", resume_upload_id FROM zip_members WHERE zip_id = $1 AND name = 'a.txt'",
+        zip_id
+    )
+    .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(uploaded.status, ZipMemberStatus::Uploaded);
+    assert_eq!(uploaded.resume_upload_id, Some(upload_id));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM resumes WHERE upload_id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM zip_archives WHERE id = $1", zip_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_zip_entry_names_and_limits() {
+    let mut state = setup_app_state().await;
+    let storage = Arc::new(MockStorageProvider::new());
+    state.storage = storage.clone();
+
+    let mut buf = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut buf));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("../../etc/alex.txt", options).unwrap();
+        zip.write_all(b"Alex Rivera - Rust").unwrap();
+        zip.start_file("etc\\alex.txt", options).unwrap();
+        zip.write_all(b"Jordan Lee - SQL").unwrap();
+        zip.finish().unwrap();
+    }
+    storage.put_object("zip-archives", "names.zip", buf, None).await.unwrap();
+
+    let zip_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO zip_archives (id, filename, status) VALUES ($1, 'names.zip', 'pending')", zip_id)
+        .execute(&state.pool).await.unwrap();
+    ResumeService::new(state.clone()).handle_batch_extraction(zip_id, "names.zip".to_string()).await;
+
+    // Traversal is stripped and the names that now collide get distinct keys
+    let mut members: Vec<String> = sqlx::query_scalar!("SELECT object_key FROM zip_members WHERE zip_id = $1", zip_id)
+        .fetch_all(&state.pool).await.unwrap()
+        .into_iter().flatten().collect();
+    members.sort();
+    assert_eq!(members, vec!["names.zip_etc/alex (2).txt", "names.zip_etc/alex.txt"]);
+    assert!(storage.objects.lock().unwrap().contains_key("resumes/names.zip_etc/alex (2).txt"));
+
+    // The same archive is rejected outright once it exceeds the entry limit
+    Arc::make_mut(&mut state.config).archive = ArchiveLimits { max_entries: 1, ..ArchiveLimits::default() };
+    let rejected_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO zip_archives (id, filename, status) VALUES ($1, 'names.zip', 'pending')", rejected_id)
+        .execute(&state.pool).await.unwrap();
+    ResumeService::new(state.clone()).handle_batch_extraction(rejected_id, "names.zip".to_string()).await;
+
+    let zip = sqlx::query!("SELECT status as \"status: DocumentStatus\", error_message FROM zip_archives WHERE id = $1", rejected_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert!(matches!(zip.status, Some(DocumentStatus::Failed)));
+    assert_eq!(zip.error_message.as_deref(), Some("Archive rejected: 2 entries exceeds the limit of 1"));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM zip_archives WHERE id = ANY($1)", &[zip_id, rejected_id]).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_archive_rejected_part_way_uploads_nothing() {
+    let mut state = setup_app_state().await;
+    let storage = Arc::new(MockStorageProvider::new());
+    state.storage = storage.clone();
+    Arc::make_mut(&mut state.config).archive = ArchiveLimits { max_entry_bytes: 64, ..ArchiveLimits::default() };
+
+    // tar has no central directory, so the oversized second entry is only found after the first
+    let mut tarball = tar::Builder::new(Vec::new());
+    for (name, data) in [("alex.txt", &b"Alex Rivera - Rust"[..]), ("huge.txt", &[b'x'; 128][..])] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        tarball.append_data(&mut header, name, data).unwrap();
+    }
+    storage.put_object("zip-archives", "late.tar", tarball.into_inner().unwrap(), None).await.unwrap();
+
+    let job_id = Uuid::new_v4();
+    let zip_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO zip_archives (id, filename, status, job_id) VALUES ($1, 'late.tar', 'pending', $2)", zip_id, job_id)
+        .execute(&state.pool).await.unwrap();
+    ResumeService::new(state.clone()).handle_batch_extraction(zip_id, "late.tar".to_string()).await;
+
+    let zip = sqlx::query!("SELECT status as \"status: DocumentStatus\" FROM zip_archives WHERE id = $1", zip_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert!(matches!(zip.status, Some(DocumentStatus::Failed)));
+    assert!(!storage.objects.lock().unwrap().contains_key("resumes/late.tar_alex.txt"));
+    let members = sqlx::query_scalar!("SELECT count(*) FROM zip_members WHERE zip_id = $1", zip_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(members, Some(0));
+
+    // The rejection reaches the job like any other failure
+    let job = sqlx::query!("SELECT rust_error FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    let errors = job.rust_error.unwrap()["resumes"].as_array().unwrap().clone();
+    assert!(errors[0]["error"].as_str().unwrap().starts_with("Archive rejected: entry 'huge.txt'"));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM zip_archives WHERE id = $1", zip_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_nested_tarball_members_keep_zip_lineage() {
+    let mut state = setup_app_state().await;
+    let storage = Arc::new(MockStorageProvider::new());
+    state.storage = storage.clone();
+
+    let mut inner = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut inner));
+        zip.start_file("sam.txt", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"Sam Patel - Go").unwrap();
+        zip.finish().unwrap();
+    }
+    let mut tarball = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+    for (name, data) in [("alex.txt", &b"Alex Rivera - Rust"[..]), ("more/inner.zip", &inner[..])] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        tarball.append_data(&mut header, name, data).unwrap();
+    }
+    let tarball = tarball.into_inner().unwrap().finish().unwrap();
+    storage.put_object("zip-archives", "bundle.tar.gz", tarball, None).await.unwrap();
+
+    let job_id = Uuid::new_v4();
+    let zip_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO zip_archives (id, filename, status, job_id) VALUES ($1, 'bundle.tar.gz', 'pending', $2)", zip_id, job_id)
+        .execute(&state.pool).await.unwrap();
+    ResumeService::new(state.clone()).handle_batch_extraction(zip_id, "bundle.tar.gz".to_string()).await;
+
+    let zip = sqlx::query!("SELECT status as \"status: DocumentStatus\" FROM zip_archives WHERE id = $1", zip_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert!(matches!(zip.status, Some(DocumentStatus::Completed)));
+
+    // Members of the nested ZIP are re-uploaded like any other, with the outer archive's lineage
+    for key in ["resumes/bundle.tar.gz_alex.txt", "resumes/bundle.tar.gz_more/inner.zip/sam.txt"] {
+        let (_, metadata) = storage.objects.lock().unwrap().get(key).cloned()
+            .unwrap_or_else(|| panic!("{} was not uploaded", key));
+        let metadata = metadata.unwrap();
+        assert_eq!(metadata["zip_id"], zip_id.to_string());
+        assert_eq!(metadata["job_id"], job_id.to_string());
+    }
+
+    // Cleanup
+    sqlx::query!("DELETE FROM zip_archives WHERE id = $1", zip_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::config::StorageBackend;
+use matchmaker_orchestrator::storage::LocalStorageProvider;
+
+#[tokio::test]
+async fn test_local_storage_queues_zip_members_itself() {
+    let mut state = setup_app_state().await;
+    let root = tempfile::tempdir().unwrap();
+    Arc::make_mut(&mut state.config).storage.backend = StorageBackend::Local;
+    state.storage = Arc::new(LocalStorageProvider::new(root.path()));
+
+    let mut archive = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut archive));
+        zip.start_file("alex.txt", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"Alex Rivera - Rust").unwrap();
+        zip.finish().unwrap();
+    }
+    let filename = format!("{}.zip", Uuid::new_v4());
+    state.storage.put_object("zip-archives", &filename, archive, None).await.unwrap();
+
+    let job_id = Uuid::new_v4();
+    let zip_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO zip_archives (id, filename, status, job_id, term) VALUES ($1, $2, 'pending', $3, 'Fall')", zip_id, filename, job_id)
+        .execute(&state.pool).await.unwrap();
+    ResumeService::new(state.clone()).handle_batch_extraction(zip_id, filename.clone()).await;
+
+    // Without a storage webhook, the member gets its upload row and task from the archive
+    let key = format!("{}_alex.txt", filename);
+    let upload = sqlx::query!("SELECT id, job_id, term, status as \"status: DocumentStatus\" FROM resume_uploads WHERE zip_id = $1", zip_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(upload.job_id, Some(job_id));
+    assert_eq!(upload.term.as_deref(), Some("Fall"));
+    assert!(matches!(upload.status, Some(DocumentStatus::Pending)));
+    let task = sqlx::query!("SELECT filename FROM tasks WHERE kind = 'resume' AND upload_id = $1", upload.id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(task.filename, key);
+    assert!(state.storage.get_object("resumes", &key).await.is_ok());
+
+    // Cleanup
+    sqlx::query!("DELETE FROM tasks WHERE upload_id = $1", upload.id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM zip_members WHERE zip_id = $1", zip_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE id = $1", upload.id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM zip_archives WHERE id = $1", zip_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+/// Serves objects from a `MockStorageProvider`, but the first stream breaks half way through.
+struct BrokenStreamStorage {
+    inner: MockStorageProvider,
+    broken: std::sync::atomic::AtomicBool,
+}
+
+/// Fails every read, like a connection reset.
+struct ResetReader;
+
+impl tokio::io::AsyncRead for ResetReader {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+        _buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Err(std::io::ErrorKind::ConnectionReset.into()))
+    }
+}
+
+#[async_trait::async_trait]
+impl StorageProvider for BrokenStreamStorage {
+    async fn get_object(&self, bucket: &str, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.inner.get_object(bucket, key).await
+    }
+
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+        metadata: Option<std::collections::HashMap<String, String>>,
+    ) -> anyhow::Result<()> {
+        self.inner.put_object(bucket, key, body, metadata).await
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> anyhow::Result<()> {
+        self.inner.delete_object(bucket, key).await
+    }
+
+    async fn get_object_stream(&self, bucket: &str, key: &str) -> anyhow::Result<matchmaker_orchestrator::storage::ObjectStream> {
+        use tokio::io::AsyncReadExt;
+        let data = self.inner.get_object(bucket, key).await?;
+        if self.broken.swap(false, Ordering::SeqCst) {
+            let half = data[..data.len() / 2].to_vec();
+            return Ok(Box::new(std::io::Cursor::new(half).chain(ResetReader)));
+        }
+        Ok(Box::new(std::io::Cursor::new(data)))
+    }
+}
+
+#[tokio::test]
+async fn test_broken_archive_download_is_restarted() {
+    let mut state = setup_app_state().await;
+    Arc::make_mut(&mut state.config).retry.base_delay_ms = 1;
+    let storage = Arc::new(BrokenStreamStorage {
+        inner: MockStorageProvider::new(),
+        broken: std::sync::atomic::AtomicBool::new(true),
+    });
+    state.storage = storage.clone();
+
+    let mut buf = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut buf));
+        zip.start_file("alex.txt", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all("Alex Rivera - Rust\n".repeat(200).as_bytes()).unwrap();
+        zip.finish().unwrap();
+    }
+    storage.put_object("zip-archives", "stream.zip", buf, None).await.unwrap();
+
+    let zip_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO zip_archives (id, filename, status) VALUES ($1, 'stream.zip', 'pending')", zip_id)
+        .execute(&state.pool).await.unwrap();
+    ResumeService::new(state.clone()).handle_batch_extraction(zip_id, "stream.zip".to_string()).await;
+
+    // The retry starts the download over instead of appending to the partial file
+    let zip = sqlx::query!("SELECT status as \"status: DocumentStatus\", error_message FROM zip_archives WHERE id = $1", zip_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert!(matches!(zip.status, Some(DocumentStatus::Completed)), "{:?}", zip.error_message);
+    let uploaded = storage.inner.get_object("resumes", "stream.zip_alex.txt").await.unwrap();
+    assert_eq!(uploaded, "Alex Rivera - Rust\n".repeat(200).into_bytes());
+
+    // Cleanup
+    sqlx::query!("DELETE FROM zip_archives WHERE id = $1", zip_id).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_duplicate_resumes_are_linked_or_skipped() {
+    let mut state = setup_app_state().await;
+    let llm = Arc::new(MockLlmProvider::with_json(json!({"name": "Jordan Lee"})));
+    state.llm = llm.clone();
+    let tag = Uuid::new_v4();
+    let text = format!("Jordan Lee {}\nGo developer\n", tag);
+    let reformatted = format!("JORDAN  LEE {}\r\n\r\nGo Developer", tag.to_string().to_uppercase());
+    state.storage.put_object("resumes", "jordan.txt", text.clone().into_bytes(), None).await.unwrap();
+    state.storage.put_object("resumes", "copy.txt", text.into_bytes(), None).await.unwrap();
+    state.storage.put_object("resumes", "export.txt", reformatted.into_bytes(), None).await.unwrap();
+
+    let job_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'pending')", job_id)
+        .execute(&state.pool).await.unwrap();
+    let uploads = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+    for (upload_id, filename) in uploads.iter().zip(["jordan.txt", "copy.txt", "export.txt"]) {
+        sqlx::query!("INSERT INTO resume_uploads (id, filename, status, job_id, term) VALUES ($1, $2, 'pending', $3, 'Fall')", upload_id, filename, job_id)
+            .execute(&state.pool).await.unwrap();
+    }
+
+    // The first upload is parsed, the identical second one is linked to it
+    ResumeService::new(state.clone()).process_resume_upload(uploads[0], "jordan.txt".to_string()).await;
+    ResumeService::new(state.clone()).process_resume_upload(uploads[1], "copy.txt".to_string()).await;
+    assert_eq!(llm.requests.lock().unwrap().len(), 1);
+
+    let original = sqlx::query!("SELECT id, file_hash, text_hash FROM resumes WHERE upload_id = $1", uploads[0])
+        .fetch_one(&state.pool).await.unwrap();
+    assert!(original.file_hash.is_some() && original.text_hash.is_some());
+    let linked = sqlx::query!("SELECT duplicate_of, structured, embedding_model FROM resumes WHERE upload_id = $1", uploads[1])
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(linked.duplicate_of, Some(original.id));
+    assert_eq!(linked.structured, Some(json!({"name": "Jordan Lee"})));
+    assert_eq!(linked.embedding_model.as_deref(), Some("counting-model"));
+
+    // A different file with the same text is caught before the LLM call and skipped
+    Arc::make_mut(&mut state.config).duplicate_policy = DuplicatePolicy::Skip;
+    ResumeService::new(state.clone()).process_resume_upload(uploads[2], "export.txt".to_string()).await;
+    assert_eq!(llm.requests.lock().unwrap().len(), 1);
+    let skipped = sqlx::query_scalar!("SELECT count(*) FROM resumes WHERE upload_id = $1", uploads[2])
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(skipped, Some(0));
+    let upload = sqlx::query!("SELECT status as \"status: DocumentStatus\" FROM resume_uploads WHERE id = $1", uploads[2])
+        .fetch_one(&state.pool).await.unwrap();
+    assert!(matches!(upload.status, Some(DocumentStatus::Completed)));
+
+    let job = sqlx::query!("SELECT status as \"status: JobStatus\", rust_error FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(job.status, Some(JobStatus::Ready));
+    let duplicates = job.rust_error.unwrap()["duplicates"].as_array().unwrap().clone();
+    assert_eq!(duplicates.len(), 2);
+    assert_eq!(duplicates[0]["id"], uploads[1].to_string());
+    assert_eq!(duplicates[0]["matched_on"], "file");
+    assert_eq!(duplicates[0]["action"], "linked");
+    assert_eq!(duplicates[1]["duplicate_of"], original.id.to_string());
+    assert_eq!(duplicates[1]["matched_on"], "text");
+    assert_eq!(duplicates[1]["action"], "skipped");
+
+    // A skipped duplicate whose row is already matched can't be deleted and stays linked
+    let match_id = sqlx::query_scalar!("INSERT INTO matches (resume_id, score) SELECT id, 0.5 FROM resumes WHERE upload_id = $1 RETURNING id", uploads[1])
+        .fetch_one(&state.pool).await.unwrap();
+    ResumeService::new(state.clone()).process_resume_upload(uploads[1], "copy.txt".to_string()).await;
+    let kept = sqlx::query_scalar!("SELECT duplicate_of FROM resumes WHERE upload_id = $1", uploads[1])
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(kept, Some(original.id));
+    let upload = sqlx::query!("SELECT status as \"status: DocumentStatus\" FROM resume_uploads WHERE id = $1", uploads[1])
+        .fetch_one(&state.pool).await.unwrap();
+    assert!(matches!(upload.status, Some(DocumentStatus::Completed)));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM matches WHERE id = $1", match_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM interns WHERE id IN (SELECT intern_id FROM resumes WHERE upload_id = ANY($1))", &uploads[..]).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resumes WHERE upload_id = ANY($1)", &uploads[..]).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE id = ANY($1)", &uploads[..]).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::identity::Identity;
+
+#[tokio::test]
+async fn test_intern_resumes_are_grouped_and_ranked_once() {
+    let state = setup_app_state().await;
+    let service = ResumeService::new(state.clone());
+    let term = format!("TestTerm-{}", Uuid::new_v4());
+    let email = format!("{}@example.com", Uuid::new_v4());
+
+    // An intern updates their resume mid-term, a second intern shares their name
+    let mut resume_ids = Vec::new();
+    for (contact, skills, age) in [
+        (json!({"name": "Sam Park", "email": email, "phone": null}), json!(["Rust", "SQL"]), 2),
+        (json!({"name": "Samuel Park", "email": email.to_uppercase(), "phone": "555-010-2000"}), json!(["React"]), 1),
+        (json!({"name": "Sam Park", "email": "other.sam@example.com", "phone": null}), json!(["Rust"]), 0),
+    ] {
+        let structured = json!({"contact": contact, "skills": skills, "experience": []});
+        let id = sqlx::query_scalar!(
+            "INSERT INTO resumes (filename, structured, term, created_at) VALUES ('r.pdf', $1, $2, now() - make_interval(days => $3)) RETURNING id",
+            structured,
+            term,
+            age
+        )
+        .fetch_one(&state.pool).await.unwrap();
+        service.resolve_intern(id, &Identity::from_structured(&structured)).await.unwrap();
+        resume_ids.push(id);
+    }
+
+    let rows = sqlx::query!("SELECT id, intern_id FROM resumes WHERE id = ANY($1)", &resume_ids)
+        .fetch_all(&state.pool).await.unwrap();
+    let intern_of = |id: Uuid| rows.iter().find(|r| r.id == id).unwrap().intern_id.unwrap();
+    let sam = intern_of(resume_ids[0]);
+    assert_eq!(intern_of(resume_ids[1]), sam);
+    assert_ne!(intern_of(resume_ids[2]), sam);
+
+    // The newest resume is canonical and the intern picked up the phone number
+    let intern = sqlx::query!("SELECT email, phone, canonical_resume_id FROM interns WHERE id = $1", sam)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(intern.email, Some(email.clone()));
+    assert_eq!(intern.phone.as_deref(), Some("5550102000"));
+    assert_eq!(intern.canonical_resume_id, Some(resume_ids[1]));
+
+    // Matching only sees Sam's newest resume, so the Rust project goes to the other Sam
+    let job_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status, term) VALUES ($1, 'ready', $2)", job_id, term)
+        .execute(&state.pool).await.unwrap();
+    let upload_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO project_uploads (id, filename, status, job_id, term) VALUES ($1, 'p.csv', 'completed', $2, $3)", upload_id, job_id, term)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!(
+        "INSERT INTO projects (upload_id, title, requirements, priority, intern_cap, term) VALUES ($1, 'Rust API', 'Rust, SQL', 1, 2, $2)",
+        upload_id,
+        term
+    )
+    .execute(&state.pool).await.unwrap();
+    MatchingService::new(state.clone()).run_job(job_id).await;
+
+    let matched = sqlx::query_scalar!("SELECT resume_id FROM matches WHERE job_id = $1", job_id)
+        .fetch_all(&state.pool).await.unwrap();
+    assert!(!matched.contains(&Some(resume_ids[0])));
+    assert!(matched.contains(&Some(resume_ids[2])));
+
+    // Cleanup
+    let interns: Vec<Uuid> = rows.iter().filter_map(|r| r.intern_id).collect();
+    sqlx::query!("DELETE FROM matches WHERE job_id = $1", job_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resumes WHERE term = $1", term).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM interns WHERE id = ANY($1)", &interns).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM projects WHERE upload_id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM project_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_name_only_resume_creates_no_intern() {
+    let state = setup_app_state().await;
+    let service = ResumeService::new(state.clone());
+    let name = format!("Sam {}", Uuid::new_v4().simple());
+    let structured = json!({"contact": {"name": name, "email": null, "phone": null}, "skills": [], "experience": []});
+    let id = sqlx::query_scalar!("INSERT INTO resumes (filename, structured) VALUES ('r.pdf', $1) RETURNING id", structured)
+        .fetch_one(&state.pool).await.unwrap();
+
+    // Resolving it again, as re-parsing and the startup backfill do, leaves nothing behind
+    for _ in 0..2 {
+        let intern = service.resolve_intern(id, &Identity::from_structured(&structured)).await.unwrap();
+        assert_eq!(intern, None);
+    }
+    let interns = sqlx::query_scalar!("SELECT count(*) FROM interns WHERE name = $1", name.to_lowercase())
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(interns, Some(0));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM resumes WHERE id = $1", id).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unassigned_resumes_are_attached_by_one_replica() {
+    let state = setup_app_state().await;
+    let structured = json!({"contact": {"name": "Sam Park", "email": format!("{}@example.com", Uuid::new_v4()), "phone": null}, "skills": [], "experience": []});
+    let id = sqlx::query_scalar!("INSERT INTO resumes (filename, structured) VALUES ('r.pdf', $1) RETURNING id", structured)
+        .fetch_one(&state.pool).await.unwrap();
+    let intern_of = || sqlx::query_scalar!("SELECT intern_id FROM resumes WHERE id = $1", id).fetch_one(&state.pool);
+
+    // Nothing happens while another replica holds the lock
+    let mut other = state.pool.acquire().await.unwrap();
+    sqlx::query!("SELECT pg_advisory_lock($1)", BACKFILL_LOCK).execute(&mut *other).await.unwrap();
+    assert_eq!(ResumeService::new(state.clone()).resolve_unassigned().await.unwrap(), 0);
+    assert_eq!(intern_of().await.unwrap(), None);
+    sqlx::query_scalar!("SELECT pg_advisory_unlock($1)", BACKFILL_LOCK).fetch_one(&mut *other).await.unwrap();
+
+    assert!(ResumeService::new(state.clone()).resolve_unassigned().await.unwrap() >= 1);
+    let intern_id = intern_of().await.unwrap().unwrap();
+
+    // Cleanup
+    sqlx::query!("DELETE FROM resumes WHERE id = $1", id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM interns WHERE id = $1", intern_id).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_concurrent_resumes_of_a_new_intern_create_one() {
+    let state = setup_app_state().await;
+    let term = format!("TestTerm-{}", Uuid::new_v4());
+    let structured = json!({"contact": {"name": "Sam Park", "email": format!("{}@example.com", Uuid::new_v4()), "phone": null}, "skills": [], "experience": []});
+    let identity = Identity::from_structured(&structured);
+
+    let mut resume_ids = Vec::new();
+    for _ in 0..4 {
+        let id = sqlx::query_scalar!("INSERT INTO resumes (filename, structured, term) VALUES ('r.pdf', $1, $2) RETURNING id", structured, term)
+            .fetch_one(&state.pool).await.unwrap();
+        resume_ids.push(id);
+    }
+    let mut resolving = tokio::task::JoinSet::new();
+    for id in resume_ids.clone() {
+        let (service, identity) = (ResumeService::new(state.clone()), identity.clone());
+        resolving.spawn(async move { service.resolve_intern(id, &identity).await });
+    }
+    let interns: std::collections::HashSet<Uuid> = resolving.join_all().await.into_iter().map(|r| r.unwrap().unwrap()).collect();
+    assert_eq!(interns.len(), 1);
+
+    // Cleanup
+    let interns: Vec<Uuid> = interns.into_iter().collect();
+    sqlx::query!("DELETE FROM resumes WHERE term = $1", term).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM interns WHERE id = ANY($1)", &interns).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::events::JobEvent;
+
+#[tokio::test]
+async fn test_status_transitions_reach_other_replicas() {
+    let mut state = setup_app_state().await;
+    // Two replicas sharing events through Postgres
+    let publisher = EventBus::with_notify(state.pool.clone());
+    let subscriber = EventBus::with_notify(state.pool.clone());
+    let listener = tokio::spawn(subscriber.clone().listen(state.pool.clone()));
+    let mut receiver = subscriber.subscribe();
+    state.events = publisher;
+    // Give the listener time to LISTEN before anything is published
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let job_id = Uuid::new_v4();
+    let upload_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO project_uploads (id, filename, status, job_id) VALUES ($1, 'p.csv', 'processing', $2)", upload_id, job_id)
+        .execute(&state.pool).await.unwrap();
+
+    let service = ProjectService::new(state.clone());
+    service.update_upload_status(upload_id, DocumentStatus::Failed, Some("Bad sheet".to_string())).await.unwrap();
+    service.maybe_mark_job_as_ready(job_id).await;
+
+    let mut received = Vec::new();
+    while received.len() < 2 {
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), receiver.recv())
+            .await.expect("no event within 5s").unwrap();
+        if event.job_id() == job_id {
+            received.push(event);
+        }
+    }
+    assert_eq!(received[0], JobEvent::ProjectUpload {
+        job_id,
+        id: upload_id,
+        status: DocumentStatus::Failed,
+        error: Some("Bad sheet".to_string()),
+    });
+    // Its only upload failed, so the job fails
+    assert_eq!(received[1], JobEvent::Job { job_id, status: JobStatus::Failed });
+
+    // Cleanup
+    listener.abort();
+    sqlx::query!("DELETE FROM project_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::reprocess::{self, RetryError};
+
+#[tokio::test]
+async fn test_failed_upload_is_retried_in_place() {
+    let mut state = setup_app_state().await;
+    let queue_name = format!("test-{}", Uuid::new_v4());
+    state.queue = TaskQueue::with_name(state.pool.clone(), &queue_name);
+    state.llm = Arc::new(MockLlmProvider::with_json(json!({ "skills": ["Rust"] })));
+    state.resume_schema = resume_schema();
+    Arc::make_mut(&mut state.config).llm.schema_repair = false;
+    let text = format!("Alex Rivera {}\nRust developer\n", Uuid::new_v4());
+    state.storage.put_object("resumes", "retry.txt", text.into_bytes(), None).await.unwrap();
+
+    let job_id = Uuid::new_v4();
+    let upload_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO resume_uploads (id, filename, status, job_id) VALUES ($1, 'retry.txt', 'pending', $2)", upload_id, job_id)
+        .execute(&state.pool).await.unwrap();
+
+    // 1. The first attempt fails schema validation and records an error on the job
+    ResumeService::new(state.clone()).process_resume_upload(upload_id, "retry.txt".to_string()).await;
+    let upload = sqlx::query!("SELECT status as \"status: DocumentStatus\" FROM resume_uploads WHERE id = $1", upload_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(upload.status, Some(DocumentStatus::Failed));
+
+    // 2. Only failed uploads can be retried, and the job's errors are archived when they are
+    let mut events = state.events.subscribe();
+    let retried = reprocess::retry_failed(&state, job_id).await.unwrap();
+    assert_eq!(retried.uploads.len(), 1);
+    let mut published = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        published.push(event);
+    }
+    assert!(published.contains(&JobEvent::ResumeUpload {
+        job_id,
+        id: upload_id,
+        status: DocumentStatus::Pending,
+        error: None,
+    }));
+    assert_eq!(retried.uploads[0].id, upload_id);
+    assert!(!retried.rematch);
+    assert!(matches!(
+        reprocess::retry_upload(&state, TaskKind::Resume, upload_id).await,
+        Err(RetryError::Conflict(_))
+    ));
+    let job = sqlx::query!("SELECT status as \"status: JobStatus\", rust_error FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(job.status, Some(JobStatus::Processing));
+    let rust_error = job.rust_error.unwrap();
+    assert_eq!(rust_error["resumes"], json!([]));
+    assert!(rust_error.get("failure").is_none());
+    let archived = rust_error["archived"].as_array().unwrap();
+    assert_eq!(archived.len(), 2);
+    assert_eq!(archived[0]["id"], upload_id.to_string());
+    assert_eq!(archived[1]["failure"], "Every upload failed");
+
+    // 3. The queued retry succeeds and reuses the upload's resume row
+    state.llm = Arc::new(MockLlmProvider::with_json(valid_structured_resume()));
+    let task = state.queue.claim().await.unwrap().expect("retry should be queued");
+    assert_eq!((task.kind, task.upload_id), (TaskKind::Resume, upload_id));
+    ResumeService::new(state.clone()).process_resume_upload(upload_id, task.filename).await;
+    state.queue.complete(task.id).await.unwrap();
+
+    let resumes = sqlx::query!("SELECT structured FROM resumes WHERE upload_id = $1", upload_id)
+        .fetch_all(&state.pool).await.unwrap();
+    assert_eq!(resumes.len(), 1);
+    assert!(resumes[0].structured.is_some());
+    let job = sqlx::query!("SELECT status as \"status: JobStatus\" FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(job.status, Some(JobStatus::Ready));
+    assert!(matches!(
+        reprocess::retry_failed(&state, job_id).await,
+        Err(RetryError::Conflict(_))
+    ));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM interns WHERE id IN (SELECT intern_id FROM resumes WHERE upload_id = $1)", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resumes WHERE upload_id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM tasks WHERE queue = $1", queue_name).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_concurrent_retries_queue_an_upload_once() {
+    let mut state = setup_app_state().await;
+    let queue_name = format!("test-{}", Uuid::new_v4());
+    state.queue = TaskQueue::with_name(state.pool.clone(), &queue_name);
+    let job_id = Uuid::new_v4();
+    let upload_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'failed')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO resume_uploads (id, filename, status, job_id) VALUES ($1, 'twice.txt', 'failed', $2)", upload_id, job_id)
+        .execute(&state.pool).await.unwrap();
+
+    let (first, second) = tokio::join!(
+        reprocess::retry_upload(&state, TaskKind::Resume, upload_id),
+        reprocess::retry_upload(&state, TaskKind::Resume, upload_id)
+    );
+    assert!(first.is_ok() != second.is_ok(), "exactly one retry should win");
+    assert!(matches!(first.err().or(second.err()), Some(RetryError::Conflict(_))));
+    let queued = sqlx::query_scalar!("SELECT count(*) FROM tasks WHERE queue = $1", queue_name)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(queued, Some(1));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM tasks WHERE queue = $1", queue_name).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::cancel;
+use matchmaker_orchestrator::llm::{LlmProvider, Message};
+use tokio::sync::Notify;
+
+/// Never answers, like an OpenAI request that is still in flight; announces each call.
+#[derive(Default)]
+struct HangingLlm {
+    called: Notify,
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for HangingLlm {
+    fn name(&self) -> &str {
+        "hanging"
+    }
+
+    async fn complete_json(&self, _messages: &[Message], _schema: &serde_json::Value) -> anyhow::Result<String> {
+        self.called.notify_one();
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn test_cancelled_job_stops_in_flight_work() {
+    let mut state = setup_app_state().await;
+    let llm = Arc::new(HangingLlm::default());
+    state.llm = llm.clone();
+    let text = format!("Sam Ortiz {}\nData engineer\n", Uuid::new_v4());
+    state.storage.put_object("resumes", "cancel.txt", text.into_bytes(), None).await.unwrap();
+
+    let job_id = Uuid::new_v4();
+    let uploads = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'pending')", job_id)
+        .execute(&state.pool).await.unwrap();
+    for upload_id in &uploads {
+        sqlx::query!("INSERT INTO resume_uploads (id, filename, status, job_id) VALUES ($1, 'cancel.txt', 'pending', $2)", upload_id, job_id)
+            .execute(&state.pool).await.unwrap();
+    }
+
+    // 1. Cancelling stops the upload that is waiting on the LLM
+    let service = ResumeService::new(state.clone());
+    let upload_id = uploads[0];
+    let running = tokio::spawn(async move { service.process_resume_upload(upload_id, "cancel.txt".to_string()).await });
+    llm.called.notified().await;
+    let cancelled = cancel::cancel_job(&state, job_id).await.unwrap();
+    assert_eq!(cancelled.resumes, 3);
+    tokio::time::timeout(std::time::Duration::from_secs(5), running)
+        .await
+        .expect("in-flight upload should stop")
+        .unwrap();
+
+    // 2. Uploads of the cancelled job that start later are skipped
+    ResumeService::new(state.clone()).process_resume_upload(uploads[1], "cancel.txt".to_string()).await;
+    let statuses = sqlx::query_scalar!("SELECT status as \"status: DocumentStatus\" FROM resume_uploads WHERE job_id = $1", job_id)
+        .fetch_all(&state.pool).await.unwrap();
+    assert!(statuses.iter().all(|s| *s == Some(DocumentStatus::Cancelled)), "{:?}", statuses);
+    let parsed = sqlx::query_scalar!("SELECT count(*) FROM resumes WHERE upload_id = ANY($1) AND structured IS NOT NULL", &uploads[..])
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(parsed, Some(0));
+
+    // 3. The job stays cancelled and can't be cancelled or retried again
+    ProjectService::new(state.clone()).maybe_mark_job_as_ready(job_id).await;
+    let job = sqlx::query!("SELECT status as \"status: JobStatus\" FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(job.status, Some(JobStatus::Cancelled));
+    assert!(matches!(cancel::cancel_job(&state, job_id).await, Err(cancel::CancelError::Conflict(JobStatus::Cancelled))));
+    assert!(matches!(reprocess::retry_failed(&state, job_id).await, Err(RetryError::Conflict(_))));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM resumes WHERE upload_id = ANY($1)", &uploads[..]).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE job_id = $1", job_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::job_state;
+
+#[tokio::test]
+async fn test_failed_zip_download_settles_the_job() {
+    let mut state = setup_app_state().await;
+    Arc::make_mut(&mut state.config).retry.max_attempts = 1;
+    let job_id = Uuid::new_v4();
+    let zip_id = Uuid::new_v4();
+    let upload_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO zip_archives (id, filename, status, job_id) VALUES ($1, 'missing.zip', 'pending', $2)", zip_id, job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO project_uploads (id, filename, status, job_id) VALUES ($1, 'p.csv', 'completed', $2)", upload_id, job_id)
+        .execute(&state.pool).await.unwrap();
+
+    // 1. The archive can't be downloaded; the job no longer hangs in processing
+    ResumeService::new(state.clone()).handle_batch_extraction(zip_id, "missing.zip".to_string()).await;
+    let job = sqlx::query!("SELECT status as \"status: JobStatus\", rust_error FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(job.status, Some(JobStatus::Ready));
+    let errors = job.rust_error.unwrap()["resumes"].as_array().unwrap().clone();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["id"], zip_id.to_string());
+
+    // 2. Requiring resumes fails a job whose only archive failed, and says why
+    Arc::make_mut(&mut state.config).jobs.require_resumes = true;
+    job_state::transition_job(&state, job_id, None, JobStatus::Processing).await.unwrap();
+    job_state::settle(&state, job_id).await;
+    let job = sqlx::query!("SELECT status as \"status: JobStatus\", rust_error FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(job.status, Some(JobStatus::Failed));
+    assert_eq!(job.rust_error.unwrap()["failure"], "No resumes were parsed");
+
+    // 3. Matching can't start from failed, and nothing moves back to pending
+    assert!(!job_state::transition_job(&state, job_id, None, JobStatus::Matching).await.unwrap());
+    assert!(!job_state::transition_job(&state, job_id, None, JobStatus::Pending).await.unwrap());
+
+    // Cleanup
+    sqlx::query!("DELETE FROM zip_archives WHERE id = $1", zip_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM project_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::reaper::{self, ReaperConfig, StaleAction};
+
+#[tokio::test]
+async fn test_reaper_recovers_uploads_stuck_in_processing() {
+    let mut state = setup_app_state().await;
+    let queue_name = format!("test-{}", Uuid::new_v4());
+    state.queue = TaskQueue::with_name(state.pool.clone(), &queue_name);
+    let job_id = Uuid::new_v4();
+    let resume_id = Uuid::new_v4();
+    let zip_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'processing')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!(
+        "INSERT INTO resume_uploads (id, filename, status, job_id, started_at, updated_at) VALUES ($1, 'stuck.pdf', 'processing', $2, now() - interval '2 hours', now() - interval '2 hours')",
+        resume_id, job_id
+    ).execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO zip_archives (id, filename, status, job_id, started_at) VALUES ($1, 'busy.zip', 'processing', $2, now())", zip_id, job_id)
+        .execute(&state.pool).await.unwrap();
+    let mut config = ReaperConfig { timeout: std::time::Duration::from_secs(3600), ..ReaperConfig::default() };
+
+    // 1. The stale resume goes back on the queue; the archive that is still making progress is left alone
+    let reaped = reaper::reap_stale(&state, &config).await.unwrap();
+    assert!(reaped.iter().any(|upload| upload.id == resume_id && upload.requeued));
+    assert!(!reaped.iter().any(|upload| upload.id == zip_id));
+    let status = sqlx::query_scalar!("SELECT status as \"status: DocumentStatus\" FROM resume_uploads WHERE id = $1", resume_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(status, Some(DocumentStatus::Pending));
+    let task = state.queue.claim().await.unwrap().expect("stale resume should be queued again");
+    assert_eq!(task.upload_id, resume_id);
+    state.queue.complete(task.id).await.unwrap();
+
+    // 2. Failing instead records why, and the job settles once nothing is left running
+    sqlx::query!("UPDATE resume_uploads SET status = 'completed' WHERE id = $1", resume_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("UPDATE zip_archives SET updated_at = now() - interval '2 hours' WHERE id = $1", zip_id)
+        .execute(&state.pool).await.unwrap();
+    config.action = StaleAction::Fail;
+    let reaped = reaper::reap_stale(&state, &config).await.unwrap();
+    assert!(reaped.iter().any(|upload| upload.id == zip_id && !upload.requeued));
+    let zip = sqlx::query!("SELECT status as \"status: DocumentStatus\", error_message FROM zip_archives WHERE id = $1", zip_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(zip.status, Some(DocumentStatus::Failed));
+    assert!(zip.error_message.unwrap().contains("still processing after 3600 seconds"));
+    let job = sqlx::query!("SELECT status as \"status: JobStatus\", rust_error FROM jobs WHERE id = $1", job_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(job.status, Some(JobStatus::Ready));
+    assert_eq!(job.rust_error.unwrap()["resumes"][0]["id"], zip_id.to_string());
+    assert!(state.queue.claim().await.unwrap().is_none());
+
+    // Cleanup
+    sqlx::query!("DELETE FROM tasks WHERE queue = $1", queue_name).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE id = $1", resume_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM zip_archives WHERE id = $1", zip_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
+
+use matchmaker_orchestrator::queue;
+use matchmaker_orchestrator::shutdown::INTERRUPTED_ERROR;
+
+#[tokio::test]
+async fn test_shutdown_hands_interrupted_work_back_to_the_queue() {
+    let mut state = setup_app_state().await;
+    let llm = Arc::new(HangingLlm::default());
+    state.llm = llm.clone();
+    let queue_name = format!("test-{}", Uuid::new_v4());
+    state.queue = TaskQueue::with_name(state.pool.clone(), &queue_name);
+    let text = format!("Ana Lima {}\nBackend developer\n", Uuid::new_v4());
+    state.storage.put_object("resumes", "shutdown.txt", text.into_bytes(), None).await.unwrap();
+
+    let job_id = Uuid::new_v4();
+    let upload_id = Uuid::new_v4();
+    sqlx::query!("INSERT INTO jobs (id, status) VALUES ($1, 'pending')", job_id)
+        .execute(&state.pool).await.unwrap();
+    sqlx::query!("INSERT INTO resume_uploads (id, filename, status, job_id) VALUES ($1, 'shutdown.txt', 'pending', $2)", upload_id, job_id)
+        .execute(&state.pool).await.unwrap();
+    state.queue.enqueue(TaskKind::Resume, upload_id, "shutdown.txt").await.unwrap();
+
+    // 1. A worker picks the upload up and waits on the LLM
+    queue::spawn_workers(state.clone(), 1);
+    llm.called.notified().await;
+
+    // 2. The drain deadline passes, so the upload is interrupted and handed back
+    let drained = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        state.shutdown.drain(&state.semaphore, 1, std::time::Duration::from_millis(100)),
+    )
+    .await
+    .expect("drain should respect its deadline");
+    assert!(!drained);
+    let upload = sqlx::query!("SELECT status as \"status: DocumentStatus\", error_message FROM resume_uploads WHERE id = $1", upload_id)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(upload.status, Some(DocumentStatus::Pending));
+    assert_eq!(upload.error_message.as_deref(), Some(INTERRUPTED_ERROR));
+    let task = sqlx::query!("SELECT status as \"status: TaskStatus\", attempts, last_error FROM tasks WHERE queue = $1", queue_name)
+        .fetch_one(&state.pool).await.unwrap();
+    assert_eq!(task.status, TaskStatus::Queued);
+    assert_eq!(task.attempts, 0);
+    assert_eq!(task.last_error.as_deref(), Some(INTERRUPTED_ERROR));
+
+    // Cleanup
+    sqlx::query!("DELETE FROM tasks WHERE queue = $1", queue_name).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resumes WHERE upload_id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM resume_uploads WHERE id = $1", upload_id).execute(&state.pool).await.unwrap();
+    sqlx::query!("DELETE FROM jobs WHERE id = $1", job_id).execute(&state.pool).await.unwrap();
+}
@@ -1,8 +1,10 @@
 -- CI Schema Setup
 
-CREATE TYPE document_status AS ENUM ('pending', 'processing', 'completed', 'failed');
-CREATE TYPE job_status AS ENUM ('pending', 'processing', 'ready', 'matching', 'completed', 'failed');
+-- 'partial' is only used by zip_archives: some members could not be processed
+CREATE TYPE document_status AS ENUM ('pending', 'processing', 'completed', 'partial', 'failed', 'cancelled');
+CREATE TYPE job_status AS ENUM ('pending', 'processing', 'ready', 'matching', 'completed', 'failed', 'cancelled');
 CREATE TYPE match_decision AS ENUM ('rejected', 'accepted', 'pending');
+CREATE TYPE extraction_method AS ENUM ('text', 'ocr');
 
 CREATE TABLE project_uploads (
     id uuid PRIMARY KEY,
@@ -12,7 +14,10 @@ CREATE TABLE project_uploads (
     user_id uuid,
     job_id uuid,
     term text,
-    created_at timestamp with time zone DEFAULT now()
+    created_at timestamp with time zone DEFAULT now(),
+    -- When processing last started, and when the status (or, for resumes, the attempt count) last changed
+    started_at timestamp with time zone,
+    updated_at timestamp with time zone DEFAULT now()
 );
 
 CREATE TABLE projects (
@@ -27,6 +32,10 @@ CREATE TABLE projects (
     intern_cap smallint DEFAULT 1,
     term text,
     deliverable text,
+    embedding real[],
+    embedding_model text,
+    embedding_hash text,
+    embedded_at timestamp with time zone,
     created_at timestamp with time zone DEFAULT now()
 );
 
@@ -38,7 +47,10 @@ CREATE TABLE zip_archives (
     user_id uuid,
     job_id uuid,
     term text,
-    created_at timestamp with time zone DEFAULT now()
+    created_at timestamp with time zone DEFAULT now(),
+    -- When processing last started, and when the status (or, for resumes, the attempt count) last changed
+    started_at timestamp with time zone,
+    updated_at timestamp with time zone DEFAULT now()
 );
 
 CREATE TABLE resume_uploads (
@@ -50,26 +62,121 @@ CREATE TABLE resume_uploads (
     job_id uuid,
     zip_id uuid REFERENCES zip_archives(id),
     term text,
-    created_at timestamp with time zone DEFAULT now()
+    attempts integer NOT NULL DEFAULT 0,
+    created_at timestamp with time zone DEFAULT now(),
+    -- When processing last started, and when the status (or, for resumes, the attempt count) last changed
+    started_at timestamp with time zone,
+    updated_at timestamp with time zone DEFAULT now()
+);
+
+CREATE TYPE zip_member_status AS ENUM ('pending', 'uploaded', 'skipped', 'failed');
+
+-- One row per file found in a zip archive, so dropped resumes are visible
+CREATE TABLE zip_members (
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    zip_id uuid NOT NULL REFERENCES zip_archives(id) ON DELETE CASCADE,
+    name text NOT NULL,
+    size bigint,
+    -- Key the member was re-uploaded to in the resumes bucket
+    object_key text,
+    status zip_member_status NOT NULL DEFAULT 'pending',
+    resume_upload_id uuid REFERENCES resume_uploads(id) ON DELETE SET NULL,
+    error_message text,
+    created_at timestamp with time zone DEFAULT now(),
+    UNIQUE (zip_id, name)
+);
+
+-- One row per person; an intern's resumes point here through resumes.intern_id
+CREATE TABLE interns (
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Normalized identity taken from the resumes' contact details
+    email text,
+    phone text,
+    name text,
+    -- The intern's most recent resume
+    canonical_resume_id uuid,
+    created_at timestamp with time zone DEFAULT now(),
+    updated_at timestamp with time zone DEFAULT now()
 );
 
+CREATE INDEX interns_email_idx ON interns (email);
+CREATE INDEX interns_phone_idx ON interns (phone);
+CREATE INDEX interns_name_idx ON interns (name);
+
 CREATE TABLE resumes (
     id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
     upload_id uuid REFERENCES resume_uploads(id),
     zip_id uuid REFERENCES zip_archives(id),
     filename text NOT NULL,
     text text,
+    extraction_method extraction_method,
     structured json,
+    -- Version of resume_schema.json that produced `structured`; NULL means version 1
+    schema_version integer,
     user_id uuid,
     term text,
+    embedding real[],
+    embedding_model text,
+    embedding_hash text,
+    embedded_at timestamp with time zone,
+    -- SHA-256 of the uploaded file and of its normalized text, for duplicate detection
+    file_hash text,
+    text_hash text,
+    -- The resume this one was identified as a duplicate of
+    duplicate_of uuid REFERENCES resumes(id) ON DELETE SET NULL,
+    -- The person this resume belongs to, see src/identity.rs
+    intern_id uuid REFERENCES interns(id) ON DELETE SET NULL,
     created_at timestamp with time zone DEFAULT now()
 );
 
+CREATE INDEX resumes_file_hash_idx ON resumes (file_hash);
+CREATE INDEX resumes_text_hash_idx ON resumes (text_hash);
+CREATE INDEX resumes_intern_id_idx ON resumes (intern_id);
+
+ALTER TABLE interns ADD FOREIGN KEY (canonical_resume_id) REFERENCES resumes(id) ON DELETE SET NULL;
+
 CREATE TABLE jobs (
     id uuid PRIMARY KEY,
     term text,
     status job_status DEFAULT 'pending',
     rust_error jsonb DEFAULT '{"resumes": [], "projects": []}'::jsonb,
     python_error text,
+    created_at timestamp with time zone DEFAULT now(),
+    -- Last status change
+    updated_at timestamp with time zone DEFAULT now()
+);
+
+CREATE TABLE matches (
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    job_id uuid REFERENCES jobs(id),
+    resume_id uuid REFERENCES resumes(id),
+    project_id uuid REFERENCES projects(id),
+    score real NOT NULL,
+    decision match_decision DEFAULT 'pending',
     created_at timestamp with time zone DEFAULT now()
 );
+
+CREATE TYPE task_kind AS ENUM ('resume', 'zip', 'project');
+CREATE TYPE task_status AS ENUM ('queued', 'running', 'completed', 'failed');
+
+CREATE TABLE tasks (
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    queue text NOT NULL DEFAULT 'default',
+    kind task_kind NOT NULL,
+    upload_id uuid NOT NULL,
+    filename text NOT NULL,
+    status task_status NOT NULL DEFAULT 'queued',
+    attempts integer NOT NULL DEFAULT 0,
+    locked_until timestamp with time zone,
+    last_error text,
+    created_at timestamp with time zone DEFAULT now(),
+    updated_at timestamp with time zone DEFAULT now()
+);
+
+CREATE UNIQUE INDEX tasks_active_upload ON tasks (kind, upload_id) WHERE status IN ('queued', 'running');
+CREATE INDEX tasks_claim ON tasks (queue, status, created_at);
+
+-- Uploads the stale-upload reaper looks at
+CREATE INDEX resume_uploads_processing_idx ON resume_uploads (updated_at) WHERE status = 'processing';
+CREATE INDEX zip_archives_processing_idx ON zip_archives (updated_at) WHERE status = 'processing';
+CREATE INDEX project_uploads_processing_idx ON project_uploads (updated_at) WHERE status = 'processing';